@@ -0,0 +1,41 @@
+use std::{env, fs, path::Path, process::Command};
+
+/// Writes `build_info.rs` into `OUT_DIR` with what `src/build_info.rs` can't know
+/// by itself: the git commit being built and the enabled cargo features.
+fn main() {
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_owned())
+        .unwrap_or_else(|| "unknown".to_owned());
+
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            let feature = key.strip_prefix("CARGO_FEATURE_")?;
+            Some(feature.to_lowercase().replace('_', "-"))
+        })
+        .collect::<Vec<_>>();
+    features.sort();
+
+    let out_dir = env::var("OUT_DIR").expect("cargo sets OUT_DIR");
+    fs::write(
+        Path::new(&out_dir).join("build_info.rs"),
+        format!(
+            "pub const GIT_HASH: &str = {:?};\npub const FEATURES: &[&str] = &{:?};\n",
+            git_hash, features
+        ),
+    )
+    .expect("failed to write build_info.rs");
+
+    // Rebuild when a commit is made or another branch is checked out.
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    if let Ok(head) = fs::read_to_string(".git/HEAD") {
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            println!("cargo:rerun-if-changed=.git/{}", reference);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
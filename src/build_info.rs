@@ -0,0 +1,30 @@
+//! What exactly was built, for `--version`, the About window, and bug reports.
+
+include!(concat!(env!("OUT_DIR"), "/build_info.rs"));
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Bumped whenever the surface syntax accepted by the parser changes:
+///
+/// 2. `type T = dual U`
+/// 3. String literals
+/// 4. `{name}` in string literals
+/// 5. List literals
+/// 6. Parameters after a definition's name
+/// 7. Holes, `?` and `?name`
+/// 8. `true`, `false`, and `if ... then ... else`
+/// 9. Number literals, and `+`, `-`, and `*`
+pub const GRAMMAR_VERSION: u32 = 9;
+
+/// Everything above, in the form printed by `--version`.
+pub fn report() -> String {
+    let features = if FEATURES.is_empty() {
+        "none".to_owned()
+    } else {
+        FEATURES.join(", ")
+    };
+    format!(
+        "{} ({})\ngrammar version {}\nfeatures: {}",
+        VERSION, GIT_HASH, GRAMMAR_VERSION, features
+    )
+}
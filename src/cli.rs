@@ -0,0 +1,1167 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{channel::mpsc, StreamExt};
+
+use crate::{
+    compiled::{Compiled, Error},
+    config::{Config, Verbosity},
+    events::{EventBus, RuntimeEvent},
+    interact::{Event, Handle, Request},
+    par::{
+        frontend::Frontend,
+        language::{Expression, Internal},
+        lexer::quote,
+        lint,
+        parse::{Loc, Name, Program},
+        runtime::Context,
+        suggest,
+        types::{Type, TypeDefs},
+        value::{parse_value, Value},
+    },
+    spawn::{self, Activity, TokioSpawn},
+    transcript::Transcript,
+};
+
+/// How long reading back waits for something new to show before checking whether the
+/// run can still make any progress at all.
+const IDLE_CHECK: Duration = Duration::from_millis(250);
+
+/// How many values may be nested inside each other before a result is too deep to show.
+const MAX_DEPTH: usize = 1000;
+
+pub(crate) type RunHandle = Arc<Mutex<Handle<Loc, Internal<Name>, ()>>>;
+
+/// Runs `definition` from the program in `file` without the playground, applied to
+/// `arguments` written in the textual value syntax, and prints what it produces.
+pub async fn run(
+    frontend: &dyn Frontend,
+    file: &Path,
+    definition: &str,
+    arguments: &[String],
+    config: &Config,
+) -> Result<(), String> {
+    let code = read_source(file)?;
+    let output = evaluate(
+        frontend,
+        code,
+        definition,
+        arguments,
+        config.verbosity(),
+        config.max_steps,
+    )
+    .await?;
+    print!("{}", output);
+    Ok(())
+}
+
+/// Type checks the program in `file` without running anything, warning about the
+/// definitions that `definition` never uses.
+pub fn check(
+    frontend: &dyn Frontend,
+    file: &Path,
+    definition: &str,
+    config: &Config,
+) -> Result<(), String> {
+    let code = read_source(file)?;
+    let definitions = check_code(frontend, code, definition, config.verbosity())?;
+    if config.verbosity() >= Verbosity::Normal {
+        println!("{}", checked_message(definitions));
+    }
+    Ok(())
+}
+
+/// What `par check` says about a program with `definitions` definitions that type checks.
+pub(crate) fn checked_message(definitions: usize) -> String {
+    format!(
+        "{} definition{} type check.",
+        definitions,
+        if definitions == 1 { "" } else { "s" }
+    )
+}
+
+/// Parses, lints and type checks `code`, run from `definition`, and returns how many
+/// definitions it has.
+fn check_code(
+    frontend: &dyn Frontend,
+    code: Arc<str>,
+    definition: &str,
+    verbosity: Verbosity,
+) -> Result<usize, String> {
+    let compiled =
+        Compiled::from_source(frontend, &code).map_err(|error| error.display(code.clone()))?;
+    if verbosity >= Verbosity::Normal {
+        print_diagnostics(&compiled, &code, definition);
+    }
+    match compiled.checked {
+        Ok(_) => Ok(compiled.program.definitions.len()),
+        Err(error) => Err(Error::Type(error).display(code)),
+    }
+}
+
+/// Prints the program's warnings, run from `definition`, and, if it type checks, what
+/// each of its holes needs.
+fn print_diagnostics(compiled: &Compiled, code: &Arc<str>, definition: &str) {
+    let warnings = compiled.warnings_running(Some(definition));
+    for report in lint::reports(&warnings, code.clone()) {
+        eprintln!("{:?}", report);
+    }
+    if let Ok(checked) = &compiled.checked {
+        for hole in &checked.holes {
+            eprintln!("{:?}", hole.to_report(code.clone()));
+        }
+    }
+}
+
+fn read_source(file: &Path) -> Result<Arc<str>, String> {
+    fs::read_to_string(file)
+        .map(Arc::from)
+        .map_err(|error| format!("Could not read {}: {}", file.display(), error))
+}
+
+/// Runs `definition` from `code` and returns its result. Plain data is written in the
+/// value syntax; anything else, such as a value still waiting for input, is shown as
+/// the tree of events the playground would display.
+pub(crate) async fn evaluate(
+    frontend: &dyn Frontend,
+    code: Arc<str>,
+    definition: &str,
+    arguments: &[String],
+    verbosity: Verbosity,
+    max_steps: Option<usize>,
+) -> Result<String, String> {
+    let (handle, mut events, activity) = launch(
+        frontend,
+        code.clone(),
+        definition,
+        arguments,
+        verbosity,
+        max_steps,
+    )
+    .await?;
+    settle(&handle, &mut events, &activity).await;
+
+    let result = output(&code, &handle);
+    handle.lock().expect("lock failed").cancel();
+    result
+}
+
+/// What `par run` prints for the value as it is now: plain data in the value syntax, or
+/// else the tree of events, which is an error if any part of it failed.
+pub(crate) fn output(code: &Arc<str>, handle: &RunHandle) -> Result<String, String> {
+    match readback(handle) {
+        Err(error) => Err(error.to_string()),
+        Ok(Some(value)) => Ok(format!("{}\n", value)),
+        Ok(None) => {
+            let mut output = String::new();
+            let failed = show(&mut output, code, handle);
+            if failed {
+                Err(output)
+            } else {
+                Ok(output)
+            }
+        }
+    }
+}
+
+/// Runs the definition a transcript recorded in the playground starts with, applied to
+/// the same arguments, makes the same choices, and checks that everything looks as it did.
+pub async fn replay(
+    frontend: &dyn Frontend,
+    file: &Path,
+    transcript: &Path,
+    config: &Config,
+) -> Result<(), String> {
+    let code = read_source(file)?;
+    let transcript = fs::read_to_string(transcript)
+        .map_err(|error| format!("Could not read {}: {}", transcript.display(), error))
+        .and_then(|text| {
+            serde_json::from_str::<Transcript>(&text)
+                .map_err(|error| format!("{}: {}", transcript.display(), error))
+        })?;
+    check_transcript(
+        frontend,
+        code,
+        &transcript,
+        config.verbosity(),
+        config.max_steps,
+    )
+    .await?;
+    if config.verbosity() >= Verbosity::Normal {
+        println!("Replayed {} steps.", transcript.steps.len());
+    }
+    Ok(())
+}
+
+async fn check_transcript(
+    frontend: &dyn Frontend,
+    code: Arc<str>,
+    transcript: &Transcript,
+    verbosity: Verbosity,
+    max_steps: Option<usize>,
+) -> Result<(), String> {
+    let (handle, mut events, activity) = launch(
+        frontend,
+        code.clone(),
+        &transcript.definition,
+        &transcript.arguments,
+        verbosity,
+        max_steps,
+    )
+    .await?;
+    let result = async {
+        for (number, step) in transcript.steps.iter().enumerate() {
+            settle(&handle, &mut events, &activity).await;
+            let seen = observe(&code, &handle);
+            if seen != step.seen {
+                return Err(mismatch(
+                    &format!("Before step {}", number + 1),
+                    &step.seen,
+                    &seen,
+                ));
+            }
+            let answered = descend(&handle, &step.path).and_then(|chosen| {
+                let request = chosen.lock().expect("lock failed").interaction();
+                match request?.ok()? {
+                    Request::Either(loc, choices) => {
+                        let choice = choices
+                            .iter()
+                            .find(|choice| choice.to_string() == step.choice)?;
+                        Handle::choose(chosen, loc, choice.clone());
+                    }
+                    Request::String(loc) => {
+                        let Ok(Value::String(text)) = parse_value(&step.choice) else {
+                            return None;
+                        };
+                        Handle::send_string(chosen, loc, Arc::from(text));
+                    }
+                    Request::Int(loc, asked) => {
+                        let Ok(Value::Int(number)) = parse_value(&step.choice) else {
+                            return None;
+                        };
+                        if !asked.contains(number) {
+                            return None;
+                        }
+                        Handle::send_int(chosen, loc, number);
+                    }
+                    Request::Dynamic(_) => return None,
+                }
+                Some(())
+            });
+            if answered.is_none() {
+                return Err(format!(
+                    "Step {}: nothing at {:?} is waiting for `{}`.",
+                    number + 1,
+                    step.path,
+                    step.choice
+                ));
+            }
+        }
+        settle(&handle, &mut events, &activity).await;
+        let seen = observe(&code, &handle);
+        if seen != transcript.result {
+            return Err(mismatch("After the last step", &transcript.result, &seen));
+        }
+        Ok(())
+    }
+    .await;
+    handle.lock().expect("lock failed").cancel();
+    result
+}
+
+fn mismatch(when: &str, expected: &str, seen: &str) -> String {
+    format!(
+        "{}, expected to see:\n{}but saw:\n{}",
+        when,
+        indent(expected),
+        indent(seen).trim_end()
+    )
+}
+
+fn indent(text: &str) -> String {
+    text.lines().map(|line| format!("    {}\n", line)).collect()
+}
+
+/// Compiles `code` and starts running `definition` applied to `arguments`.
+async fn launch(
+    frontend: &dyn Frontend,
+    code: Arc<str>,
+    definition: &str,
+    arguments: &[String],
+    verbosity: Verbosity,
+    max_steps: Option<usize>,
+) -> Result<
+    (
+        RunHandle,
+        mpsc::UnboundedReceiver<RuntimeEvent>,
+        Arc<Activity>,
+    ),
+    String,
+> {
+    let (mut program, errors) = frontend.parse(&code);
+    if !errors.is_empty() {
+        return Err(Error::recovered(errors, program).display(code));
+    }
+    let entry = entry(&mut program, definition, arguments)?;
+
+    let compiled = spawn::blocking(move || Compiled::from_parsed(program))
+        .await
+        .map_err(|error| error.display(code.clone()))?;
+    if verbosity >= Verbosity::Normal {
+        print_diagnostics(&compiled, &code, definition);
+    }
+    if let Err(error) = compiled.checked {
+        return Err(Error::Type(error).display(code));
+    }
+
+    let globals = compiled.definitions();
+    let naturals = compiled.naturals();
+    let expression = Arc::clone(&globals[&Internal::Original(entry)]);
+
+    let bus = EventBus::default();
+    let events = bus.subscribe();
+    let spawner = TokioSpawn::new(bus.clone());
+    let activity = spawner.activity();
+    let handle = Handle::start_expression(
+        bus,
+        Context::new(Arc::new(spawner), globals)
+            .with_max_steps(max_steps)
+            .with_naturals(naturals),
+        &expression,
+    );
+    Ok((handle, events, activity))
+}
+
+/// Names the definition that runs `definition` applied to `arguments`. That's
+/// `definition` itself when there are no arguments, or else one added to `program`.
+pub(crate) fn entry(
+    program: &mut Program<Loc, Name, Expression<Loc, Name>>,
+    definition: &str,
+    arguments: &[String],
+) -> Result<Name, String> {
+    let name = Name::from(definition.to_owned());
+    if !program
+        .definitions
+        .iter()
+        .any(|(_, defined, _)| defined == &name)
+    {
+        let defined = (program.definitions.iter()).map(|(_, defined, _)| defined.string.as_str());
+        return Err(match suggest::closest(definition, defined) {
+            Some(closest) => format!("`{}` is not defined. Did you mean `{}`?", name, closest),
+            None => format!("`{}` is not defined.", name),
+        });
+    }
+
+    // A function left waiting for an argument would only show up as stuck, so when the
+    // declaration says more are needed, say so right away. An annotation on the
+    // definition itself is one of the declarations too.
+    program.add_builtin_types();
+    let declared = (program.declarations.iter()).find(|(_, declared, _)| declared == &name);
+    // Type definitions that don't check are reported once the program is compiled.
+    let type_defs = TypeDefs::new_with_validation(&program.type_defs);
+    if let (Some((_, _, typ)), Ok(type_defs)) = (declared, type_defs) {
+        let takes = arity(typ, &type_defs);
+        if takes > arguments.len() {
+            return Err(format!(
+                "`{}` takes {} argument{}, but {} {} given. Pass each one with `--arg`.",
+                name,
+                takes,
+                if takes == 1 { "" } else { "s" },
+                arguments.len(),
+                if arguments.len() == 1 { "was" } else { "were" },
+            ));
+        }
+    }
+
+    if arguments.is_empty() {
+        return Ok(name);
+    }
+    let arguments = arguments
+        .iter()
+        .map(|argument| {
+            parse_value(argument).map_err(|error| {
+                Error::Parse(vec![error], None).display(Arc::from(argument.as_str()))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // `#` can't appear in source names, so this never clashes with a definition.
+    let entry = Name::from(format!("{}#run", name));
+    program.definitions.push((
+        Loc::External,
+        entry.clone(),
+        Value::apply_to(name, &arguments),
+    ));
+    Ok(entry)
+}
+
+/// How many values a function of type `typ` receives before it produces its result,
+/// looking through the types named along the way. Those are checked not to refer back
+/// to themselves, so this always ends.
+fn arity(typ: &Type<Loc, Name>, type_defs: &TypeDefs<Loc, Name>) -> usize {
+    match typ {
+        Type::Receive(_, _, then) => 1 + arity(then, type_defs),
+        Type::Name(loc, name, args) => {
+            (type_defs.get(loc, name, args)).map_or(0, |named| arity(&named, type_defs))
+        }
+        _ => 0,
+    }
+}
+
+/// Waits until the value has [`settled`], or until no process of the run has done
+/// anything for a whole [`IDLE_CHECK`], in which case nothing more can ever arrive and
+/// whatever hasn't is shown as stuck. A run that is only slow is waited for.
+async fn settle(
+    handle: &RunHandle,
+    events: &mut mpsc::UnboundedReceiver<RuntimeEvent>,
+    activity: &Activity,
+) {
+    while !settled(handle) {
+        let mark = activity.mark();
+        match tokio::time::timeout(IDLE_CHECK, events.next()).await {
+            Ok(Some(_)) => {}
+            Ok(None) => break,
+            Err(_) if activity.idle_since(mark) => break,
+            Err(_) => {}
+        }
+    }
+}
+
+/// What the value looks like so far, shown as by `par run`.
+pub(crate) fn observe(code: &Arc<str>, handle: &RunHandle) -> String {
+    let mut output = String::new();
+    show(&mut output, code, handle);
+    output
+}
+
+/// The value sent or received at `path`, as described by [`Step::path`].
+///
+/// [`Step::path`]: crate::transcript::Step::path
+fn descend(handle: &RunHandle, path: &[usize]) -> Option<RunHandle> {
+    let mut handle = Arc::clone(handle);
+    for &index in path {
+        let next = match handle.lock().expect("lock failed").events().get(index)? {
+            Event::Send(_, inner) | Event::Receive(_, inner) => Arc::clone(inner),
+            _ => return None,
+        };
+        handle = next;
+    }
+    Some(handle)
+}
+
+/// A result with values nested more than [`MAX_DEPTH`] levels deep.
+#[derive(Debug, PartialEq, Eq)]
+struct TooDeep;
+
+impl std::fmt::Display for TooDeep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The result has values nested more than {} levels deep, too deep to read back.",
+            MAX_DEPTH
+        )
+    }
+}
+
+/// A value being read back, with the events of its handle not yet read.
+struct Frame {
+    handle: RunHandle,
+    unread: usize,
+    value: Value,
+}
+
+impl Frame {
+    /// Starts reading back `handle` from its end, if it ended as plain data does.
+    fn start(handle: &RunHandle) -> Option<Self> {
+        let locked = handle.lock().expect("lock failed");
+        if locked.interaction().is_some() {
+            return None;
+        }
+        let (last, rest) = locked.events().split_last()?;
+        let value = match last {
+            Event::Break(_) => Value::Break,
+            Event::String(_, text) => Value::String(text.to_string()),
+            Event::Int(_, number) => Value::Int(*number),
+            _ => return None,
+        };
+        Some(Self {
+            handle: Arc::clone(handle),
+            unread: rest.len(),
+            value,
+        })
+    }
+}
+
+/// Reads the result back as a [`Value`], if it is plain data that has fully arrived.
+///
+/// Values sent inside it are read with a stack of their own rather than by recursion,
+/// so a deeply nested result is reported instead of overflowing the stack.
+fn readback(handle: &RunHandle) -> Result<Option<Value>, TooDeep> {
+    let Some(frame) = Frame::start(handle) else {
+        return Ok(None);
+    };
+    let mut stack = vec![frame];
+    loop {
+        let top = stack.last_mut().expect("stack is never empty");
+        if top.unread == 0 {
+            let done = stack.pop().expect("stack is never empty");
+            match stack.last_mut() {
+                // Whatever is below was waiting for this as the argument it sent.
+                Some(below) => {
+                    let then = std::mem::replace(&mut below.value, Value::Break);
+                    below.value = Value::Send(Box::new(done.value), Box::new(then));
+                }
+                None => return Ok(Some(done.value)),
+            }
+            continue;
+        }
+
+        top.unread -= 1;
+        let argument = match &top.handle.lock().expect("lock failed").events()[top.unread] {
+            Event::Send(_, argument) => Arc::clone(argument),
+            Event::Choose(_, chosen) => {
+                let then = std::mem::replace(&mut top.value, Value::Break);
+                top.value = Value::Choose(Name::from(chosen.to_string()), Box::new(then));
+                continue;
+            }
+            _ => return Ok(None),
+        };
+        if stack.len() >= MAX_DEPTH {
+            return Err(TooDeep);
+        }
+        let Some(frame) = Frame::start(&argument) else {
+            return Ok(None);
+        };
+        stack.push(frame);
+    }
+}
+
+/// A part of the value being walked with an explicit stack, children before their
+/// parent, so that a deeply nested value can't overflow the stack.
+struct Part {
+    handle: RunHandle,
+    /// How many of its events have been walked.
+    walked: usize,
+    finished: bool,
+    /// Whether a value sent or received by it is waiting for input, directly or further
+    /// in. Until it gets it, this part can't be expected to get any further.
+    waits_inside: bool,
+}
+
+impl Part {
+    fn new(handle: &RunHandle) -> Self {
+        Self {
+            handle: Arc::clone(handle),
+            walked: 0,
+            finished: false,
+            waits_inside: false,
+        }
+    }
+}
+
+/// Whether every part of the value has either finished, failed, or is waiting for input.
+/// Parts nested more than [`MAX_DEPTH`] levels deep aren't waited for, since they are
+/// too deep to read back or show anyway.
+fn settled(handle: &RunHandle) -> bool {
+    let mut stack = vec![Part::new(handle)];
+    while !stack.is_empty() {
+        let depth = stack.len();
+        let top = stack.last_mut().expect("stack is never empty");
+        let handle = Arc::clone(&top.handle);
+        let handle = handle.lock().expect("lock failed");
+        if let Some(event) = handle.events().get(top.walked) {
+            top.walked += 1;
+            match event {
+                Event::Send(_, inner) | Event::Receive(_, inner) if depth < MAX_DEPTH => {
+                    stack.push(Part::new(inner))
+                }
+                Event::Send(_, _) | Event::Receive(_, _) => {}
+                Event::Break(_) | Event::Continue(_) | Event::String(_, _) | Event::Int(_, _) => {
+                    top.finished = true
+                }
+                Event::Choose(_, _) | Event::Either(_, _) => {}
+            }
+            continue;
+        }
+        let interaction = handle.interaction();
+        if interaction.is_none() && !top.finished && !top.waits_inside {
+            return false;
+        }
+        let waits = matches!(interaction, Some(Ok(_))) || top.waits_inside;
+        stack.pop();
+        if let Some(below) = stack.last_mut() {
+            below.waits_inside |= waits;
+        }
+    }
+    true
+}
+
+/// Writes the events of `handle` as an indented tree, returning whether any part failed.
+/// A part that has neither finished nor is waiting for input, itself or inside, is
+/// marked `⟨stuck⟩`, and one nested more than [`MAX_DEPTH`] levels deep is left out.
+fn show(out: &mut String, code: &Arc<str>, handle: &RunHandle) -> bool {
+    let mut failed = false;
+    // Each part, with what closes it once its events are written.
+    let mut stack = vec![(Part::new(handle), "")];
+    while !stack.is_empty() {
+        let depth = stack.len();
+        let pad = "  ".repeat(depth - 1);
+        let (top, _) = stack.last_mut().expect("stack is never empty");
+        let handle = Arc::clone(&top.handle);
+        let handle = handle.lock().expect("lock failed");
+
+        if let Some(event) = handle.events().get(top.walked) {
+            top.walked += 1;
+            let (inner, open, close) = match event {
+                Event::Send(_, argument) => (argument, "(", ")"),
+                Event::Receive(_, parameter) => (parameter, "[", "]"),
+                Event::Choose(_, chosen) => {
+                    writeln!(out, "{}+ {}", pad, chosen).expect("write failed");
+                    continue;
+                }
+                Event::Either(_, chosen) => {
+                    writeln!(out, "{}> {}", pad, chosen).expect("write failed");
+                    continue;
+                }
+                Event::Break(_) => {
+                    top.finished = true;
+                    writeln!(out, "{}break", pad).expect("write failed");
+                    continue;
+                }
+                Event::Continue(_) => {
+                    top.finished = true;
+                    writeln!(out, "{}continue", pad).expect("write failed");
+                    continue;
+                }
+                Event::String(_, text) => {
+                    top.finished = true;
+                    writeln!(out, "{}{}", pad, quote(text)).expect("write failed");
+                    continue;
+                }
+                Event::Int(_, number) => {
+                    top.finished = true;
+                    writeln!(out, "{}{}", pad, number).expect("write failed");
+                    continue;
+                }
+            };
+            writeln!(out, "{}{}", pad, open).expect("write failed");
+            if depth >= MAX_DEPTH {
+                writeln!(out, "{}  ⟨too deep to show⟩", pad).expect("write failed");
+                writeln!(out, "{}{}", pad, close).expect("write failed");
+                failed = true;
+            } else {
+                stack.push((Part::new(inner), close));
+            }
+            continue;
+        }
+
+        let interaction = handle.interaction();
+        let waits = matches!(interaction, Some(Ok(_))) || top.waits_inside;
+        match interaction {
+            Some(Ok(Request::Dynamic(_))) => {
+                writeln!(out, "{}<UI>", pad).expect("write failed");
+            }
+            Some(Ok(Request::Either(_, choices))) => {
+                let choices = choices
+                    .iter()
+                    .map(|choice| format!(".{}", choice))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(out, "{}<waiting for one of {}>", pad, choices).expect("write failed");
+            }
+            Some(Ok(Request::String(_))) => {
+                writeln!(out, "{}<waiting for a string>", pad).expect("write failed");
+            }
+            Some(Ok(Request::Int(_, number))) => {
+                writeln!(out, "{}<waiting for a {}>", pad, number).expect("write failed");
+            }
+            Some(Err(error)) => {
+                writeln!(out, "{}", Error::Runtime(error).display(code.clone()))
+                    .expect("write failed");
+                failed = true;
+            }
+            None if !top.finished && !top.waits_inside => {
+                writeln!(out, "{}⟨stuck⟩", pad).expect("write failed");
+                failed = true;
+            }
+            None => {}
+        }
+
+        let (_, close) = stack.pop().expect("stack is never empty");
+        if let Some((below, _)) = stack.last_mut() {
+            below.waits_inside |= waits;
+            writeln!(out, "{}{}", "  ".repeat(stack.len() - 1), close).expect("write failed");
+        }
+    }
+    failed
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        gallery::{FrontMatter, EXAMPLES},
+        par::frontend::Par,
+        transcript::Step,
+    };
+
+    async fn sample(definition: &str, arguments: &[&str]) -> Result<String, String> {
+        let arguments = arguments
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>();
+        evaluate(
+            &Par,
+            Arc::from(include_str!("../examples/sample.par")),
+            definition,
+            &arguments,
+            Verbosity::Quiet,
+            None,
+        )
+        .await
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_sample() {
+        assert_eq!(
+            sample("listrgb", &[]).await.unwrap(),
+            "[.red!, .green!, .blue!]\n"
+        );
+        assert_eq!(sample("rgb", &[".green"]).await.unwrap(), ".green!\n");
+        assert_eq!(sample("swap", &["true"]).await.unwrap(), "false\n");
+        assert_eq!(
+            sample("red_forever", &[]).await.unwrap(),
+            "<waiting for one of .close, .next>\n"
+        );
+        assert!(sample("swap", &[".maybe"]).await.is_err());
+        assert_eq!(
+            sample("rgb", &[]).await,
+            Err("`rgb` takes 1 argument, but 0 were given. Pass each one with `--arg`.".to_owned())
+        );
+        let boxed = format!(
+            "{}\ndec boxed : ([Color] Color)!\ndef boxed = (rgb)!",
+            include_str!("../examples/sample.par")
+        );
+        // The function isn't stuck, it waits for its argument to be picked.
+        assert_eq!(
+            evaluate(&Par, Arc::from(boxed), "boxed", &[], Verbosity::Quiet, None).await,
+            Ok("(\n  [\n    <waiting for one of .red, .green, .blue>\n  ]\n)\nbreak\n".to_owned())
+        );
+        assert_eq!(
+            sample("nope", &[]).await,
+            Err("`nope` is not defined.".to_owned())
+        );
+        assert_eq!(
+            sample("listrbg", &[]).await,
+            Err("`listrbg` is not defined. Did you mean `listrgb`?".to_owned())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_arity() {
+        let run = |code: &str| {
+            let code = Arc::from(code);
+            async move { evaluate(&Par, code, "f", &[], Verbosity::Quiet, None).await }
+        };
+        let takes_two = Err("`f` takes 2 arguments, but 0 were given. \
+                             Pass each one with `--arg`."
+            .to_owned());
+        // Through a type definition, with arguments of its own.
+        assert_eq!(
+            run("type Fn<A> = [A] [A] A\ndec f : Fn<Int>\ndef f = [a, b] a + b").await,
+            takes_two
+        );
+        // From an annotation on the definition itself.
+        assert_eq!(
+            run("def f : [Int] [Nat] Int = [a, b] a + b").await,
+            takes_two
+        );
+        assert_eq!(
+            run("type Curried = [Int] Then\ntype Then = [Int] Int\n\
+                 dec f : Curried\ndef f = [a, b] a * b")
+            .await,
+            takes_two
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_bool() {
+        let run = |code: &str| {
+            let code = Arc::from(code);
+            async move { evaluate(&Par, code, "main", &[], Verbosity::Quiet, None).await }
+        };
+        assert_eq!(
+            run("def main: either { .yes!, .no! } = if true then .yes! else .no!").await,
+            Ok(".yes!\n".to_owned())
+        );
+        assert_eq!(
+            run("dec not : [Bool] Bool\n\
+                 def not = [b] if b then false else true\n\
+                 def main = (not(true), not(false))!")
+            .await,
+            Ok("(false, true)!\n".to_owned())
+        );
+        // A program's own `Bool` replaces the builtin one.
+        assert!(
+            run("type Bool = either { .yes!, .no! }\ndef main: Bool = true")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_numbers() {
+        let run = |code: &str| {
+            let code = Arc::from(code);
+            async move { evaluate(&Par, code, "main", &[], Verbosity::Quiet, None).await }
+        };
+        assert_eq!(
+            run("def main: Int = 2 * 3 - 10").await,
+            Ok("-4\n".to_owned())
+        );
+        assert_eq!(
+            run("dec times : [Int, Int] Int\n\
+                 def times = [a, b] a * b\n\
+                 def main = (times(-3 + 1, 2), times(4, 4))!")
+            .await,
+            Ok("(-4, 16)!\n".to_owned())
+        );
+        // A minus sign right before the digits is the number's own.
+        assert_eq!(run("def main: Int = 3 - -5 -1").await, Ok("7\n".to_owned()));
+        assert_eq!(
+            run("def main: Int = -170141183460469231731687303715884105728 + 1").await,
+            Ok("-170141183460469231731687303715884105727\n".to_owned())
+        );
+        assert!(
+            run("def main: Int = 99999999999999999999 * 99999999999999999999")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_replay_transcript() {
+        let code = Arc::from(include_str!("../examples/sample.par"));
+        let next = "> next\n(\n  + red\n  break\n)\n";
+        let waiting = "<waiting for one of .close, .next>\n";
+        let mut transcript = Transcript {
+            definition: "red_forever".to_owned(),
+            arguments: vec![],
+            steps: vec![
+                Step {
+                    seen: waiting.to_owned(),
+                    path: vec![],
+                    choice: "next".to_owned(),
+                },
+                Step {
+                    seen: format!("{}{}", next, waiting),
+                    path: vec![],
+                    choice: "close".to_owned(),
+                },
+            ],
+            result: format!("{}> close\nbreak\n", next),
+        };
+        assert_eq!(
+            check_transcript(&Par, Arc::clone(&code), &transcript, Verbosity::Quiet, None).await,
+            Ok(())
+        );
+
+        transcript.steps[1].path = vec![1];
+        assert!(
+            check_transcript(&Par, Arc::clone(&code), &transcript, Verbosity::Quiet, None)
+                .await
+                .is_err()
+        );
+        transcript.steps[1].path = vec![];
+        transcript.result = format!("{}> close\n", next);
+        assert!(
+            check_transcript(&Par, code, &transcript, Verbosity::Quiet, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_replay_string_input() {
+        let code = Arc::from(
+            r#"
+            dec ask : { .greet => [String] String, .quit => ! }
+            def ask = { .greet => [name] "Hi, {name}!", .quit => ! }
+            "#,
+        );
+        let transcript = Transcript {
+            definition: "ask".to_owned(),
+            arguments: vec![],
+            steps: vec![
+                Step {
+                    seen: "<waiting for one of .greet, .quit>\n".to_owned(),
+                    path: vec![],
+                    choice: "greet".to_owned(),
+                },
+                Step {
+                    seen: "> greet\n[\n  <waiting for a string>\n]\n".to_owned(),
+                    path: vec![1],
+                    choice: r#""Ada""#.to_owned(),
+                },
+            ],
+            result: "> greet\n[\n  \"Ada\"\n]\n\"Hi, Ada!\"\n".to_owned(),
+        };
+        assert_eq!(
+            check_transcript(&Par, code, &transcript, Verbosity::Quiet, None).await,
+            Ok(())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_replay_preset() {
+        let code: Arc<str> = Arc::from(
+            r#"
+            dec greet : [String] { .hi => String, .bye => String }
+            def greet = [name] { .hi => "Hi, {name}!", .bye => "Bye, {name}!" }
+            "#,
+        );
+        let mut transcript = Transcript {
+            definition: "greet".to_owned(),
+            arguments: vec![r#""Ada""#.to_owned()],
+            steps: vec![Step {
+                seen: "<waiting for one of .hi, .bye>\n".to_owned(),
+                path: vec![],
+                choice: "hi".to_owned(),
+            }],
+            result: "> hi\n\"Hi, Ada!\"\n".to_owned(),
+        };
+        // Twice, like Re-run does it: each time from the recorded definition and
+        // arguments, not the definition they were applied in.
+        for _ in 0..2 {
+            assert_eq!(
+                check_transcript(&Par, Arc::clone(&code), &transcript, Verbosity::Quiet, None)
+                    .await,
+                Ok(())
+            );
+        }
+
+        transcript.arguments.clear();
+        assert!(
+            check_transcript(&Par, code, &transcript, Verbosity::Quiet, None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_replay_numbers() {
+        let code: Arc<str> = Arc::from(
+            r#"
+            dec calc : { .add => [Nat, Int] Int }
+            def calc = { .add => [a, b] a + b }
+            "#,
+        );
+        let step = |seen: &str, path: usize, choice: &str| Step {
+            seen: seen.to_owned(),
+            path: vec![path],
+            choice: choice.to_owned(),
+        };
+        let mut transcript = Transcript {
+            definition: "calc".to_owned(),
+            arguments: vec![],
+            steps: vec![
+                Step {
+                    seen: "<waiting for one of .add>\n".to_owned(),
+                    path: vec![],
+                    choice: "add".to_owned(),
+                },
+                step(
+                    "> add\n[\n  <waiting for a natural number>\n]\n[\n  ⟨stuck⟩\n]\n",
+                    1,
+                    "2",
+                ),
+                step(
+                    "> add\n[\n  2\n]\n[\n  <waiting for a number>\n]\n",
+                    2,
+                    "-5",
+                ),
+            ],
+            result: "> add\n[\n  2\n]\n[\n  -5\n]\n-3\n".to_owned(),
+        };
+        assert_eq!(
+            check_transcript(&Par, Arc::clone(&code), &transcript, Verbosity::Quiet, None).await,
+            Ok(())
+        );
+
+        // The first number is a `Nat`, so it can't be negative.
+        transcript.steps[1].choice = "-2".to_owned();
+        assert_eq!(
+            check_transcript(&Par, code, &transcript, Verbosity::Quiet, None).await,
+            Err("Step 2: nothing at [1] is waiting for `-2`.".to_owned())
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_gallery_expectations() {
+        for example in EXAMPLES {
+            let front_matter = FrontMatter::parse(example.code);
+            if let (Some(run), Some(expect)) = (front_matter.run, front_matter.expect) {
+                assert_eq!(
+                    evaluate(
+                        &Par,
+                        Arc::from(example.code),
+                        &run,
+                        &[],
+                        Verbosity::Quiet,
+                        None
+                    )
+                    .await,
+                    Ok(format!("{}\n", expect)),
+                    "unexpected result from {}",
+                    example.file
+                );
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_run_strings() {
+        let code: Arc<str> = Arc::from(
+            r#"
+            dec pass : [String] String
+            def pass = [s] s
+            dec greeting : either { .hello (String, String) ! }
+            def greeting = .hello("Hi, \"you\"", pass("!\n"))!
+            dec greet : [String, String] String
+            def greet = [first, last] "Hello, {first} {last}! \{\}"
+            "#,
+        );
+        let run = |definition: &'static str, arguments: &[&str]| {
+            let arguments = arguments
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>();
+            let code = Arc::clone(&code);
+            async move { evaluate(&Par, code, definition, &arguments, Verbosity::Quiet, None).await }
+        };
+        assert_eq!(
+            run("greeting", &[]).await.unwrap(),
+            ".hello(\"Hi, \\\"you\\\"\", \"!\\n\")!\n"
+        );
+        assert_eq!(run("pass", &["\"⅋\""]).await.unwrap(), "\"⅋\"\n");
+        assert_eq!(
+            run("greet", &["\"Ada\"", "\"Lovelace\""]).await.unwrap(),
+            "\"Hello, Ada Lovelace! \\{\\}\"\n"
+        );
+    }
+
+    /// A program whose `main` has 2^`doublings` values nested inside it, one in each,
+    /// each followed by a `leaf` of type `Leaf`.
+    fn nest(doublings: usize, leaf_type: &str, leaf: &str) -> String {
+        format!(
+            "type Nat = recursive either {{ .zero!, .add1 self }}
+             type Leaf = {}
+             type Nest = recursive either {{ .leaf Leaf, .nest(self) self }}
+             dec double : [Nat] Nat
+             def double = [n] n begin {{
+               .zero! => .zero!,
+               .add1 m => .add1 .add1 m loop,
+             }}
+             dec nest : [Nat] Nest
+             def nest = [n] n begin {{
+               .zero! => .leaf {},
+               .add1 m => .nest(m loop) .leaf {},
+             }}
+             def main = nest({}.add1 .zero!{})",
+            leaf_type,
+            leaf,
+            leaf,
+            "double(".repeat(doublings),
+            ")".repeat(doublings)
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_readback_depth() {
+        let nest = |doublings| nest(doublings, "!", "!");
+        let run =
+            |code: String| evaluate(&Par, Arc::from(code), "main", &[], Verbosity::Quiet, None);
+        assert_eq!(
+            run(nest(1)).await,
+            Ok(".nest(.nest(.leaf!) .leaf!) .leaf!\n".to_owned())
+        );
+        assert!(run(nest(9))
+            .await
+            .unwrap()
+            .starts_with(".nest(.nest(.nest("));
+        assert_eq!(run(nest(10)).await, Err(TooDeep.to_string()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_show_depth() {
+        // Every leaf waits for a choice, so the result is shown as a tree of events.
+        let nest = |doublings| nest(doublings, "{ .close => ! }", "{ .close => ! }");
+        let run =
+            |code: String| evaluate(&Par, Arc::from(code), "main", &[], Verbosity::Quiet, None);
+        let shown = run(nest(9)).await.unwrap();
+        assert!(shown.starts_with("+ nest\n(\n  + nest\n  (\n"));
+        assert_eq!(shown.matches("<waiting for one of .close>").count(), 513);
+        let shown = run(nest(10)).await.unwrap_err();
+        assert!(shown.contains("⟨too deep to show⟩"));
+        assert!(!shown.contains("⟨stuck⟩"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_max_steps() {
+        let code = Arc::from(include_str!("../examples/sample.par"));
+        let run = |max_steps| {
+            let code = Arc::clone(&code);
+            async move { evaluate(&Par, code, "listrgb", &[], Verbosity::Quiet, max_steps).await }
+        };
+        let stopped = run(Some(1)).await.unwrap_err();
+        assert!(stopped.contains("limit of 1 steps"), "{}", stopped);
+        assert_eq!(
+            run(Some(1000)).await,
+            Ok("[.red!, .green!, .blue!]\n".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let code = include_str!("../examples/sample.par");
+        let definitions = code.lines().filter(|line| line.starts_with("def ")).count();
+        assert_eq!(
+            check_code(&Par, Arc::from(code), "main", Verbosity::Quiet),
+            Ok(definitions)
+        );
+        assert!(check_code(
+            &Par,
+            Arc::from("dec x : either { .a! }\ndef x = .b!"),
+            "main",
+            Verbosity::Quiet
+        )
+        .is_err());
+        assert!(check_code(&Par, Arc::from("def x = ("), "main", Verbosity::Quiet).is_err());
+    }
+
+    #[test]
+    fn test_check_recovered() {
+        // `x` fails to parse, but `y` is still checked against its declared type.
+        let code = "dec x : either { .a! }\ndef x = (\ndec y : either { .a! }\ndef y = .b!";
+        let Err(error) = check_code(&Par, Arc::from(code), "main", Verbosity::Quiet) else {
+            panic!("a program that doesn't parse checked");
+        };
+        assert!(error.contains("Syntax error."));
+        assert!(error.contains("Branch `b` is not available"));
+
+        // Without a declaration, nothing says what `x` was meant to be.
+        let Err(error) = check_code(
+            &Par,
+            Arc::from("def x = (\ndef y = x"),
+            "main",
+            Verbosity::Quiet,
+        ) else {
+            panic!("a program that doesn't parse checked");
+        };
+        assert!(error.contains("The type of what failed to parse here"));
+    }
+}
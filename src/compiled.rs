@@ -0,0 +1,549 @@
+use std::{collections::HashSet, fmt::Write, sync::Arc};
+
+use indexmap::IndexMap;
+use miette::{LabeledSpan, SourceSpan};
+
+use crate::par::{
+    frontend::Frontend,
+    language::{self, CompileError, Internal},
+    lint::{self, Uses, Warning},
+    outline,
+    parse::{Loc, Name, Program, SyntaxError},
+    process::{Definitions, Expression},
+    protocol,
+    runtime::{self, Operation},
+    scope::Scopes,
+    types::{self, Hole, Type, TypeError},
+};
+
+/// A program compiled for the runtime, and how checking it went.
+#[derive(Clone)]
+pub struct Compiled {
+    /// The program with every definition compiled down to processes.
+    pub program: Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, ()>>>,
+    /// The compiled definitions, written out one after another.
+    pub pretty: String,
+    /// The program with types filled in, or the first type error found.
+    pub checked: Result<Checked, TypeError<Loc, Internal<Name>>>,
+    /// What the linter found, which doesn't keep the program from running.
+    pub warnings: Vec<Warning<Loc, Name>>,
+    /// Which definitions each definition uses, to warn about the ones never used by
+    /// the definition that's run.
+    pub uses: Uses<Loc, Name>,
+}
+
+impl Compiled {
+    pub fn from_source(frontend: &dyn Frontend, source: &str) -> Result<Compiled, Error> {
+        let (program, errors) = frontend.parse(source);
+        if !errors.is_empty() {
+            return Err(Error::recovered(errors, program));
+        }
+        Self::from_parsed(program)
+    }
+
+    pub fn from_parsed(
+        mut program: Program<Loc, Name, language::Expression<Loc, Name>>,
+    ) -> Result<Compiled, Error> {
+        program.add_builtin_types();
+        let (warnings, uses) = lint::check_program(&program);
+        let scopes = (program.definitions.iter())
+            .map(|(_, _, expression)| Scopes::of(expression))
+            .collect::<Vec<_>>();
+        let type_defs = program
+            .type_defs
+            .into_iter()
+            .map(|(loc, name, params, typ)| {
+                (
+                    loc,
+                    Internal::Original(name),
+                    params.into_iter().map(Internal::Original).collect(),
+                    typ.map_names(&mut Internal::Original),
+                )
+            })
+            .collect();
+        let declarations = program
+            .declarations
+            .into_iter()
+            .map(|(loc, name, typ)| {
+                (
+                    loc,
+                    Internal::Original(name),
+                    typ.map_names(&mut Internal::Original),
+                )
+            })
+            .collect();
+        let compile_result = program
+            .definitions
+            .into_iter()
+            .map(|(loc, name, def)| {
+                def.compile().map(|compiled| {
+                    (
+                        loc,
+                        Internal::Original(name.clone()),
+                        compiled.optimize().fix_captures(&IndexMap::new()).0,
+                    )
+                })
+            })
+            .collect::<Result<_, CompileError<Loc>>>();
+        match compile_result {
+            Ok(compiled) => {
+                let mut compiled = Compiled {
+                    warnings,
+                    uses,
+                    ..Compiled::from_program(Program {
+                        type_defs,
+                        declarations,
+                        definitions: compiled,
+                        allows: program.allows,
+                        ends: program.ends,
+                    })
+                };
+                compiled.checked = compiled
+                    .checked
+                    .map_err(|error| point_out_of_scope(error, &scopes));
+                Ok(compiled)
+            }
+            Err(error) => Err(Error::Compile(error)),
+        }
+    }
+
+    /// The linter's warnings, along with the definitions never used by `entry`, the
+    /// definition that's run, if that's known.
+    pub fn warnings_running(&self, entry: Option<&str>) -> Vec<Warning<Loc, Name>> {
+        let unused = entry.map(|entry| self.uses.unused_from(&Name::from(entry.to_owned())));
+        (self.warnings.iter().cloned())
+            .chain(unused.into_iter().flatten())
+            .collect()
+    }
+
+    /// The compiled definitions, keyed by name for running.
+    pub fn definitions(&self) -> Arc<Definitions<Loc, Internal<Name>, ()>> {
+        Arc::new(
+            (self.program.definitions.iter())
+                .map(|(_, name, expression)| (name.clone(), Arc::clone(expression)))
+                .collect(),
+        )
+    }
+
+    /// Where arithmetic takes a `Nat`, as in [`Checked::naturals`], or nowhere if the
+    /// program doesn't type check.
+    pub fn naturals(&self) -> Arc<HashSet<Loc>> {
+        (self.checked.as_ref())
+            .map_or_else(|_| Arc::default(), |checked| Arc::clone(&checked.naturals))
+    }
+
+    pub fn from_program(
+        program: Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, ()>>>,
+    ) -> Self {
+        let pretty = program
+            .definitions
+            .iter()
+            .map(|(_, name, def)| {
+                let mut buf = String::new();
+                write!(&mut buf, "define {} = ", name).expect("write failed");
+                def.pretty(&mut buf, 0).expect("write failed");
+                write!(&mut buf, "\n\n").expect("write failed");
+                buf
+            })
+            .collect();
+
+        // attempt to type check
+        let (definitions, types, holes, naturals) =
+            match types::Context::new_with_type_checking(&program) {
+                Ok(context) => (
+                    context.get_checked_definitions(),
+                    context.get_checked_types(),
+                    context.get_holes(),
+                    context.get_naturals(),
+                ),
+                Err(error) => {
+                    return Compiled {
+                        program,
+                        pretty,
+                        checked: Err(error),
+                        warnings: Vec::new(),
+                        uses: Uses::default(),
+                    }
+                }
+            };
+        let new_program = Program {
+            type_defs: program.type_defs.clone(),
+            declarations: program.declarations.clone(),
+            definitions,
+            allows: program.allows.clone(),
+            ends: program.ends.clone(),
+        };
+        return Compiled {
+            program,
+            pretty,
+            checked: Ok(Checked::from_program(new_program, types, holes, naturals)),
+            warnings: Vec::new(),
+            uses: Uses::default(),
+        };
+    }
+}
+
+/// A program that type checks.
+#[derive(Clone)]
+pub struct Checked {
+    /// The compiled program, with the type of every expression in it.
+    pub program: TypedProgram,
+    /// The type of each definition, declared or inferred.
+    pub types: IndexMap<Internal<Name>, Type<Loc, Internal<Name>>>,
+    /// Every hole in the program, in the order they were met.
+    pub holes: Vec<Hole<Loc, Internal<Name>>>,
+    /// Where arithmetic takes a `Nat`, by the location of the operand, for
+    /// [`Context::with_naturals`](runtime::Context::with_naturals).
+    pub naturals: Arc<HashSet<Loc>>,
+}
+
+type TypedProgram =
+    Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, Type<Loc, Internal<Name>>>>>;
+
+impl Checked {
+    pub fn from_program(
+        program: TypedProgram,
+        types: IndexMap<Internal<Name>, Type<Loc, Internal<Name>>>,
+        mut holes: Vec<Hole<Loc, Internal<Name>>>,
+        naturals: HashSet<Loc>,
+    ) -> Self {
+        // Only show the variables written in the source, not the ones made up while
+        // compiling.
+        for hole in &mut holes {
+            (hole.variables).retain(|name, _| matches!(name, Internal::Original(_)));
+        }
+        Checked {
+            program,
+            types,
+            holes,
+            naturals: Arc::new(naturals),
+        }
+    }
+
+    /// The type of the definition under `cursor_line`, laid out step by step.
+    pub fn outline(&self, cursor_line: usize) -> Option<(&Name, Vec<outline::Node>)> {
+        let (name, _) = definition_under_cursor(&self.program, cursor_line)?;
+        let typ = self.types.get(&Internal::Original(name.clone()))?;
+        Some((name, outline::outline(typ)))
+    }
+
+    /// How the definition under `cursor_line` uses each of its channels.
+    pub fn protocol(&self, cursor_line: usize) -> Option<String> {
+        let (name, expression) = definition_under_cursor(&self.program, cursor_line)?;
+        let mut buf = String::new();
+        write!(&mut buf, "define {}\n\n", name).expect("write failed");
+        for protocol in protocol::protocols(expression) {
+            protocol.pretty(&mut buf).expect("write failed");
+            writeln!(&mut buf).expect("write failed");
+        }
+        Some(buf)
+    }
+}
+
+/// The definition the line is in, from the line of its name to that of its last token.
+/// There is none on lines between definitions, or in declarations and types.
+pub fn definition_under_cursor<E>(
+    program: &Program<Loc, Internal<Name>, E>,
+    cursor_line: usize,
+) -> Option<(&Name, &E)> {
+    (program.definitions.iter()).find_map(|(loc, name, expression)| {
+        let (Loc::Code { line: start, .. }, Internal::Original(name)) = (loc, name) else {
+            return None;
+        };
+        let Some(Loc::Code { line: end, .. }) = program.ends.get(loc) else {
+            return None;
+        };
+        (*start..=*end)
+            .contains(&cursor_line)
+            .then_some((name, expression))
+    })
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Every syntax error, and the first error compiling or checking what could be
+    /// parsed ran into, if any.
+    Parse(Vec<SyntaxError>, Option<Box<Error>>),
+    Compile(CompileError<Loc>),
+    Type(TypeError<Loc, Internal<Name>>),
+    Runtime(runtime::Error<Loc, Internal<Name>>),
+}
+
+/// Turns a name that isn't defined into one that isn't in scope, if it's bound
+/// somewhere else in the definition that uses it.
+fn point_out_of_scope(
+    error: TypeError<Loc, Internal<Name>>,
+    scopes: &[Scopes<Loc, Name>],
+) -> TypeError<Loc, Internal<Name>> {
+    let TypeError::NameNotDefined(loc, Internal::Original(name)) = error else {
+        return error;
+    };
+    let binders = (scopes.iter())
+        .map(|scopes| scopes.out_of_reach(&loc, &name))
+        .find(|binders| !binders.is_empty());
+    match binders {
+        Some(binders) => TypeError::NameNotInScope(loc, Internal::Original(name), binders),
+        None => TypeError::NameNotDefined(loc, Internal::Original(name)),
+    }
+}
+
+/// Create a `LabeledSpan` without a label at `loc`
+pub fn labels_from_loc(loc: &Loc) -> Vec<LabeledSpan> {
+    loc.span()
+        .map(|span| LabeledSpan::new_with_span(None, span))
+        .into_iter()
+        .collect()
+}
+
+#[derive(Debug, miette::Diagnostic)]
+struct RuntimeError {
+    #[label]
+    span: Option<SourceSpan>,
+    #[label(collection)]
+    others: Vec<LabeledSpan>,
+    #[related]
+    related: Vec<miette::ErrReport>,
+    message: String,
+}
+impl core::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        format!("Runtime Error: {}.", self.message).fmt(f)
+    }
+}
+impl core::error::Error for RuntimeError {}
+
+impl Error {
+    /// The syntax errors found parsing a program, along with what compiling and checking
+    /// the `program` recovered from them runs into, so both can be fixed at once.
+    pub fn recovered(
+        errors: Vec<SyntaxError>,
+        program: Program<Loc, Name, language::Expression<Loc, Name>>,
+    ) -> Self {
+        let rest = match Compiled::from_parsed(program) {
+            Ok(compiled) => compiled.checked.err().map(Self::Type),
+            Err(error) => Some(error),
+        };
+        Self::Parse(errors, rest.map(Box::new))
+    }
+
+    pub fn display(&self, code: Arc<str>) -> String {
+        match self {
+            Self::Parse(errors, rest) => errors
+                .iter()
+                .map(|error| {
+                    // Show syntax error with miette's formatting
+                    format!(
+                        "{:?}",
+                        miette::Report::from(error.to_owned()).with_source_code(code.clone())
+                    )
+                })
+                .chain(rest.iter().map(|rest| rest.display(code.clone())))
+                .collect(),
+
+            Self::Compile(CompileError::MustEndProcess(loc)) => {
+                let labels = labels_from_loc(loc);
+                let code = if labels.is_empty() {
+                    "<UI>".into()
+                } else {
+                    code
+                };
+                let error = miette::miette! {
+                    labels = labels,
+                    help = "End it with a command like `x!`, `x <> y`, or `loop`. Inside the \
+                            branches of a match, it may instead go on with a process written \
+                            after their closing brace.",
+                    "This process must end."
+                }
+                .with_source_code(code);
+                format!("{error:?}")
+            }
+
+            Self::Compile(CompileError::UnreachablePass(loc)) => {
+                let error = miette::miette! {
+                    labels = labels_from_loc(loc),
+                    help = "Only branches that finish without ending their process go on with \
+                            the one after them. Remove it, or leave a branch unfinished.",
+                    "The process after these branches is never reached, because every one of them ends."
+                }
+                .with_source_code(code);
+                format!("{error:?}")
+            }
+
+            Self::Type(error) => format!("{:?}", error.into_report(code)),
+
+            Self::Runtime(error) => format!(
+                "{:?}",
+                miette::Report::from(Self::display_runtime_error(error))
+            ),
+        }
+    }
+
+    fn display_runtime_error(error: &runtime::Error<Loc, Internal<Name>>) -> RuntimeError {
+        use runtime::Error::*;
+        match error {
+            NameNotDefined(loc, name) => RuntimeError {
+                span: loc.span(),
+                related: Vec::new(),
+                others: Vec::new(),
+                message: format!("`{}` is not defined.", name),
+            },
+            ShadowedObligation(loc, name) => RuntimeError {
+                span: loc.span(),
+                related: Vec::new(),
+                others: Vec::new(),
+                message: format!("Cannot re-assign `{}` before handling it.", name),
+            },
+            UnfulfilledObligations(loc, names) => RuntimeError {
+                span: loc.span(),
+                related: Vec::new(),
+                others: Vec::new(),
+                message: format!(
+                    "Cannot end this process before handling {}.",
+                    names
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| if i == 0 {
+                            format!("`{}`", name)
+                        } else {
+                            format!(", `{}`", name)
+                        })
+                        .collect::<String>()
+                ),
+            },
+            IncompatibleOperations(op1, op2) => RuntimeError {
+                span: None,
+                related: Vec::new(),
+                others: [Self::display_operation(op1), Self::display_operation(op2)]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                message: "These operations are incompatible.".to_owned(),
+            },
+            NoSuchLoopPoint(loc, _) => RuntimeError {
+                span: loc.span(),
+                others: Vec::new(),
+                related: Vec::new(),
+                message: "There is no matching loop point in scope.".to_owned(),
+            },
+            Hole(loc, name) => RuntimeError {
+                span: loc.span(),
+                others: Vec::new(),
+                related: Vec::new(),
+                message: format!(
+                    "The hole `?{}` was run before it was filled in",
+                    name.as_ref().map_or(String::new(), ToString::to_string)
+                ),
+            },
+            Placeholder(loc) => RuntimeError {
+                span: loc.span(),
+                others: Vec::new(),
+                related: Vec::new(),
+                message: "This part of the program was run, but it did not parse.".to_owned(),
+            },
+            OutOfSteps(loc, max_steps) => RuntimeError {
+                span: loc.span(),
+                others: Vec::new(),
+                related: Vec::new(),
+                message: format!(
+                    "Stopped here, having reached the limit of {} steps set by `--max-steps` \
+                     or `max_steps` in the configuration",
+                    max_steps
+                ),
+            },
+            Overflow(loc) => RuntimeError {
+                span: loc.span(),
+                others: Vec::new(),
+                related: Vec::new(),
+                message: "This number is too large to hold.".to_owned(),
+            },
+            Multiple(error1, error2) => RuntimeError {
+                span: None,
+                others: Vec::new(),
+                related: vec![
+                    miette::Report::from(Self::display_runtime_error(error1)),
+                    miette::Report::from(Self::display_runtime_error(error2)),
+                ],
+                message: "multiple errors".to_owned(),
+            },
+        }
+    }
+
+    fn display_operation(op: &Operation<Loc, Internal<Name>>) -> Vec<LabeledSpan> {
+        match op {
+            Operation::Unknown(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("Unknown operation.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::Send(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is sending a value.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::Receive(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is receiving a value.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::Choose(loc, chosen) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some(format!("This side is choosing `{}`.", chosen)));
+                    x
+                })
+                .collect(),
+            Operation::Match(loc, choices) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some(format!(
+                        "This side is offering either of {}.",
+                        choices
+                            .iter()
+                            .enumerate()
+                            .map(|(i, name)| if i == 0 {
+                                format!("`{}`", name)
+                            } else {
+                                format!(", `{}`", name)
+                            })
+                            .collect::<String>(),
+                    )));
+                    x
+                })
+                .collect(),
+            Operation::Break(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is breaking.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::Continue(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is continuing.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::String(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is a string.".to_owned()));
+                    x
+                })
+                .collect(),
+            Operation::Int(loc) => labels_from_loc(loc)
+                .into_iter()
+                .map(|mut x| {
+                    x.set_label(Some("This side is a number.".to_owned()));
+                    x
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::par::{
+    frontend::{self, Frontend, FRONTENDS},
+    suggest,
+};
+
+/// Settings shared by the command line and the playground.
+///
+/// They are read, each overriding the one before, from `~/.config/par/config.toml`
+/// (or `$XDG_CONFIG_HOME/par/config.toml`), from the nearest `par.toml` in the current
+/// directory or above it, and from `PAR_*` environment variables. Anything left unset
+/// falls back to a default.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Number of threads processes are run on, apart from the ones the command line and
+    /// the playground use. Defaults to one per core.
+    pub threads: Option<usize>,
+    pub verbosity: Option<Verbosity>,
+    pub theme: Option<Theme>,
+    pub font_size: Option<f32>,
+    /// Name of the surface syntax programs are written in. Defaults to `par`.
+    pub syntax: Option<String>,
+    /// How many steps a run may take before it's stopped. Unlimited if unset.
+    pub max_steps: Option<usize>,
+    /// Ready-made ways to run a definition, written as `[[presets]]` tables.
+    pub presets: Vec<Preset>,
+}
+
+/// A definition to run along with the arguments to run it with, picked by name with
+/// `par run --preset` or from the playground's Run menu.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    pub name: String,
+    pub definition: String,
+    /// Written in the same syntax as `--arg`.
+    #[serde(default)]
+    pub arguments: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// Only results and errors.
+    Quiet,
+    /// Warnings too.
+    Normal,
+    /// Also which configuration files were read.
+    Verbose,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    System,
+    Dark,
+    Light,
+}
+
+/// A configuration along with the files it was read from.
+pub struct Loaded {
+    pub config: Config,
+    pub sources: Vec<PathBuf>,
+}
+
+impl Config {
+    pub fn load() -> Result<Loaded, String> {
+        let mut config = Config::default();
+        let mut sources = Vec::new();
+
+        let user = directory().map(|dir| dir.join("config.toml"));
+        let project = env::current_dir().ok().and_then(|dir| {
+            dir.ancestors()
+                .map(|dir| dir.join("par.toml"))
+                .find(|path| path.is_file())
+        });
+
+        for path in user.into_iter().chain(project) {
+            if !path.is_file() {
+                continue;
+            }
+            let text = fs::read_to_string(&path)
+                .map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+            let file =
+                Config::parse(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+            config = config.overridden_by(file);
+            sources.push(path);
+        }
+
+        let config = config.overridden_by(Config::from_env(env::vars())?);
+        Ok(Loaded { config, sources })
+    }
+
+    fn parse(text: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(text).map_err(|error| error.to_string())?;
+        // The compute pool can't be started without any threads to run on.
+        if config.threads == Some(0) {
+            return Err("Invalid threads: 0".to_owned());
+        }
+        Ok(config)
+    }
+
+    /// Reads the `PAR_THREADS`, `PAR_VERBOSITY`, `PAR_THEME`, `PAR_FONT_SIZE`,
+    /// `PAR_SYNTAX`, and `PAR_MAX_STEPS` variables.
+    fn from_env(vars: impl Iterator<Item = (String, String)>) -> Result<Config, String> {
+        let mut config = Config::default();
+        for (key, value) in vars {
+            let invalid = |error: String| format!("Invalid {}: {}", key, error);
+            match key.as_str() {
+                "PAR_THREADS" => {
+                    config.threads = Some(
+                        (value.parse().ok())
+                            .filter(|&threads| threads > 0)
+                            .ok_or_else(|| invalid(value.clone()))?,
+                    )
+                }
+                "PAR_VERBOSITY" => {
+                    config.verbosity = Config::parse(&format!("verbosity = {:?}", value))
+                        .map_err(invalid)?
+                        .verbosity
+                }
+                "PAR_THEME" => {
+                    config.theme = Config::parse(&format!("theme = {:?}", value))
+                        .map_err(invalid)?
+                        .theme
+                }
+                "PAR_FONT_SIZE" => {
+                    config.font_size = Some(value.parse().map_err(|_| invalid(value.clone()))?)
+                }
+                "PAR_SYNTAX" => config.syntax = Some(value),
+                "PAR_MAX_STEPS" => {
+                    config.max_steps = Some(value.parse().map_err(|_| invalid(value.clone()))?)
+                }
+                _ => {}
+            }
+        }
+        Ok(config)
+    }
+
+    fn overridden_by(self, other: Config) -> Config {
+        let mut presets = self.presets;
+        for preset in other.presets {
+            presets.retain(|kept| kept.name != preset.name);
+            presets.push(preset);
+        }
+        Config {
+            presets,
+            threads: other.threads.or(self.threads),
+            verbosity: other.verbosity.or(self.verbosity),
+            theme: other.theme.or(self.theme),
+            font_size: other.font_size.or(self.font_size),
+            syntax: other.syntax.or(self.syntax),
+            max_steps: other.max_steps.or(self.max_steps),
+        }
+    }
+
+    pub fn verbosity(&self) -> Verbosity {
+        self.verbosity.unwrap_or(Verbosity::Normal)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn theme(&self) -> Theme {
+        self.theme.unwrap_or(Theme::System)
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn font_size(&self) -> f32 {
+        self.font_size.unwrap_or(16.0)
+    }
+
+    pub fn preset(&self, name: &str) -> Result<&Preset, String> {
+        if let Some(preset) = self.presets.iter().find(|preset| preset.name == name) {
+            return Ok(preset);
+        }
+        let names = self.presets.iter().map(|preset| preset.name.as_str());
+        Err(match suggest::closest(name, names) {
+            Some(closest) => format!("No preset is named `{}`. Did you mean `{}`?", name, closest),
+            None => format!("No preset is named `{}`.", name),
+        })
+    }
+
+    pub fn frontend(&self) -> Result<&'static dyn Frontend, String> {
+        let name = self.syntax.as_deref().unwrap_or("par");
+        frontend::by_name(name).ok_or_else(|| {
+            let known = FRONTENDS
+                .iter()
+                .map(|frontend| format!("`{}`", frontend.name()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("Unknown syntax `{}`. Known syntaxes are {}.", name, known)
+        })
+    }
+}
+
+/// Where per-user files live: `~/.config/par`, or `$XDG_CONFIG_HOME/par`.
+pub fn directory() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .map(|dir| dir.join("par"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_config_layers() {
+        let user = Config::parse("threads = 4\ntheme = \"dark\"\nfont_size = 20.0").unwrap();
+        let project = Config::parse("verbosity = \"quiet\"\ntheme = \"light\"").unwrap();
+        let env = Config::from_env(
+            [
+                ("PAR_THREADS".to_owned(), "2".to_owned()),
+                ("HOME".to_owned(), "/home/par".to_owned()),
+            ]
+            .into_iter(),
+        )
+        .unwrap();
+
+        let config = Config::default()
+            .overridden_by(user)
+            .overridden_by(project)
+            .overridden_by(env);
+        assert_eq!(
+            config,
+            Config {
+                threads: Some(2),
+                verbosity: Some(Verbosity::Quiet),
+                theme: Some(Theme::Light),
+                font_size: Some(20.0),
+                syntax: None,
+                max_steps: None,
+                presets: Vec::new(),
+            }
+        );
+        assert_eq!(Config::default().verbosity(), Verbosity::Normal);
+        assert_eq!(Config::default().frontend().unwrap().name(), "par");
+        assert!(Config::parse("syntax = \"lisp\"")
+            .unwrap()
+            .frontend()
+            .is_err());
+
+        let user = Config::parse(
+            "[[presets]]\nname = \"one\"\ndefinition = \"f\"\narguments = [\".a\"]\n\
+             [[presets]]\nname = \"two\"\ndefinition = \"g\"",
+        )
+        .unwrap();
+        let project = Config::parse("[[presets]]\nname = \"one\"\ndefinition = \"h\"").unwrap();
+        let config = user.overridden_by(project);
+        assert_eq!(
+            config.preset("two").unwrap().arguments,
+            Vec::<String>::new()
+        );
+        assert_eq!(config.preset("one").unwrap().definition, "h");
+        assert!(config.preset("twp").unwrap_err().contains("`two`"));
+        assert!(Config::parse("[[presets]]\nname = \"x\"").is_err());
+
+        assert!(Config::parse("colour = \"red\"").is_err());
+        assert!(Config::parse("theme = \"sepia\"").is_err());
+        assert!(
+            Config::from_env([("PAR_VERBOSITY".to_owned(), "loud".to_owned())].into_iter())
+                .is_err()
+        );
+        assert!(Config::parse("threads = 0").is_err());
+        assert_eq!(
+            Config::from_env([("PAR_THREADS".to_owned(), "0".to_owned())].into_iter()),
+            Err("Invalid PAR_THREADS: 0".to_owned())
+        );
+    }
+}
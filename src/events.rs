@@ -0,0 +1,75 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::channel::mpsc;
+use tokio::sync::watch;
+
+/// Something that happened while compiling or running a program.
+#[derive(Clone, Debug)]
+pub enum RuntimeEvent {
+    CompileStarted,
+    CompileFinished(Duration),
+    /// A value being read back has something new to show.
+    OutputAvailable,
+    /// A value being read back ran into a runtime error, which its handle holds.
+    Failed,
+    /// A process of `run` panicked, and the rest of the run was dropped. Its spawner's
+    /// supervisor holds what the panic said.
+    Panicked {
+        run: u64,
+    },
+}
+
+/// How many processes `run` has spawned so far. Every spawner numbers its run apart
+/// from the others, so the counts of runs sharing a bus are never mixed up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub run: u64,
+    pub processes: usize,
+}
+
+/// Delivers every [`RuntimeEvent`] to everyone subscribed, so panes don't need to
+/// share state with the tasks doing the work to learn what they're up to.
+///
+/// [`Progress`] is reported far more often than anyone looks at it, so only the latest
+/// is kept, rather than queueing up every one for subscribers that aren't reading.
+#[derive(Clone)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<RuntimeEvent>>>>,
+    progress: Arc<watch::Sender<Progress>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            subscribers: Arc::default(),
+            progress: Arc::new(watch::Sender::new(Progress::default())),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<RuntimeEvent> {
+        let (tx, rx) = mpsc::unbounded();
+        self.subscribers.lock().expect("lock failed").push(tx);
+        rx
+    }
+
+    pub fn publish(&self, event: RuntimeEvent) {
+        self.subscribers
+            .lock()
+            .expect("lock failed")
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+
+    /// The latest progress reported, which changes as runs go on.
+    pub fn progress(&self) -> watch::Receiver<Progress> {
+        self.progress.subscribe()
+    }
+
+    pub fn report(&self, progress: Progress) {
+        self.progress.send_replace(progress);
+    }
+}
@@ -0,0 +1,180 @@
+/// An example program shipped with the playground.
+pub struct Example {
+    pub file: &'static str,
+    pub code: &'static str,
+}
+
+pub static EXAMPLES: &[Example] = &[
+    Example {
+        file: "sample.par",
+        code: include_str!("../examples/sample.par"),
+    },
+    Example {
+        file: "flatten.par",
+        code: include_str!("../examples/flatten.par"),
+    },
+    Example {
+        file: "fibonacci.par",
+        code: include_str!("../examples/fibonacci.par"),
+    },
+    Example {
+        file: "rock_paper_scissors.par",
+        code: include_str!("../examples/rock_paper_scissors.par"),
+    },
+    Example {
+        file: "bubble_sort.par",
+        code: include_str!("../examples/bubble_sort.par"),
+    },
+    Example {
+        file: "semigroup_queue.par",
+        code: include_str!("../examples/semigroup_queue.par"),
+    },
+];
+
+/// What a program says about itself in the comment lines at its very top:
+///
+/// ```text
+/// // title: Flatten
+/// // description: Flattens a tree into a list,
+/// //   from left to right.
+/// // run: main
+/// // expect: [true, false]
+/// // try: Pick `.next` a few times.
+/// ```
+///
+/// A line that doesn't start with one of these keys continues the one before it,
+/// and `try` may be given more than once. `expect` is written in the value syntax
+/// that `par run` prints.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub run: Option<String>,
+    pub expect: Option<String>,
+    pub tries: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Key {
+    Title,
+    Description,
+    Run,
+    Expect,
+    Try,
+}
+
+impl FrontMatter {
+    pub fn parse(code: &str) -> Self {
+        let mut front_matter = Self::default();
+        let mut last = None;
+
+        for line in code.lines() {
+            let Some(line) = line.trim().strip_prefix("//") else {
+                break;
+            };
+            let line = line.trim();
+            let key = line.split_once(':').and_then(|(key, value)| {
+                let key = match key {
+                    "title" => Key::Title,
+                    "description" => Key::Description,
+                    "run" => Key::Run,
+                    "expect" => Key::Expect,
+                    "try" => Key::Try,
+                    _ => return None,
+                };
+                Some((key, value.trim()))
+            });
+            match (key, last) {
+                (Some((key, value)), _) => {
+                    *front_matter.start(key) = value.to_owned();
+                    last = Some(key);
+                }
+                (None, Some(key)) => {
+                    let text = front_matter.continued(key);
+                    if line.is_empty() {
+                        text.push('\n');
+                    } else {
+                        if !text.is_empty() && !text.ends_with('\n') {
+                            text.push(' ');
+                        }
+                        text.push_str(line);
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+
+        front_matter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    fn start(&mut self, key: Key) -> &mut String {
+        if let Key::Try = key {
+            self.tries.push(String::new());
+        }
+        self.continued(key)
+    }
+
+    fn continued(&mut self, key: Key) -> &mut String {
+        match key {
+            Key::Title => self.title.get_or_insert_default(),
+            Key::Description => self.description.get_or_insert_default(),
+            Key::Run => self.run.get_or_insert_default(),
+            Key::Expect => self.expect.get_or_insert_default(),
+            Key::Try => self.tries.last_mut().expect("`try` was started"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_front_matter() {
+        let front_matter = FrontMatter::parse(
+            "// title: Flatten
+             // description: Flattens a tree
+             //   into a list.
+             //
+             //   From left to right.
+             // try: Run `main`.
+             // try: Read the result.
+             // expect: .empty!
+             type Tree = !
+             // run: ignored
+            ",
+        );
+        assert_eq!(
+            front_matter,
+            FrontMatter {
+                title: Some("Flatten".to_owned()),
+                description: Some("Flattens a tree into a list.\nFrom left to right.".to_owned()),
+                run: None,
+                expect: Some(".empty!".to_owned()),
+                tries: vec!["Run `main`.".to_owned(), "Read the result.".to_owned()],
+            }
+        );
+        assert!(FrontMatter::parse("type Tree = !").is_empty());
+    }
+
+    #[test]
+    fn test_examples_have_titles() {
+        for example in EXAMPLES {
+            let front_matter = FrontMatter::parse(example.code);
+            assert!(
+                front_matter.title.is_some(),
+                "{} has no title",
+                example.file
+            );
+            assert!(
+                front_matter.description.is_some(),
+                "{} has no description",
+                example.file
+            );
+        }
+    }
+}
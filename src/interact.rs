@@ -1,15 +1,21 @@
 use crate::{
+    events::{EventBus, RuntimeEvent},
     par::process::Expression,
-    par::runtime::{self, Context, Message, Value},
+    par::runtime::{self, Context, Message, Number, Value},
 };
 use futures::{channel::oneshot, task::SpawnExt};
 use std::{
+    collections::VecDeque,
     hash::Hash,
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 pub struct Handle<Loc, Name, Typ> {
-    refresh: Arc<dyn Fn() + Send + Sync>,
+    bus: EventBus,
+    trace: Trace<Loc, Name>,
+    /// Where this value is inside the one that was run, as in [`Traced::path`].
+    path: Vec<usize>,
     events: Vec<Event<Loc, Name, Typ>>,
     interaction: Option<Result<Interaction<Loc, Name, Typ>, runtime::Error<Loc, Name>>>,
     cancelled: bool,
@@ -22,6 +28,8 @@ pub enum Event<Loc, Name, Typ> {
     Either(Loc, Name),
     Break(Loc),
     Continue(Loc),
+    String(Loc, Arc<str>),
+    Int(Loc, i128),
 }
 
 struct Interaction<Loc, Name, Typ> {
@@ -30,10 +38,68 @@ struct Interaction<Loc, Name, Typ> {
     request: Request<Loc, Name>,
 }
 
+/// The last [`TRACE_LIMIT`] things done on the channels read back from a run, in order.
+/// It's shared by the handle of the value run and those of every value sent or received
+/// on it.
+#[derive(Clone)]
+pub struct Trace<Loc, Name> {
+    started: Instant,
+    entries: Arc<Mutex<VecDeque<Traced<Loc, Name>>>>,
+}
+
+/// How many entries a [`Trace`] keeps before dropping the oldest, so that a long run
+/// doesn't keep growing it.
+pub const TRACE_LIMIT: usize = 10_000;
+
+/// Kept by every run, but only read back by the playground, which saves it.
+#[derive(Clone, Debug)]
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct Traced<Loc, Name> {
+    /// How long after the run started it happened.
+    pub at: Duration,
+    /// Which channel it happened on: starting from the value that was run, the
+    /// position among the events of each sent or received value to step into.
+    pub path: Vec<usize>,
+    pub loc: Loc,
+    pub action: Action<Name>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action<Name> {
+    Send,
+    Receive,
+    Choose(Name),
+    /// A branch picked in the playground for a value waiting for one.
+    Either(Name),
+    Break,
+    Continue,
+    String(Arc<str>),
+    Int(i128),
+}
+
+impl<Loc, Name> Trace<Loc, Name> {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            entries: Arc::default(),
+        }
+    }
+
+    fn record(&self, traced: Traced<Loc, Name>) {
+        let mut entries = self.entries.lock().expect("lock failed");
+        if entries.len() == TRACE_LIMIT {
+            entries.pop_front();
+        }
+        entries.push_back(traced);
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Request<Loc, Name> {
     Dynamic(Loc),
     Either(Loc, Arc<[Name]>),
+    String(Loc),
+    Int(Loc, Number),
 }
 
 impl<Loc, Name, Typ> Handle<Loc, Name, Typ>
@@ -46,6 +112,14 @@ where
         &self.events
     }
 
+    /// The last [`TRACE_LIMIT`] things done on this value's channel and every one
+    /// inside it.
+    #[cfg(any(feature = "gui", test))]
+    pub fn trace(&self) -> Vec<Traced<Loc, Name>> {
+        let entries = self.trace.entries.lock().expect("lock failed");
+        entries.iter().cloned().collect()
+    }
+
     pub fn interaction(&self) -> Option<Result<Request<Loc, Name>, runtime::Error<Loc, Name>>> {
         match &self.interaction {
             Some(Ok(int)) => Some(Ok(int.request.clone())),
@@ -76,7 +150,7 @@ where
                             Err(error) => {
                                 let mut handle = handle.lock().expect("lock failed");
                                 handle.interaction = Some(Err(error));
-                                (handle.refresh)();
+                                handle.bus.publish(RuntimeEvent::Failed);
                             }
                         }
                     }
@@ -85,16 +159,51 @@ where
         }
     }
 
+    /// Answers a request for a string with `text`. If the run has stopped since it asked,
+    /// the string goes nowhere and nothing is recorded, as for a cancelled run.
+    pub fn send_string(handle: Arc<Mutex<Self>>, loc: Loc, text: Arc<str>) {
+        let mut handle = handle.lock().expect("lock failed");
+        if let Some(Ok(Interaction {
+            value: Value::Sender(tx),
+            ..
+        })) = handle.interaction.take()
+        {
+            if tx
+                .send(Message::String(Loc::default(), Arc::clone(&text)))
+                .is_ok()
+            {
+                handle.add_event(Event::String(loc, text));
+            }
+        }
+    }
+
+    /// Answers a request for a number with `number`, or drops it like
+    /// [`Handle::send_string`] if the run has stopped.
+    pub fn send_int(handle: Arc<Mutex<Self>>, loc: Loc, number: i128) {
+        let mut handle = handle.lock().expect("lock failed");
+        if let Some(Ok(Interaction {
+            value: Value::Sender(tx),
+            ..
+        })) = handle.interaction.take()
+        {
+            if tx.send(Message::Int(Loc::default(), number)).is_ok() {
+                handle.add_event(Event::Int(loc, number));
+            }
+        }
+    }
+
     pub fn start_expression(
-        refresh: Arc<dyn Fn() + Send + Sync>,
+        bus: EventBus,
         context: Context<Loc, Name, Typ>,
         expression: &Expression<Loc, Name, Typ>,
     ) -> Arc<Mutex<Self>> {
         let mut context = context;
         match context.evaluate(expression) {
-            Ok(value) => Self::start(refresh, context, value),
+            Ok(value) => Self::start(bus, context, value),
             Err(error) => Arc::new(Mutex::new(Self {
-                refresh,
+                bus,
+                trace: Trace::new(),
+                path: Vec::new(),
                 events: Vec::new(),
                 interaction: Some(Err(error)),
                 cancelled: false,
@@ -103,12 +212,36 @@ where
     }
 
     pub fn start(
-        refresh: Arc<dyn Fn() + Send + Sync>,
+        bus: EventBus,
+        context: Context<Loc, Name, Typ>,
+        value: Value<Loc, Name>,
+    ) -> Arc<Mutex<Self>> {
+        Self::start_at(bus, Trace::new(), Vec::new(), context, value)
+    }
+
+    /// Starts reading back a value sent or received on this one, which will be its
+    /// next event.
+    fn start_inside(
+        &self,
+        context: Context<Loc, Name, Typ>,
+        value: Value<Loc, Name>,
+    ) -> Arc<Mutex<Self>> {
+        let mut path = self.path.clone();
+        path.push(self.events.len());
+        Self::start_at(self.bus.clone(), self.trace.clone(), path, context, value)
+    }
+
+    fn start_at(
+        bus: EventBus,
+        trace: Trace<Loc, Name>,
+        path: Vec<usize>,
         context: Context<Loc, Name, Typ>,
         value: Value<Loc, Name>,
     ) -> Arc<Mutex<Self>> {
         let handle = Arc::new(Mutex::new(Self {
-            refresh,
+            bus,
+            trace,
+            path,
             events: Vec::new(),
             interaction: None,
             cancelled: false,
@@ -160,11 +293,8 @@ where
                                 .ok()
                                 .expect("receiver dropped");
 
-                            let refresh = Arc::clone(&handle.refresh);
-                            handle.add_event(Event::Receive(
-                                loc,
-                                Handle::start(refresh, context.split(), Value::Sender(tx1)),
-                            ));
+                            let inside = handle.start_inside(context.split(), Value::Sender(tx1));
+                            handle.add_event(Event::Receive(loc, inside));
 
                             value = Value::Sender(tx2);
                         }
@@ -178,6 +308,24 @@ where
                             break;
                         }
 
+                        Message::Swap(runtime::Request::String(loc), tx) => {
+                            handle.request_interaction(
+                                context,
+                                Value::Sender(tx),
+                                Request::String(loc),
+                            );
+                            break;
+                        }
+
+                        Message::Swap(runtime::Request::Int(loc, number), tx) => {
+                            handle.request_interaction(
+                                context,
+                                Value::Sender(tx),
+                                Request::Int(loc, number),
+                            );
+                            break;
+                        }
+
                         Message::Swap(runtime::Request::Continue(loc), tx) => {
                             tx.send(Message::Break(Loc::default()))
                                 .ok()
@@ -187,11 +335,8 @@ where
                         }
 
                         Message::Send(loc, argument, rx) => {
-                            let refresh = Arc::clone(&handle.refresh);
-                            handle.add_event(Event::Send(
-                                loc,
-                                Handle::start(refresh, context.split(), argument),
-                            ));
+                            let inside = handle.start_inside(context.split(), argument);
+                            handle.add_event(Event::Send(loc, inside));
                             value = Value::Receiver(rx);
                         }
 
@@ -205,9 +350,19 @@ where
                             break;
                         }
 
+                        Message::String(loc, text) => {
+                            handle.add_event(Event::String(loc, text));
+                            break;
+                        }
+
+                        Message::Int(loc, number) => {
+                            handle.add_event(Event::Int(loc, number));
+                            break;
+                        }
+
                         Message::Error(error) => {
                             handle.interaction = Some(Err(error));
-                            (handle.refresh)();
+                            handle.bus.publish(RuntimeEvent::Failed);
                             break;
                         }
                     }
@@ -223,8 +378,36 @@ where
     }
 
     fn add_event(&mut self, event: Event<Loc, Name, Typ>) {
+        if self.cancelled {
+            // The run was cancelled while this value was still being read back.
+            // Anything it spawned since is torn down right away instead of being
+            // recorded, so a cancelled handle never grows new sub-handles.
+            match event {
+                Event::Send(_, int) | Event::Receive(_, int) => {
+                    int.lock().expect("lock failed").cancel()
+                }
+                _ => {}
+            }
+            return;
+        }
+        let (loc, action) = match &event {
+            Event::Send(loc, _) => (loc, Action::Send),
+            Event::Receive(loc, _) => (loc, Action::Receive),
+            Event::Choose(loc, chosen) => (loc, Action::Choose(chosen.clone())),
+            Event::Either(loc, chosen) => (loc, Action::Either(chosen.clone())),
+            Event::Break(loc) => (loc, Action::Break),
+            Event::Continue(loc) => (loc, Action::Continue),
+            Event::String(loc, text) => (loc, Action::String(Arc::clone(text))),
+            Event::Int(loc, number) => (loc, Action::Int(*number)),
+        };
+        self.trace.record(Traced {
+            at: self.trace.started.elapsed(),
+            path: self.path.clone(),
+            loc: loc.clone(),
+            action,
+        });
         self.events.push(event);
-        (self.refresh)();
+        self.bus.publish(RuntimeEvent::OutputAvailable);
     }
 
     fn request_interaction(
@@ -247,7 +430,7 @@ where
             value,
             request,
         }));
-        (self.refresh)();
+        self.bus.publish(RuntimeEvent::OutputAvailable);
     }
 
     pub fn cancel(&mut self) {
@@ -269,3 +452,170 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{compiled::Compiled, events::EventBus, par::frontend::Par, spawn::TokioSpawn};
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn cancel_at_any_point() {
+        let Ok(compiled) = Compiled::from_source(&Par, include_str!("../examples/sample.par"))
+        else {
+            panic!("sample failed to compile");
+        };
+        let globals = Arc::new(
+            compiled
+                .program
+                .definitions
+                .iter()
+                .map(|(_, name, expr)| (name.clone(), expr.clone()))
+                .collect(),
+        );
+
+        let mut inside = 0;
+        for (_, _, expression) in &compiled.program.definitions {
+            for steps in 0..8 {
+                let bus = EventBus::default();
+                let handle = Handle::start_expression(
+                    bus.clone(),
+                    Context::new(Arc::new(TokioSpawn::new(bus)), Arc::clone(&globals)),
+                    expression,
+                );
+                for _ in 0..steps {
+                    tokio::task::yield_now().await;
+                }
+
+                // Every value read back so far, to check that the ones inside stop too.
+                let mut handles = Vec::new();
+                let mut pending = vec![Arc::clone(&handle)];
+                while let Some(next) = pending.pop() {
+                    for event in next.lock().expect("lock failed").events() {
+                        if let Event::Send(_, inner) | Event::Receive(_, inner) = event {
+                            pending.push(Arc::clone(inner));
+                        }
+                    }
+                    handles.push(next);
+                }
+                inside += handles.len() - 1;
+
+                handle.lock().expect("lock failed").cancel();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+
+                for handle in &handles {
+                    let handle = handle.lock().expect("lock failed");
+                    assert!(handle.events().is_empty());
+                    assert!(!matches!(handle.interaction(), Some(Ok(_))));
+                }
+            }
+        }
+        assert!(
+            inside > 0,
+            "no value inside another was read back before cancelling"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn answer_after_stopping() {
+        let bus = EventBus::default();
+        // A value asking for input whose run has stopped, leaving nothing to receive it.
+        let waiting = |request| {
+            let (tx, _) = oneshot::channel();
+            Arc::new(Mutex::new(Handle::<usize, String, ()> {
+                bus: bus.clone(),
+                trace: Trace::new(),
+                path: Vec::new(),
+                events: Vec::new(),
+                interaction: Some(Ok(Interaction {
+                    context: Context::new(Arc::new(TokioSpawn::new(bus.clone())), Arc::default()),
+                    value: Value::Sender(tx),
+                    request,
+                })),
+                cancelled: false,
+            }))
+        };
+
+        let asked = waiting(Request::String(0));
+        Handle::send_string(Arc::clone(&asked), 0, Arc::from("late"));
+        let asked = asked.lock().expect("lock failed");
+        assert!(asked.events().is_empty() && asked.trace().is_empty());
+
+        let asked = waiting(Request::Int(0, Number::Int));
+        Handle::send_int(Arc::clone(&asked), 0, 5);
+        let asked = asked.lock().expect("lock failed");
+        assert!(asked.events().is_empty() && asked.trace().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_trace() {
+        let Ok(compiled) = Compiled::from_source(&Par, "def main = [.red!, .green!]") else {
+            panic!("failed to compile");
+        };
+        let (_, _, expression) = &compiled.program.definitions[0];
+        let bus = EventBus::default();
+        let handle = Handle::start_expression(
+            bus.clone(),
+            Context::new(Arc::new(TokioSpawn::new(bus)), compiled.definitions()),
+            expression,
+        );
+        let trace = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let trace = handle.lock().expect("lock failed").trace();
+                if trace.len() == 10 {
+                    break trace;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("the run wasn't traced in time");
+
+        let on = |path: &[usize]| {
+            (trace.iter())
+                .filter(|traced| traced.path == path)
+                .map(|traced| match &traced.action {
+                    Action::Choose(name) => Action::Choose(name.to_string()),
+                    Action::Send => Action::Send,
+                    Action::Break => Action::Break,
+                    _ => panic!("unexpected action"),
+                })
+                .collect::<Vec<_>>()
+        };
+        let choose = |name: &str| Action::Choose(name.to_owned());
+        assert_eq!(
+            on(&[]),
+            vec![
+                choose("item"),
+                Action::Send,
+                choose("item"),
+                Action::Send,
+                choose("empty"),
+                Action::Break
+            ]
+        );
+        assert_eq!(on(&[1]), vec![choose("red"), Action::Break]);
+        assert_eq!(on(&[3]), vec![choose("green"), Action::Break]);
+        assert!(trace.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    }
+
+    #[test]
+    fn trace_keeps_the_last() {
+        let trace = Trace::<usize, String>::new();
+        for loc in 0..TRACE_LIMIT + 5 {
+            trace.record(Traced {
+                at: Duration::ZERO,
+                path: Vec::new(),
+                loc,
+                action: Action::Break,
+            });
+        }
+        let entries = trace.entries.lock().expect("lock failed");
+        assert_eq!(entries.len(), TRACE_LIMIT);
+        assert_eq!(entries.front().map(|traced| traced.loc), Some(5));
+        assert_eq!(
+            entries.back().map(|traced| traced.loc),
+            Some(TRACE_LIMIT + 4)
+        );
+    }
+}
@@ -0,0 +1,25 @@
+//! Par as a library, for checking and running Par programs from other Rust code.
+//!
+//! [`Compiled::from_source`] parses a program with one of the [frontends], checks
+//! it, and compiles it for the runtime. [`Compiled::definitions`] are what
+//! [`runtime::Context`] runs, with processes spawned by [`TokioSpawn`] onto a pool of
+//! threads of its own.
+//!
+//! ```
+//! use par_lang::{compiled::Compiled, par::frontend::Par};
+//!
+//! let compiled = Compiled::from_source(&Par, "def unit = !").unwrap();
+//! assert!(compiled.checked.is_ok());
+//! ```
+//!
+//! [`Compiled::from_source`]: compiled::Compiled::from_source
+//! [`Compiled::definitions`]: compiled::Compiled::definitions
+//! [frontends]: par::frontend::FRONTENDS
+//! [`runtime::Context`]: par::runtime::Context
+//! [`TokioSpawn`]: spawn::TokioSpawn
+
+pub mod build_info;
+pub mod compiled;
+pub mod events;
+pub mod par;
+pub mod spawn;
@@ -1,24 +1,178 @@
+use std::path::PathBuf;
+
+use clap::{arg, command, value_parser, Command};
+use config::{Config, Verbosity};
+#[cfg(feature = "gui")]
 use eframe::egui;
+use par::frontend::Frontend;
+use par_lang::{build_info, compiled, events, par, spawn};
+#[cfg(feature = "gui")]
 use playground::Playground;
 
+mod cli;
+mod config;
+#[cfg(any(feature = "gui", test))]
+mod gallery;
 mod interact;
-mod par;
+#[cfg(feature = "gui")]
 mod playground;
-mod spawn;
+#[cfg(feature = "gui")]
+mod session;
+#[cfg(feature = "gui")]
+mod trace;
+mod transcript;
+#[cfg(feature = "gui")]
+mod tutorial;
+
+fn main() {
+    let loaded = match Config::load() {
+        Ok(loaded) => loaded,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    let config = loaded.config;
+    if config.verbosity() >= Verbosity::Verbose {
+        for source in &loaded.sources {
+            eprintln!("Using configuration from {}", source.display());
+        }
+    }
+
+    spawn::start_pool(config.threads);
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the runtime")
+        .block_on(run(config));
+}
+
+async fn run(mut config: Config) {
+    let matches = command!()
+        .long_version(build_info::report())
+        .arg(
+            arg!(--syntax <NAME> "Surface syntax programs are written in")
+                .global(true),
+        )
+        .arg(
+            arg!(--"max-steps" <N> "Stop a run after this many steps, in case it never finishes")
+                .value_parser(value_parser!(usize))
+                .global(true),
+        )
+        .subcommand(
+            Command::new("run")
+                .about("Run a definition without opening the playground")
+                .arg(arg!(<file> "Par source file to run").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(-d --def <NAME> "Definition to run").default_value("main"))
+                .arg(
+                    arg!(-a --arg <VALUE> "Argument passed to the definition, e.g. `.foo` or `(x)!`")
+                        .action(clap::ArgAction::Append)
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    arg!(-p --preset <NAME> "Run a preset from the configuration instead")
+                        .conflicts_with_all(["def", "arg"]),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Type check a program without running anything")
+                .arg(arg!(<file> "Par source file to check").value_parser(value_parser!(PathBuf)))
+                .arg(
+                    arg!(-d --def <NAME> "Definition the program is run from, to warn about those it never uses")
+                        .default_value("main"),
+                ),
+        )
+        .subcommand(
+            Command::new("interact-replay")
+                .about("Replay a transcript saved from the playground, checking that nothing changed")
+                .arg(arg!(<file> "Par source file the transcript was recorded with").value_parser(value_parser!(PathBuf)))
+                .arg(
+                    arg!(-t --transcript <FILE> "Transcript saved from the playground")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .get_matches();
+
+    par::parse::set_miette_hook();
 
-#[tokio::main]
-async fn main() {
+    if let Some(syntax) = matches.get_one::<String>("syntax") {
+        config.syntax = Some(syntax.clone());
+    }
+    if let Some(&max_steps) = matches.get_one::<usize>("max-steps") {
+        config.max_steps = Some(max_steps);
+    }
+    let frontend = match config.frontend() {
+        Ok(frontend) => frontend,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    match matches.subcommand() {
+        Some(("run", args)) => {
+            let file = args.get_one::<PathBuf>("file").expect("file is required");
+            let (def, arguments) = match args.get_one::<String>("preset") {
+                Some(name) => match config.preset(name) {
+                    Ok(preset) => (&preset.definition, preset.arguments.clone()),
+                    Err(error) => {
+                        eprintln!("{}", error);
+                        std::process::exit(1);
+                    }
+                },
+                None => (
+                    args.get_one::<String>("def").expect("def has a default"),
+                    args.get_many::<String>("arg")
+                        .map(|values| values.cloned().collect::<Vec<_>>())
+                        .unwrap_or_default(),
+                ),
+            };
+            if let Err(error) = cli::run(frontend, file, def, &arguments, &config).await {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        Some(("check", args)) => {
+            let file = args.get_one::<PathBuf>("file").expect("file is required");
+            let def = args.get_one::<String>("def").expect("def has a default");
+            if let Err(error) = cli::check(frontend, file, def, &config) {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        Some(("interact-replay", args)) => {
+            let file = args.get_one::<PathBuf>("file").expect("file is required");
+            let transcript = args
+                .get_one::<PathBuf>("transcript")
+                .expect("transcript is required");
+            if let Err(error) = cli::replay(frontend, file, transcript, &config).await {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        _ => run_playground(config, frontend),
+    }
+}
+
+#[cfg(feature = "gui")]
+fn run_playground(config: Config, frontend: &'static dyn Frontend) {
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default().with_inner_size([1000.0, 700.0]),
         ..Default::default()
     };
 
-    par::parse::set_miette_hook();
-
     eframe::run_native(
         "⅋layground",
         options,
-        Box::new(|cc| Ok(Playground::new(cc))),
+        Box::new(|cc| Ok(Playground::new(cc, &config, frontend))),
     )
     .expect("egui crashed");
 }
+
+#[cfg(not(feature = "gui"))]
+fn run_playground(_config: Config, _frontend: &'static dyn Frontend) {
+    eprintln!("This build has no playground. Use `run` or `check`, or see `--help`.");
+    std::process::exit(1);
+}
@@ -1,6 +1,14 @@
+pub mod frontend;
 pub mod language;
 pub mod lexer;
+pub mod lint;
+pub mod mistakes;
+pub mod outline;
 pub mod parse;
 pub mod process;
+pub mod protocol;
 pub mod runtime;
+pub mod scope;
+pub mod suggest;
 pub mod types;
+pub mod value;
@@ -0,0 +1,39 @@
+use super::{
+    language::Expression,
+    parse::{parse_program_recovering, Loc, Name, Program, SyntaxError},
+};
+
+/// A surface syntax for Par: turns source code into the language the rest of the
+/// compiler works on, so the type checker and runtime never see the syntax itself.
+///
+/// New syntaxes can be tried out by adding an implementation to [`FRONTENDS`], after
+/// which `--syntax`, the `syntax` configuration setting, and the playground offer it.
+pub trait Frontend: Sync {
+    /// What the syntax is called when selecting it.
+    fn name(&self) -> &'static str;
+
+    /// Parses as much of `source` as possible, along with every error found on the way.
+    fn parse(&self, source: &str) -> (Program<Loc, Name, Expression<Loc, Name>>, Vec<SyntaxError>);
+}
+
+/// The standard syntax.
+pub struct Par;
+
+impl Frontend for Par {
+    fn name(&self) -> &'static str {
+        "par"
+    }
+
+    fn parse(&self, source: &str) -> (Program<Loc, Name, Expression<Loc, Name>>, Vec<SyntaxError>) {
+        parse_program_recovering(source)
+    }
+}
+
+pub static FRONTENDS: &[&dyn Frontend] = &[&Par];
+
+pub fn by_name(name: &str) -> Option<&'static dyn Frontend> {
+    FRONTENDS
+        .iter()
+        .copied()
+        .find(|frontend| frontend.name() == name)
+}
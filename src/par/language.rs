@@ -3,7 +3,7 @@ use std::{fmt::Display, hash::Hash, sync::Arc};
 use indexmap::IndexMap;
 
 use super::{
-    process::{self, Captures},
+    process::{self, Captures, Operator, StringPart},
     types::Type,
 };
 
@@ -23,6 +23,16 @@ pub enum Expression<Loc, Name> {
     Fork(Loc, Name, Option<Type<Loc, Name>>, Box<Process<Loc, Name>>),
     Construction(Loc, Construct<Loc, Name>),
     Application(Loc, Box<Self>, Apply<Loc, Name>),
+    StringLiteral(Loc, Arc<str>),
+    /// A string literal with `{name}` holes in it.
+    Format(Loc, Vec<StringPart<Loc, Name>>),
+    /// Stands in for an expression that failed to parse, at the syntax error.
+    Placeholder(Loc),
+    /// `?name` or just `?`, an expression still to be written.
+    Hole(Loc, Option<Name>),
+    IntLiteral(Loc, i128),
+    /// `left + right`, `left - right`, or `left * right`.
+    Arithmetic(Loc, Operator, Box<Self>, Box<Self>),
 }
 
 #[derive(Clone, Debug)]
@@ -91,6 +101,13 @@ pub enum Command<Loc, Name> {
     Send(Loc, Box<Expression<Loc, Name>>, Box<Self>),
     Receive(Loc, Pattern<Loc, Name>, Box<Self>),
     Choose(Loc, Name, Box<Self>),
+    /// Matches on the branches, then continues with the process after them, if any.
+    ///
+    /// That process runs after each branch that finishes without ending its process,
+    /// and is checked once for every such branch, in the scope that branch leaves behind:
+    /// names bound in a branch can be used after it, and must be if they aren't used up
+    /// inside. A branch that ends, by `!`, `<>`, or `loop`, doesn't continue with it, and
+    /// it is an error to write one after branches that all end.
     Either(
         Loc,
         CommandBranches<Loc, Name>,
@@ -153,6 +170,8 @@ impl<Name: Display> Display for Internal<Name> {
 #[derive(Clone, Debug)]
 pub enum CompileError<Loc> {
     MustEndProcess(Loc),
+    /// A process after the branches of a match that every branch ends before reaching.
+    UnreachablePass(Loc),
 }
 
 type Pass<Loc, Name> = Option<Arc<process::Process<Loc, Internal<Name>, ()>>>;
@@ -355,6 +374,23 @@ impl<Loc: Clone, Name: Clone + Hash + Eq> Expression<Loc, Name> {
 
             Self::Application(_, expr, Apply::Noop(_)) => expr.compile()?,
 
+            Self::StringLiteral(loc, text) => Arc::new(process::Expression::StringLiteral(
+                loc.clone(),
+                Arc::clone(text),
+            )),
+
+            Self::Format(loc, parts) => Arc::new(process::Expression::Format(
+                loc.clone(),
+                (parts.iter())
+                    .map(|part| match part {
+                        StringPart::Text(text) => StringPart::Text(Arc::clone(text)),
+                        StringPart::Hole(loc, name) => {
+                            StringPart::Hole(loc.clone(), Internal::Original(name.clone()))
+                        }
+                    })
+                    .collect(),
+            )),
+
             Self::Application(loc, expr, apply) => {
                 let expr = expr.compile()?;
                 let process = apply.compile()?;
@@ -374,6 +410,26 @@ impl<Loc: Clone, Name: Clone + Hash + Eq> Expression<Loc, Name> {
                     )),
                 ))
             }
+
+            Self::Placeholder(loc) => Arc::new(process::Expression::Placeholder(loc.clone())),
+
+            Self::Hole(loc, name) => Arc::new(process::Expression::Hole(
+                loc.clone(),
+                name.clone().map(Internal::Original),
+            )),
+
+            Self::IntLiteral(loc, number) => {
+                Arc::new(process::Expression::IntLiteral(loc.clone(), *number))
+            }
+
+            Self::Arithmetic(loc, operator, left, right) => {
+                Arc::new(process::Expression::Arithmetic(
+                    loc.clone(),
+                    *operator,
+                    left.compile()?,
+                    right.compile()?,
+                ))
+            }
         })
     }
 }
@@ -743,6 +799,9 @@ impl<Loc: Clone, Name: Clone + Hash + Eq> Command<Loc, Name> {
 
             Self::Either(loc, CommandBranches(process_branches), optional_process) => {
                 let pass = match optional_process {
+                    Some(_) if !process_branches.values().any(CommandBranch::falls_through) => {
+                        Err(CompileError::UnreachablePass(loc.clone()))?
+                    }
                     Some(process) => Some(process.compile(pass)?),
                     None => pass,
                 };
@@ -864,6 +923,46 @@ impl<Loc: Clone, Name: Clone + Hash + Eq> CommandBranch<Loc, Name> {
     }
 }
 
+impl<Loc, Name> Process<Loc, Name> {
+    /// Whether this process can finish without ending, and so go on with whatever
+    /// is passed to it.
+    pub fn falls_through(&self) -> bool {
+        match self {
+            Self::Let(_, _, _, process) | Self::Telltypes(_, process) => process.falls_through(),
+            Self::Command(_, command) => command.falls_through(),
+            Self::Noop(_) => true,
+        }
+    }
+}
+
+impl<Loc, Name> Command<Loc, Name> {
+    pub fn falls_through(&self) -> bool {
+        match self {
+            Self::Then(process) | Self::Continue(_, process) => process.falls_through(),
+            Self::Link(_, _) | Self::Break(_) | Self::Loop(_, _) => false,
+            Self::Send(_, _, command)
+            | Self::Receive(_, _, command)
+            | Self::Choose(_, _, command)
+            | Self::Begin(_, _, _, command)
+            | Self::SendType(_, _, command)
+            | Self::ReceiveType(_, _, command) => command.falls_through(),
+            Self::Either(_, _, Some(process)) => process.falls_through(),
+            Self::Either(_, CommandBranches(branches), None) => {
+                branches.values().any(CommandBranch::falls_through)
+            }
+        }
+    }
+}
+
+impl<Loc, Name> CommandBranch<Loc, Name> {
+    pub fn falls_through(&self) -> bool {
+        match self {
+            Self::Then(process) | Self::Continue(_, process) => process.falls_through(),
+            Self::Receive(_, _, branch) | Self::ReceiveType(_, _, branch) => branch.falls_through(),
+        }
+    }
+}
+
 fn original<Loc: Clone, Name: Clone + Eq + Hash>(
     annotation: &Option<Type<Loc, Name>>,
 ) -> Option<Type<Loc, Internal<Name>>> {
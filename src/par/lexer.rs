@@ -27,6 +27,11 @@ pub enum TokenKind {
     Bang,
     Quest,
     Link,
+    Plus,
+    Minus,
+    Star,
+    /// A double-quoted string literal, quotes and escapes included.
+    String,
     Unknown,
 }
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -61,6 +66,7 @@ impl From<TokenKind> for &'static str {
             TokenKind::RAngle => ">",
             TokenKind::Colon => ":",
             TokenKind::Ident => "",
+            TokenKind::String => "",
             TokenKind::Unknown => "",
             TokenKind::Comma => ",",
             TokenKind::Dot => ".",
@@ -69,6 +75,9 @@ impl From<TokenKind> for &'static str {
             TokenKind::Bang => "!",
             TokenKind::Quest => "?",
             TokenKind::Link => "<>",
+            TokenKind::Plus => "+",
+            TokenKind::Minus => "-",
+            TokenKind::Star => "*",
         }
     }
 }
@@ -114,12 +123,10 @@ pub fn lex<'s>(input: &'s str) -> Vec<Token<'s>> {
     (|input: &'s str| -> Result<Vec<Token<'s>>, Error> {
         let mut input = input;
         let input = &mut input;
-        let mut row = 0;
-        let mut last_newline = input.len();
+        let code = *input;
         let mut tokens = Vec::new();
         let mut idx = 0;
         while let Ok(c) = peek(any::<&str, Error>).parse_next(input) {
-            let column = last_newline - input.len(); // starting column
             let Some((raw, kind)) = (match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => {
                     let ident = take_while(
@@ -132,8 +139,6 @@ pub fn lex<'s>(input: &'s str) -> Vec<Token<'s>> {
                 }
                 '\n' => {
                     let _ = any::<&str, Error>.parse_next(input);
-                    row += 1;
-                    last_newline = input.len();
                     idx += 1;
                     None
                 }
@@ -217,6 +222,43 @@ pub fn lex<'s>(input: &'s str) -> Vec<Token<'s>> {
                     let raw = any::<&str, Error>.take().parse_next(input)?;
                     Some((raw, TokenKind::Quest))
                 }
+                '+' => {
+                    let raw = any::<&str, Error>.take().parse_next(input)?;
+                    Some((raw, TokenKind::Plus))
+                }
+                '-' => {
+                    let raw = any::<&str, Error>.take().parse_next(input)?;
+                    Some((raw, TokenKind::Minus))
+                }
+                '*' => {
+                    let raw = any::<&str, Error>.take().parse_next(input)?;
+                    Some((raw, TokenKind::Star))
+                }
+                '"' => {
+                    // Up to the closing quote, skipping escaped ones. A string left open at
+                    // the end of its line is lexed as unknown, so it fails to parse.
+                    let rest = *input;
+                    let mut escaped = false;
+                    let mut end = None;
+                    for (offset, c) in rest.char_indices().skip(1) {
+                        match c {
+                            '\n' => break,
+                            '"' if !escaped => {
+                                end = Some(offset + 1);
+                                break;
+                            }
+                            '\\' => escaped = !escaped,
+                            _ => escaped = false,
+                        }
+                    }
+                    let (len, kind) = match end {
+                        Some(end) => (end, TokenKind::String),
+                        None => (rest.find('\n').unwrap_or(rest.len()), TokenKind::Unknown),
+                    };
+                    let (raw, after) = rest.split_at(len);
+                    *input = after;
+                    Some((raw, kind))
+                }
                 _ => {
                     let raw = any::<&str, Error>.take().parse_next(input)?;
                     Some((raw, TokenKind::Unknown))
@@ -227,20 +269,115 @@ pub fn lex<'s>(input: &'s str) -> Vec<Token<'s>> {
             tokens.push(Token {
                 kind,
                 raw,
-                // one-based row/column
-                loc: Loc::Code {
-                    line: row + 1,
-                    column: column + 1,
-                },
+                loc: Loc::External,
                 span: idx..idx + raw.len(),
             });
             idx += raw.len();
         }
+
+        // Tokens are in order, so their lines and columns are found in a single pass.
+        let (mut line, mut column) = (1, 1);
+        let mut chars = code.char_indices().peekable();
+        for token in &mut tokens {
+            while let Some((_, c)) = chars.next_if(|(offset, _)| *offset < token.span.start) {
+                if c == '\n' {
+                    (line, column) = (line + 1, 1);
+                } else {
+                    column += 1;
+                }
+            }
+            token.loc = Loc::Code {
+                offset: token.span.start,
+                line,
+                column,
+            };
+        }
         Ok(tokens)
     })(input)
     .expect("lexing failed")
 }
 
+/// A part of a string literal: either text, or a `{name}` hole to be filled in with
+/// another string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Piece<'a> {
+    Text(String),
+    /// The name, and how many bytes into the literal it starts.
+    Hole(&'a str, usize),
+}
+
+/// The pieces of a string literal, or `None` if it has an unknown escape or a brace
+/// that doesn't go around a name. Escapes are `\"`, `\\`, `\n`, `\t`, `\r`, `\{`,
+/// and `\}`.
+pub fn pieces(raw: &str) -> Option<Vec<Piece<'_>>> {
+    let inner = raw.strip_prefix('"')?.strip_suffix('"')?;
+    let mut pieces = Vec::new();
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => text.push(match chars.next()?.1 {
+                '"' => '"',
+                '\\' => '\\',
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                '{' => '{',
+                '}' => '}',
+                _ => return None,
+            }),
+            '{' => {
+                let start = index + 1;
+                let end = start + inner[start..].find('}')?;
+                let name = &inner[start..end];
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return None;
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                // One more for the opening quote.
+                pieces.push(Piece::Hole(name, start + 1));
+                chars.find(|(index, _)| *index == end);
+            }
+            '}' => return None,
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() || pieces.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Some(pieces)
+}
+
+/// The text a string literal without holes stands for, or `None` if it isn't one.
+pub fn unescape(raw: &str) -> Option<String> {
+    match pieces(raw)?.as_mut_slice() {
+        [Piece::Text(text)] => Some(std::mem::take(text)),
+        _ => None,
+    }
+}
+
+/// Writes `text` as a string literal that [`unescape`] turns back into it.
+pub fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            '{' => quoted.push_str("\\{"),
+            '}' => quoted.push_str("\\}"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -269,4 +406,111 @@ mod test {
         );
         eprintln!("{:#?}", tokens);
     }
+
+    #[test]
+    fn locations() {
+        let code = "a /* ⅋\n */ é c\n  d";
+        let tokens = lex(code);
+        assert_eq!(
+            tokens.iter().map(|x| x.loc.clone()).collect::<Vec<_>>(),
+            vec![
+                Loc::Code {
+                    offset: 0,
+                    line: 1,
+                    column: 1
+                },
+                Loc::Code {
+                    offset: 13,
+                    line: 2,
+                    column: 5
+                },
+                Loc::Code {
+                    offset: 16,
+                    line: 2,
+                    column: 7
+                },
+                Loc::Code {
+                    offset: 20,
+                    line: 3,
+                    column: 3
+                },
+            ]
+        );
+        for token in &tokens {
+            assert_eq!(Loc::from_offset(code, token.span.start), token.loc);
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let tokens = lex("1+x2 - 3*-4");
+        assert_eq!(
+            tokens.iter().map(|x| (x.kind, x.raw)).collect::<Vec<_>>(),
+            vec![
+                (TokenKind::Ident, "1"),
+                (TokenKind::Plus, "+"),
+                (TokenKind::Ident, "x2"),
+                (TokenKind::Minus, "-"),
+                (TokenKind::Ident, "3"),
+                (TokenKind::Star, "*"),
+                (TokenKind::Minus, "-"),
+                (TokenKind::Ident, "4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn strings() {
+        let tokens = lex(r#"("a \"b\" \\" "open"#);
+        assert_eq!(
+            tokens.iter().map(|x| (x.kind, x.raw)).collect::<Vec<_>>(),
+            vec![
+                (TokenKind::LParen, "("),
+                (TokenKind::String, r#""a \"b\" \\""#),
+                (TokenKind::Unknown, r#""open"#),
+            ]
+        );
+        assert_eq!(unescape(tokens[1].raw).as_deref(), Some(r#"a "b" \"#));
+        assert_eq!(unescape(r#""\q""#), None);
+        for text in [
+            "",
+            "tab\there",
+            "line\nbreak",
+            r#"\"quoted\""#,
+            "⅋",
+            "{not a hole}",
+        ] {
+            assert_eq!(unescape(&quote(text)).as_deref(), Some(text));
+        }
+
+        assert_eq!(
+            pieces(r#""Hi, {name}!""#),
+            Some(vec![
+                Piece::Text("Hi, ".to_owned()),
+                Piece::Hole("name", 6),
+                Piece::Text("!".to_owned()),
+            ])
+        );
+        assert_eq!(
+            pieces(r#""{a}{b}""#),
+            Some(vec![Piece::Hole("a", 2), Piece::Hole("b", 5)])
+        );
+        for raw in [r#""{}""#, r#""{a""#, r#""a}""#, r#""{a b}""#] {
+            assert_eq!(pieces(raw), None);
+        }
+        assert_eq!(unescape(r#""{a}""#), None);
+    }
+
+    #[test]
+    fn comments() {
+        let tokens = lex("a /* b /* c */ d */ e // f\n/* g\n*/ h");
+        assert_eq!(
+            tokens.iter().map(|x| (x.kind, x.raw)).collect::<Vec<_>>(),
+            vec![
+                (TokenKind::Ident, "a"),
+                (TokenKind::Ident, "e"),
+                (TokenKind::Ident, "h"),
+            ]
+        );
+    }
 }
@@ -0,0 +1,756 @@
+use std::{fmt::Display, sync::Arc};
+
+use super::{
+    language::{
+        Apply, ApplyBranch, Command, CommandBranch, Construct, ConstructBranch, Expression,
+        Pattern, Process,
+    },
+    parse::{Allow, Program},
+    process::StringPart,
+    types::Type,
+};
+
+/// Something that doesn't stop a program from compiling or running, but is
+/// almost certainly a mistake.
+#[derive(Clone, Debug)]
+pub enum Warning<Loc, Name> {
+    /// A type parameter is bound again inside the scope of another one of the same name.
+    ShadowedTypeParameter(Loc, Loc, Name),
+    /// A loop label is reused inside a `begin`, `recursive`, or `iterative` with the same label.
+    ShadowedLoopLabel(Loc, Loc, Name),
+    /// A definition that the one run never gets to, directly or through other
+    /// definitions, along with the one run.
+    UnusedDefinition(Loc, Name, Name),
+    /// An `//@allow` comment names a warning that doesn't exist.
+    UnknownAllow(Loc, String),
+}
+
+/// What each kind of warning is called in an `//@allow` comment.
+const NAMES: &[&str] = &[
+    "shadowed_type_parameter",
+    "shadowed_loop_label",
+    "unused_definition",
+];
+
+impl<Loc, Name> Warning<Loc, Name> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ShadowedTypeParameter(..) => "shadowed_type_parameter",
+            Self::ShadowedLoopLabel(..) => "shadowed_loop_label",
+            Self::UnusedDefinition(..) => "unused_definition",
+            Self::UnknownAllow(..) => "unknown_allow",
+        }
+    }
+
+    /// Whether both warnings come from the same mistake, such as one type parameter
+    /// shadowed in several places, or the same unknown name in several `//@allow`s.
+    fn same_cause(&self, other: &Self) -> bool
+    where
+        Loc: Eq,
+        Name: Eq,
+    {
+        match (self, other) {
+            (
+                Self::ShadowedTypeParameter(_, outer1, name1),
+                Self::ShadowedTypeParameter(_, outer2, name2),
+            )
+            | (
+                Self::ShadowedLoopLabel(_, outer1, name1),
+                Self::ShadowedLoopLabel(_, outer2, name2),
+            ) => outer1 == outer2 && name1 == name2,
+            (Self::UnknownAllow(_, name1), Self::UnknownAllow(_, name2)) => name1 == name2,
+            _ => false,
+        }
+    }
+
+    fn loc(&self) -> &Loc {
+        match self {
+            Self::ShadowedTypeParameter(loc, ..)
+            | Self::ShadowedLoopLabel(loc, ..)
+            | Self::UnusedDefinition(loc, ..)
+            | Self::UnknownAllow(loc, _) => loc,
+        }
+    }
+}
+
+/// Reports the warnings once for each cause. Warnings with the same cause become a
+/// single report that points at every place it shows up.
+pub fn reports<Name: Display + Eq>(
+    warnings: &[Warning<super::parse::Loc, Name>],
+    source_code: Arc<str>,
+) -> Vec<miette::Report> {
+    let mut groups: Vec<Vec<&Warning<_, _>>> = Vec::new();
+    for warning in warnings {
+        match groups.iter_mut().find(|group| group[0].same_cause(warning)) {
+            Some(group) => group.push(warning),
+            None => groups.push(vec![warning]),
+        }
+    }
+    (groups.into_iter())
+        .map(|group| group[0].to_report(&group[1..], source_code.clone()))
+        .collect()
+}
+
+/// The warnings about `program`, and which definitions each of its definitions uses, to
+/// warn about the ones never used once it's known which definition is run.
+pub fn check_program<Loc: Clone + Eq, Name: Clone + Eq>(
+    program: &Program<Loc, Name, Expression<Loc, Name>>,
+) -> (Vec<Warning<Loc, Name>>, Uses<Loc, Name>) {
+    let mut linter = Linter {
+        type_params: Vec::new(),
+        labels: Vec::new(),
+        type_labels: Vec::new(),
+        references: Vec::new(),
+        warnings: Vec::new(),
+    };
+    for (loc, _, params, typ) in &program.type_defs {
+        linter.item(&program.allows, loc, |this| {
+            this.scoped(|this| {
+                for param in params {
+                    this.bind_type_param(loc, param);
+                }
+                this.typ(typ);
+            })
+        });
+    }
+    for (loc, _, typ) in &program.declarations {
+        linter.item(&program.allows, loc, |this| this.typ(typ));
+    }
+    let mut uses = Uses::default();
+    for (loc, name, expression) in &program.definitions {
+        linter.item(&program.allows, loc, |this| this.expression(expression));
+        uses.definitions.push(Used {
+            loc: loc.clone(),
+            name: name.clone(),
+            references: std::mem::take(&mut linter.references),
+            allowed: allowed(&program.allows, loc).any(|name| name == "unused_definition"),
+        });
+    }
+    for allow in &program.allows {
+        for name in &allow.warnings {
+            if !NAMES.contains(&name.as_str()) {
+                linter
+                    .warnings
+                    .push(Warning::UnknownAllow(allow.loc.clone(), name.clone()));
+            }
+        }
+    }
+    (linter.warnings, uses)
+}
+
+/// The warnings allowed for the item at `loc`.
+fn allowed<'a, Loc: Eq>(
+    allows: &'a [Allow<Loc>],
+    loc: &'a Loc,
+) -> impl Iterator<Item = &'a String> {
+    (allows.iter())
+        .filter(move |allow| &allow.item == loc)
+        .flat_map(|allow| &allow.warnings)
+}
+
+/// Which definitions each definition of a program uses, as found by [`check_program`].
+#[derive(Clone, Debug)]
+pub struct Uses<Loc, Name> {
+    definitions: Vec<Used<Loc, Name>>,
+}
+
+#[derive(Clone, Debug)]
+struct Used<Loc, Name> {
+    loc: Loc,
+    name: Name,
+    /// Every name the definition mentions.
+    references: Vec<Name>,
+    /// Whether `//@allow(unused_definition)` is on it.
+    allowed: bool,
+}
+
+impl<Loc, Name> Default for Uses<Loc, Name> {
+    fn default() -> Self {
+        Self {
+            definitions: Vec::new(),
+        }
+    }
+}
+
+impl<Name: Clone + Eq> Uses<super::parse::Loc, Name> {
+    /// Warns about the definitions that `entry` can't reach, following the names each
+    /// definition mentions. If there's no such definition, nothing is run, so none of
+    /// them is unused. Definitions not in the source, such as the one `par run` adds to
+    /// pass arguments, aren't warned about.
+    pub fn unused_from(&self, entry: &Name) -> Vec<Warning<super::parse::Loc, Name>> {
+        let definitions = &self.definitions;
+        let Some(start) = (definitions.iter()).position(|used| &used.name == entry) else {
+            return Vec::new();
+        };
+        let mut reached = vec![false; definitions.len()];
+        let mut pending = vec![start];
+        while let Some(index) = pending.pop() {
+            if std::mem::replace(&mut reached[index], true) {
+                continue;
+            }
+            pending.extend(
+                definitions[index]
+                    .references
+                    .iter()
+                    .filter_map(|reference| {
+                        (definitions.iter()).position(|used| &used.name == reference)
+                    }),
+            );
+        }
+        (definitions.iter().zip(reached))
+            .filter(|(used, reached)| {
+                !reached && !used.allowed && used.loc != super::parse::Loc::External
+            })
+            .map(|(used, _)| {
+                Warning::UnusedDefinition(used.loc.clone(), used.name.clone(), entry.clone())
+            })
+            .collect()
+    }
+}
+
+struct Linter<Loc, Name> {
+    type_params: Vec<(Loc, Name)>,
+    labels: Vec<(Loc, Name)>,
+    type_labels: Vec<(Loc, Name)>,
+    /// Every name referred to in the definition being checked. Local variables are
+    /// in here too, so a global shadowed by one still counts as used.
+    references: Vec<Name>,
+    warnings: Vec<Warning<Loc, Name>>,
+}
+
+impl<Loc: Clone + Eq, Name: Clone + Eq> Linter<Loc, Name> {
+    /// Checks the item at `loc` with `f`, leaving out the warnings allowed for it.
+    fn item(&mut self, allows: &[Allow<Loc>], loc: &Loc, f: impl FnOnce(&mut Self)) {
+        let before = self.warnings.len();
+        f(self);
+        let allowed = allowed(allows, loc).collect::<Vec<_>>();
+        let found = self.warnings.split_off(before);
+        self.warnings.extend(
+            found
+                .into_iter()
+                .filter(|warning| !allowed.iter().any(|name| *name == warning.name())),
+        );
+    }
+
+    /// Runs `f`, then forgets everything it bound.
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        let (type_params, labels, type_labels) = (
+            self.type_params.len(),
+            self.labels.len(),
+            self.type_labels.len(),
+        );
+        f(self);
+        self.type_params.truncate(type_params);
+        self.labels.truncate(labels);
+        self.type_labels.truncate(type_labels);
+    }
+
+    fn bind_type_param(&mut self, loc: &Loc, name: &Name) {
+        if let Some((outer, _)) = self.type_params.iter().rev().find(|(_, n)| n == name) {
+            self.warnings.push(Warning::ShadowedTypeParameter(
+                loc.clone(),
+                outer.clone(),
+                name.clone(),
+            ));
+        }
+        self.type_params.push((loc.clone(), name.clone()));
+    }
+
+    fn bind_label(&mut self, loc: &Loc, label: &Option<Name>, in_type: bool) {
+        let Some(name) = label else {
+            return;
+        };
+        let labels = if in_type {
+            &mut self.type_labels
+        } else {
+            &mut self.labels
+        };
+        if let Some((outer, _)) = labels.iter().rev().find(|(_, n)| n == name) {
+            self.warnings.push(Warning::ShadowedLoopLabel(
+                loc.clone(),
+                outer.clone(),
+                name.clone(),
+            ));
+        }
+        labels.push((loc.clone(), name.clone()));
+    }
+
+    fn typ(&mut self, typ: &Type<Loc, Name>) {
+        match typ {
+            Type::Chan(_, body) => self.typ(body),
+            Type::Var(_, _)
+            | Type::Break(_)
+            | Type::Continue(_)
+            | Type::Self_(_, _)
+            | Type::String(_)
+            | Type::Int(_)
+            | Type::Nat(_) => {}
+            Type::Name(_, _, args) => {
+                for arg in args {
+                    self.typ(arg);
+                }
+            }
+            Type::Send(_, t, u) | Type::Receive(_, t, u) => {
+                self.typ(t);
+                self.typ(u);
+            }
+            Type::Either(_, branches) | Type::Choice(_, branches) => {
+                for branch in branches.values() {
+                    self.typ(branch);
+                }
+            }
+            Type::Recursive(loc, _, label, body) | Type::Iterative(loc, _, label, body) => self
+                .scoped(|this| {
+                    this.bind_label(loc, label, true);
+                    this.typ(body);
+                }),
+            Type::SendType(loc, name, body) | Type::ReceiveType(loc, name, body) => {
+                self.scoped(|this| {
+                    this.bind_type_param(loc, name);
+                    this.typ(body);
+                })
+            }
+        }
+    }
+
+    // Patterns, constructions, applies, processes, and commands leave their
+    // bindings in place for whatever follows them; the caller scopes them.
+
+    fn pattern(&mut self, pattern: &Pattern<Loc, Name>) {
+        match pattern {
+            Pattern::Name(_, _, annotation) => {
+                if let Some(typ) = annotation {
+                    self.typ(typ);
+                }
+            }
+            Pattern::Receive(_, first, rest) => {
+                self.pattern(first);
+                self.pattern(rest);
+            }
+            Pattern::Continue(_) => {}
+            Pattern::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.pattern(rest);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression<Loc, Name>) {
+        match expression {
+            Expression::Reference(_, name) => self.references.push(name.clone()),
+            Expression::Format(_, parts) => {
+                for part in parts {
+                    if let StringPart::Hole(_, name) = part {
+                        self.references.push(name.clone());
+                    }
+                }
+            }
+            Expression::StringLiteral(_, _)
+            | Expression::IntLiteral(_, _)
+            | Expression::Placeholder(_)
+            | Expression::Hole(_, _) => {}
+            Expression::Arithmetic(_, _, left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Let(_, pattern, value, body) => {
+                self.expression(value);
+                self.scoped(|this| {
+                    this.pattern(pattern);
+                    this.expression(body);
+                });
+            }
+            Expression::Do(_, process, body) => self.scoped(|this| {
+                this.process(process);
+                this.expression(body);
+            }),
+            Expression::Fork(_, _, annotation, process) => {
+                if let Some(typ) = annotation {
+                    self.typ(typ);
+                }
+                self.scoped(|this| this.process(process));
+            }
+            Expression::Construction(_, construct) => self.scoped(|this| this.construct(construct)),
+            Expression::Application(_, expression, apply) => {
+                self.expression(expression);
+                self.scoped(|this| this.apply(apply));
+            }
+        }
+    }
+
+    fn construct(&mut self, construct: &Construct<Loc, Name>) {
+        match construct {
+            Construct::Then(_, expression) => self.expression(expression),
+            Construct::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.construct(rest);
+            }
+            Construct::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.construct(rest);
+            }
+            Construct::Choose(_, _, rest) => self.construct(rest),
+            Construct::Either(_, branches) => {
+                for branch in branches.0.values() {
+                    self.scoped(|this| this.construct_branch(branch));
+                }
+            }
+            Construct::Break(_) | Construct::Loop(_, _) => {}
+            Construct::Begin(loc, _, label, rest) => {
+                self.bind_label(loc, label, false);
+                self.construct(rest);
+            }
+            Construct::SendType(_, typ, rest) => {
+                self.typ(typ);
+                self.construct(rest);
+            }
+            Construct::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.construct(rest);
+            }
+        }
+    }
+
+    fn construct_branch(&mut self, branch: &ConstructBranch<Loc, Name>) {
+        match branch {
+            ConstructBranch::Then(_, expression) => self.expression(expression),
+            ConstructBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.construct_branch(rest);
+            }
+            ConstructBranch::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.construct_branch(rest);
+            }
+        }
+    }
+
+    fn apply(&mut self, apply: &Apply<Loc, Name>) {
+        match apply {
+            Apply::Noop(_) | Apply::Loop(_, _) => {}
+            Apply::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.apply(rest);
+            }
+            Apply::Choose(_, _, rest) => self.apply(rest),
+            Apply::Either(_, branches) => {
+                for branch in branches.0.values() {
+                    self.scoped(|this| this.apply_branch(branch));
+                }
+            }
+            Apply::Begin(loc, _, label, rest) => {
+                self.bind_label(loc, label, false);
+                self.apply(rest);
+            }
+            Apply::SendType(_, typ, rest) => {
+                self.typ(typ);
+                self.apply(rest);
+            }
+        }
+    }
+
+    fn apply_branch(&mut self, branch: &ApplyBranch<Loc, Name>) {
+        match branch {
+            ApplyBranch::Then(_, _, expression) | ApplyBranch::Continue(_, expression) => {
+                self.expression(expression)
+            }
+            ApplyBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.apply_branch(rest);
+            }
+            ApplyBranch::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.apply_branch(rest);
+            }
+        }
+    }
+
+    fn process(&mut self, process: &Process<Loc, Name>) {
+        match process {
+            Process::Let(_, pattern, value, rest) => {
+                self.expression(value);
+                self.pattern(pattern);
+                self.process(rest);
+            }
+            Process::Command(name, command) => {
+                self.references.push(name.clone());
+                self.command(command);
+            }
+            Process::Telltypes(_, rest) => self.process(rest),
+            Process::Noop(_) => {}
+        }
+    }
+
+    fn command(&mut self, command: &Command<Loc, Name>) {
+        match command {
+            Command::Then(process) => self.process(process),
+            Command::Link(_, expression) => self.expression(expression),
+            Command::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.command(rest);
+            }
+            Command::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.command(rest);
+            }
+            Command::Choose(_, _, rest) => self.command(rest),
+            Command::Either(_, branches, pass) => {
+                for branch in branches.0.values() {
+                    self.scoped(|this| this.command_branch(branch));
+                }
+                if let Some(process) = pass {
+                    self.process(process);
+                }
+            }
+            Command::Break(_) | Command::Loop(_, _) => {}
+            Command::Continue(_, process) => self.process(process),
+            Command::Begin(loc, _, label, rest) => {
+                self.bind_label(loc, label, false);
+                self.command(rest);
+            }
+            Command::SendType(_, typ, rest) => {
+                self.typ(typ);
+                self.command(rest);
+            }
+            Command::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.command(rest);
+            }
+        }
+    }
+
+    fn command_branch(&mut self, branch: &CommandBranch<Loc, Name>) {
+        match branch {
+            CommandBranch::Then(process) | CommandBranch::Continue(_, process) => {
+                self.process(process)
+            }
+            CommandBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.command_branch(rest);
+            }
+            CommandBranch::ReceiveType(loc, name, rest) => {
+                self.bind_type_param(loc, name);
+                self.command_branch(rest);
+            }
+        }
+    }
+}
+
+impl<Name: Display> Warning<super::parse::Loc, Name> {
+    /// Reports this warning along with `also`, more warnings with the same cause.
+    pub fn to_report(&self, also: &[&Self], source_code: Arc<str>) -> miette::Report {
+        use super::types::two_labels_from_two_locs;
+        use crate::compiled::labels_from_loc;
+        let places = match also.len() {
+            0 => String::new(),
+            more => format!(" It happens in {} places.", more + 1),
+        };
+        let more_labels = || {
+            (also.iter()).flat_map(|warning| {
+                let mut labels = labels_from_loc(warning.loc());
+                labels
+                    .iter_mut()
+                    .for_each(|label| label.set_label(Some("and this".to_owned())));
+                labels
+            })
+        };
+        match self {
+            Self::ShadowedTypeParameter(loc, outer, name) => miette::miette!(
+                severity = miette::Severity::Warning,
+                labels = two_labels_from_two_locs(
+                    loc,
+                    outer,
+                    "this".to_owned(),
+                    "previously bound here".to_owned()
+                )
+                .into_iter()
+                .chain(more_labels())
+                .collect::<Vec<_>>(),
+                "Type parameter `{}` shadows another one with the same name.{}",
+                name,
+                places
+            ),
+            Self::ShadowedLoopLabel(loc, outer, name) => miette::miette!(
+                severity = miette::Severity::Warning,
+                labels = two_labels_from_two_locs(
+                    loc,
+                    outer,
+                    "this".to_owned(),
+                    "previously bound here".to_owned()
+                )
+                .into_iter()
+                .chain(more_labels())
+                .collect::<Vec<_>>(),
+                "Loop label `{}` shadows an enclosing one with the same name.{}",
+                name,
+                places
+            ),
+            Self::UnusedDefinition(loc, name, entry) => miette::miette!(
+                severity = miette::Severity::Warning,
+                labels = labels_from_loc(loc)
+                    .into_iter()
+                    .chain(more_labels())
+                    .collect::<Vec<_>>(),
+                help = "Remove it, or use `//@allow(unused_definition)` to keep it.",
+                "Definition `{}` is never used by `{}`.{}",
+                name,
+                entry,
+                places
+            ),
+            Self::UnknownAllow(loc, name) => {
+                let help = match super::suggest::closest(name, NAMES.iter().copied()) {
+                    Some(closest) => format!("Did you mean `{}`?", closest),
+                    None => format!("Warnings that can be allowed are {}.", NAMES.join(", ")),
+                };
+                miette::miette!(
+                    severity = miette::Severity::Warning,
+                    labels = labels_from_loc(loc)
+                        .into_iter()
+                        .chain(more_labels())
+                        .collect::<Vec<_>>(),
+                    help = help,
+                    "There is no warning called `{}` to allow.{}",
+                    name,
+                    places
+                )
+            }
+        }
+        .with_source_code(source_code.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::par::parse::{parse_program, Name};
+
+    fn warnings(source: &str) -> Vec<String> {
+        let program =
+            parse_program(source).unwrap_or_else(|_| panic!("failed to parse {}", source));
+        let (warnings, uses) = check_program(&program);
+        (warnings.into_iter())
+            .chain(uses.unused_from(&Name::from("main".to_owned())))
+            .map(|warning| match warning {
+                Warning::ShadowedTypeParameter(_, _, name) => format!("type {}", name),
+                Warning::ShadowedLoopLabel(_, _, name) => format!("label {}", name),
+                Warning::UnusedDefinition(_, name, entry) => {
+                    format!("unused {} by {}", name, entry)
+                }
+                Warning::UnknownAllow(_, name) => format!("unknown {}", name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shadowing_warnings() {
+        assert_eq!(
+            warnings("type F<T> = [type T] T"),
+            vec!["type T".to_owned()]
+        );
+        assert_eq!(
+            warnings("dec id : [type T] [type T] [T] T"),
+            vec!["type T".to_owned()]
+        );
+        assert_eq!(
+            warnings("type L = recursive :l either { .a iterative :l self :l }"),
+            vec!["label l".to_owned()]
+        );
+        assert_eq!(
+            warnings(
+                "def f = [type T] [x] x begin :a { .go y => y begin :a { .go z => z loop :a } }"
+            ),
+            vec!["label a".to_owned()]
+        );
+        assert_eq!(
+            warnings("def f = [type T] chan c { c[type T] c! }"),
+            vec!["type T".to_owned()]
+        );
+        assert!(warnings(include_str!("../../examples/sample.par")).is_empty());
+        // Siblings don't shadow each other.
+        assert!(warnings("def f = { .a => [type T] !, .b => [type T] ! }").is_empty());
+        assert!(warnings("dec f : [type T] T dec g : [type T] T").is_empty());
+    }
+
+    #[test]
+    fn test_allow_comments() {
+        let shadowing = "type F<T> = [type T] T\ndec id : [type T] [type T] [T] T\n";
+        assert_eq!(
+            warnings(&format!("//@allow(shadowed_type_parameter)\n{shadowing}")),
+            vec!["type T".to_owned()]
+        );
+        assert!(warnings(
+            "  //@allow(shadowed_loop_label, shadowed_type_parameter)\n\
+             type F<T> = [type T] T\n\
+             // Two of them.\n\
+             //@allow(shadowed_type_parameter)\n\
+             dec id : [type T] [type T] [T] T"
+        )
+        .is_empty());
+        // Only a comment right above the item counts.
+        assert_eq!(
+            warnings("//@allow(shadowed_type_parameter)\n\ntype F<T> = [type T] T").len(),
+            1
+        );
+        assert_eq!(
+            warnings("//@allow(shadowed_typ_parameter)\ntype F<T> = [type T] T"),
+            vec![
+                "type T".to_owned(),
+                "unknown shadowed_typ_parameter".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unused_definitions() {
+        let program = "def main = helper
+                       def helper = chan c { let x = twice(!) c <> x }
+                       def twice = [x] x
+                       def old = !";
+        assert_eq!(warnings(program), vec!["unused old by main".to_owned()]);
+        assert!(
+            warnings(&program.replace("def old", "//@allow(unused_definition)\ndef old"))
+                .is_empty()
+        );
+        // Strings refer to definitions through their holes.
+        assert!(warnings("def main = \"{hello}\" def hello = \"hi\"").is_empty());
+        // Without a `main`, nothing is run from it.
+        assert!(warnings("def a = ! def b = !").is_empty());
+
+        // The definition run from can be any of them.
+        let (_, uses) = check_program(&parse_program(program).unwrap());
+        let unused = |entry: &str| {
+            (uses.unused_from(&Name::from(entry.to_owned())).into_iter())
+                .map(|warning| match warning {
+                    Warning::UnusedDefinition(_, name, _) => name.to_string(),
+                    _ => panic!("not about an unused definition"),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(unused("twice"), vec!["main", "helper", "old"]);
+        assert_eq!(unused("old"), vec!["main", "helper", "twice"]);
+    }
+
+    #[test]
+    fn test_grouped_reports() {
+        let source = "dec f : [type T] ([type T] T, [type T] T) !
+                      dec g : [type T] [type T] T
+                      //@allow(nope, nope)
+                      type U = !";
+        let program = parse_program(source).unwrap();
+        let (warnings, _) = check_program(&program);
+        assert_eq!(warnings.len(), 5);
+        let reports = reports(&warnings, Arc::from(source))
+            .into_iter()
+            .map(|report| report.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reports,
+            vec![
+                "Type parameter `T` shadows another one with the same name. \
+                 It happens in 2 places.",
+                "Type parameter `T` shadows another one with the same name.",
+                "There is no warning called `nope` to allow. It happens in 2 places.",
+            ]
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use winnow::Parser;
+
+use super::{
+    lexer::{pieces, Token, TokenKind},
+    parse::{keyword, Loc},
+    types::{Operation, Type, TypeError},
+};
+
+/// What was probably meant, for a mistake newcomers to Par often make.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub suggestion: String,
+    /// A short piece of code showing it done right.
+    pub example: &'static str,
+}
+
+impl Hint {
+    pub fn to_help(&self) -> String {
+        let example: String = (self.example.lines())
+            .map(|line| format!("    {}\n", line))
+            .collect();
+        format!("{}\n\nFor example:\n\n{}", self.suggestion, example)
+    }
+}
+
+/// Recognizes a common mistake from the token parsing failed at, `offset` tokens in.
+pub(super) fn in_syntax(toks: &[Token], offset: usize) -> Option<Hint> {
+    let found = toks.get(offset);
+    let before = |n: usize| offset.checked_sub(n).and_then(|index| toks.get(index));
+    let is_name = |tok: Option<&Token>| {
+        tok.is_some_and(|tok| tok.kind == TokenKind::Ident && !is_keyword(tok))
+    };
+
+    if found.is_some_and(|tok| tok.kind == TokenKind::Equal) && inside_braces(&toks[..offset]) {
+        return Some(Hint {
+            suggestion: "Branches are written with `=>`, not `=`.".to_owned(),
+            example: "bool {\n  .true!  => .false!\n  .false! => .true!\n}",
+        });
+    }
+
+    if let Some(found) = found.filter(|tok| tok.kind == TokenKind::Ident && is_keyword(tok)) {
+        let binds = before(1)
+            .is_some_and(|tok| matches!(tok.raw, "let" | "def" | "dec" | "chan" | "[" | "," | "("));
+        if binds {
+            return Some(Hint {
+                suggestion: format!(
+                    "`{}` is a keyword, so it can't be used as a name. Pick another one, such as `my_{}`.",
+                    found.raw, found.raw
+                ),
+                example: "let my_loop = .true!",
+            });
+        }
+    }
+
+    if found.is_some_and(|tok| tok.kind == TokenKind::String && pieces(tok.raw).is_none()) {
+        return Some(Hint {
+            suggestion: "Inside a string, braces go around the name of another string to put \
+                         in their place. Write `\\{` and `\\}` for the braces themselves, and \
+                         use only the escapes `\\\"`, `\\\\`, `\\n`, `\\t`, and `\\r`."
+                .to_owned(),
+            example: "def greet = [name] \"Hello, {name}! \\{not a name\\}\"",
+        });
+    }
+
+    // A process that goes on after it already ended with `!` or `<>`.
+    if found.is_some_and(|tok| tok.kind == TokenKind::Ident) && inside_braces(&toks[..offset]) {
+        if before(1).is_some_and(|tok| tok.raw == "!") {
+            return Some(Hint {
+                suggestion: "`!` ends the process, so nothing can run after it. \
+                             Move what comes next before it."
+                    .to_owned(),
+                example: "chan result {\n  let x = .red!\n  result(x)!\n}",
+            });
+        }
+        if ends_in_link(&toks[..offset]) {
+            return Some(Hint {
+                suggestion: "`<>` hands the rest of the process over to what it links to, \
+                             so nothing can run after it. Move what comes next before it."
+                    .to_owned(),
+                example: "chan result {\n  let x = .red!\n  result <> x\n}",
+            });
+        }
+    }
+
+    // A selection with nothing after it. The error is reported either at its dot or at
+    // whatever follows it, depending on how far the parser got.
+    let selection_at = |dot: usize| {
+        let ends = match toks.get(dot + 2) {
+            None => true,
+            Some(tok) => matches!(
+                tok.raw,
+                ")" | "}" | "," | "=>" | "in" | "def" | "dec" | "type"
+            ),
+        };
+        let is_command = dot
+            .checked_sub(1)
+            .is_some_and(|before| is_name(toks.get(before)));
+        (toks.get(dot).is_some_and(|tok| tok.kind == TokenKind::Dot)
+            && is_name(toks.get(dot + 1))
+            && ends
+            && !is_command)
+            .then(|| toks[dot + 1].raw)
+    };
+    let selected = selection_at(offset).or_else(|| selection_at(offset.checked_sub(2)?));
+    if let Some(selected) = selected {
+        return Some(Hint {
+            suggestion: format!(
+                "`.{}` has to be followed by what comes after it. If nothing does, end it with `!`: `.{}!`",
+                selected, selected
+            ),
+            example: "def favorite = .red!",
+        });
+    }
+
+    None
+}
+
+/// Recognizes a common mistake behind a type error.
+pub(super) fn in_types<Name>(error: &TypeError<Loc, Name>) -> Option<Hint> {
+    match error {
+        TypeError::InvalidOperation(_, Operation::Send(_), Type::Send(_, _, _)) => Some(Hint {
+            suggestion: "This sends a value to something that has a value to send first. \
+                         Receive it with `[...]` instead of sending with `(...)`."
+                .to_owned(),
+            example: "do {\n  pair[first]   // receives `first` from `pair`\n} in ...",
+        }),
+        TypeError::InvalidOperation(_, Operation::Receive(_), Type::Receive(_, _, _)) => {
+            Some(Hint {
+                suggestion: "This receives from something that is waiting to be sent a value. \
+                             Send it one with `(...)` instead of receiving with `[...]`."
+                    .to_owned(),
+                example: "swap(.true!)   // sends `.true!` to `swap`",
+            })
+        }
+        _ => None,
+    }
+}
+
+fn is_keyword(tok: &Token) -> bool {
+    keyword().parse(tok.raw).is_ok()
+}
+
+/// Whether `toks` end with a `<>` and the expression linked to, as opposed to
+/// stopping in the middle of one.
+fn ends_in_link(toks: &[Token]) -> bool {
+    let mut depth: usize = 0;
+    for tok in toks.iter().rev() {
+        match tok.kind {
+            TokenKind::RCurly | TokenKind::RParen | TokenKind::RBrack => depth += 1,
+            TokenKind::LCurly | TokenKind::LParen | TokenKind::LBrack => match depth {
+                0 => return false,
+                _ => depth -= 1,
+            },
+            _ if depth > 0 => {}
+            _ if tok.raw == "<>" => return true,
+            _ if matches!(tok.raw, "=>" | "=" | "in" | "let") => return false,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn inside_braces(toks: &[Token]) -> bool {
+    let mut depth: usize = 0;
+    for tok in toks {
+        match tok.kind {
+            TokenKind::LCurly => depth += 1,
+            TokenKind::RCurly => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        compiled::Compiled,
+        par::{frontend::Par, parse::parse_program},
+    };
+
+    fn hint(code: &str) -> Option<String> {
+        let error = parse_program(code).expect_err("should not parse");
+        let help = miette::Diagnostic::help(&error)?.to_string();
+        let (hint, _expected) = help.split_once("\n\nFor example:")?;
+        Some(hint.to_owned())
+    }
+
+    #[test]
+    fn test_syntax_hints() {
+        let arrow = hint("def swap = [b] b { .true! = .false! .false! => .true! }").unwrap();
+        assert!(arrow.contains("`=>`"), "{}", arrow);
+
+        let keyword = hint("def f = [loop] loop").unwrap();
+        assert!(keyword.contains("`loop` is a keyword"), "{}", keyword);
+        let keyword = hint("def f = do { let in = .red! } in !").unwrap();
+        assert!(keyword.contains("`in` is a keyword"), "{}", keyword);
+
+        let bang = hint("def favorite = .red\ndef other = .blue!").unwrap();
+        assert!(bang.contains("`.red!`"), "{}", bang);
+        let bang = hint("def list = .item(.red!) .empty").unwrap();
+        assert!(bang.contains("`.empty!`"), "{}", bang);
+
+        let after_break = hint("def f = chan c { c.red! let x = ! in x }").unwrap();
+        assert!(
+            after_break.contains("`!` ends the process"),
+            "{}",
+            after_break
+        );
+        let after_link = hint("def f = chan c { c <> chan d { d! } c! }").unwrap();
+        assert!(after_link.contains("`<>` hands the rest"), "{}", after_link);
+        assert_eq!(hint("def f = chan c { c(x y) }"), None);
+
+        let string = hint(r#"def f = [x] "{x y}""#).unwrap();
+        assert!(string.contains("`\\{`"), "{}", string);
+        let string = hint(r#"def f = "\q""#).unwrap();
+        assert!(string.contains("only the escapes"), "{}", string);
+
+        assert_eq!(hint("def x = ("), None);
+    }
+
+    #[test]
+    fn test_type_hints() {
+        let Ok(compiled) = Compiled::from_source(
+            &Par,
+            "type Bool = either { .true!, .false! }
+             dec pair : (Bool) Bool
+             def pair = (.true!) .false!
+             def wrong = pair(.true!)",
+        ) else {
+            panic!("should compile");
+        };
+        let Err(error) = compiled.checked else {
+            panic!("should not type check");
+        };
+        assert!(in_types(&error)
+            .unwrap()
+            .suggestion
+            .contains("Receive it with `[...]`"));
+    }
+}
@@ -0,0 +1,204 @@
+//! Lays a type out as a tree of what's done with a channel of it, one step after
+//! another, for the playground to show with each step folded or unfolded.
+
+use std::fmt::Display;
+
+use indexmap::IndexMap;
+
+use super::types::Type;
+
+/// One step done with a channel, with what it takes apart.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub label: String,
+    /// The value sent or received, the branches to pick from, or the body of a
+    /// recursive or iterative type. The steps after this one aren't in here, but
+    /// follow it in the same list.
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    fn leaf(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            children: Vec::new(),
+        }
+    }
+
+    /// A step taking apart `inner`. When that's a single name, such as in `(Bool) !`,
+    /// it's shown on the same line.
+    fn step(label: impl Display, inner: Vec<Self>) -> Self {
+        if let [only] = inner.as_slice() {
+            if only.children.is_empty() {
+                return Self::leaf(format!("{} {}", label, only.label));
+            }
+        }
+        Self {
+            label: label.to_string(),
+            children: inner,
+        }
+    }
+}
+
+/// The steps a channel of `typ` goes through, in order.
+pub fn outline<Loc, Name: Display>(typ: &Type<Loc, Name>) -> Vec<Node> {
+    let mut steps = Vec::new();
+    let mut typ = typ;
+    loop {
+        typ = match typ {
+            Type::Send(_, value, then) => {
+                steps.push(Node::step("send", outline(value)));
+                then
+            }
+            Type::Receive(_, value, then) => {
+                steps.push(Node::step("receive", outline(value)));
+                then
+            }
+            Type::SendType(_, name, then) => {
+                steps.push(Node::leaf(format!("send type {}", name)));
+                then
+            }
+            Type::ReceiveType(_, name, then) => {
+                steps.push(Node::leaf(format!("receive type {}", name)));
+                then
+            }
+            Type::Either(_, branches) => {
+                steps.push(branching("either", branches));
+                break;
+            }
+            Type::Choice(_, branches) => {
+                steps.push(branching("choice", branches));
+                break;
+            }
+            Type::Recursive(_, _, label, body) => {
+                steps.push(Node::step(labeled("recursive", label), outline(body)));
+                break;
+            }
+            Type::Iterative(_, _, label, body) => {
+                steps.push(Node::step(labeled("iterative", label), outline(body)));
+                break;
+            }
+            Type::Self_(_, label) => {
+                steps.push(Node::leaf(labeled("self", label)));
+                break;
+            }
+            Type::Chan(_, body) => {
+                steps.push(Node::step("chan", outline(body)));
+                break;
+            }
+            Type::Break(_) => {
+                steps.push(Node::leaf("!"));
+                break;
+            }
+            Type::Continue(_) => {
+                steps.push(Node::leaf("?"));
+                break;
+            }
+            Type::Var(_, _)
+            | Type::Name(_, _, _)
+            | Type::String(_)
+            | Type::Int(_)
+            | Type::Nat(_) => {
+                let mut label = String::new();
+                typ.pretty(&mut label, 0).expect("write failed");
+                steps.push(Node::leaf(label));
+                break;
+            }
+        };
+    }
+    steps
+}
+
+fn branching<Loc, Name: Display>(label: &str, branches: &IndexMap<Name, Type<Loc, Name>>) -> Node {
+    Node {
+        label: label.to_owned(),
+        children: (branches.iter())
+            .map(|(branch, typ)| Node::step(format!(".{}", branch), outline(typ)))
+            .collect(),
+    }
+}
+
+fn labeled<Name: Display>(keyword: &str, label: &Option<Name>) -> String {
+    match label {
+        Some(label) => format!("{} :{}", keyword, label),
+        None => keyword.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{compiled::Compiled, par::frontend::Par};
+
+    fn show(nodes: &[Node], indent: usize, buf: &mut String) {
+        for node in nodes {
+            buf.push_str(&"  ".repeat(indent));
+            buf.push_str(&node.label);
+            buf.push('\n');
+            show(&node.children, indent + 1, buf);
+        }
+    }
+
+    fn outline_of(code: &str, line: usize) -> String {
+        let Ok(compiled) = Compiled::from_source(&Par, code) else {
+            panic!("should compile");
+        };
+        let Ok(checked) = compiled.checked else {
+            panic!("should type check");
+        };
+        let (_, nodes) = checked
+            .outline(line)
+            .expect("a definition is under the line");
+        let mut buf = String::new();
+        show(&nodes, 0, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_outline() {
+        let code = "
+            type Bool = either { .true!, .false! }
+            dec both : [Bool, Bool] Bool
+            def both = [a, b] a {
+              .true! => b,
+              .false! => do { b { .true! => {}, .false! => {} } } in .false!,
+            }
+        ";
+        assert_eq!(outline_of(code, 4), "receive Bool\nreceive Bool\nBool\n");
+
+        let code = "
+            dec stream : iterative { .close => !, .next => (either { .a!, .b! }) self }
+            def stream = begin { .close => !, .next => (.a!) loop }
+        ";
+        assert_eq!(
+            outline_of(code, 3),
+            "iterative\n  choice\n    .close !\n    .next\n      send\n        either\n          \
+             .a !\n          .b !\n      self\n"
+        );
+    }
+
+    #[test]
+    fn test_outside_definitions() {
+        let code = "
+            dec unit : !
+            def unit = !
+
+            def pair : (!) ! =
+              (!) !
+        ";
+        let Ok(Compiled {
+            checked: Ok(checked),
+            ..
+        }) = Compiled::from_source(&Par, code)
+        else {
+            panic!("should type check");
+        };
+        let under = |line| checked.outline(line).map(|(name, _)| name.to_string());
+        assert_eq!(under(2), None);
+        assert_eq!(under(3), Some("unit".to_owned()));
+        assert_eq!(under(4), None);
+        assert_eq!(under(5), Some("pair".to_owned()));
+        assert_eq!(under(6), Some("pair".to_owned()));
+        assert_eq!(under(7), None);
+    }
+}
@@ -3,16 +3,22 @@ use super::{
         Apply, ApplyBranch, ApplyBranches, Command, CommandBranch, CommandBranches, Construct,
         ConstructBranch, ConstructBranches, Expression, Pattern, Process,
     },
-    lexer::{lex, Input, Token, TokenKind},
+    lexer::{lex, pieces, Input, Piece, Token, TokenKind},
+    mistakes,
+    process::{Operator, StringPart},
     types::Type,
 };
 use core::{fmt::Display, str::FromStr};
 use indexmap::IndexMap;
 use miette::{SourceOffset, SourceSpan};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use winnow::{
     combinator::{
-        alt, cut_err, delimited, empty, not, opt, peek, preceded, repeat, separated, terminated,
-        trace,
+        alt, backtrack_err, cut_err, delimited, empty, not, opt, peek, preceded, repeat, separated,
+        terminated, trace,
     },
     error::{
         AddContext, ContextError, ErrMode, ModalError, ParserError, StrContext, StrContextValue,
@@ -22,12 +28,58 @@ use winnow::{
     Parser,
 };
 
+/// Where something is in the source code.
+///
+/// `offset` counts bytes, as spans and diagnostics do. `line` and `column` count from
+/// one, with columns counted in characters, as editors do. Both always describe the
+/// same place, so either can be used without going back to the source.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub enum Loc {
-    Code { line: usize, column: usize },
+    Code {
+        offset: usize,
+        line: usize,
+        column: usize,
+    },
     External,
 }
 
+impl Loc {
+    /// The location of the byte `offset` in `code`.
+    pub fn from_offset(code: &str, offset: usize) -> Self {
+        let before = &code[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self::Code {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// The location right after `text`, if `text` starts here and stays on one line.
+    fn further(&self, text: &str) -> Self {
+        match self {
+            Self::Code {
+                offset,
+                line,
+                column,
+            } => Self::Code {
+                offset: offset + text.len(),
+                line: *line,
+                column: column + text.chars().count(),
+            },
+            Self::External => Self::External,
+        }
+    }
+
+    /// An empty span at the location, for pointing at it in diagnostics.
+    pub fn span(&self) -> Option<SourceSpan> {
+        match self {
+            Self::Code { offset, .. } => Some(SourceSpan::new(SourceOffset::from(*offset), 0)),
+            Self::External => None,
+        }
+    }
+}
+
 impl Default for Loc {
     fn default() -> Self {
         Self::External
@@ -37,7 +89,7 @@ impl Default for Loc {
 impl Display for Loc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Code { line, column } => write!(f, "{}:{}", line, column),
+            Self::Code { line, column, .. } => write!(f, "{}:{}", line, column),
             Self::External => write!(f, "#:#"),
         }
     }
@@ -72,6 +124,9 @@ pub struct Program<Loc, Name, Expr> {
     pub type_defs: Vec<(Loc, Name, Vec<Name>, Type<Loc, Name>)>,
     pub declarations: Vec<(Loc, Name, Type<Loc, Name>)>,
     pub definitions: Vec<(Loc, Name, Expr)>,
+    pub allows: Vec<Allow<Loc>>,
+    /// Where the last token of each item is, by the item's location.
+    pub ends: HashMap<Loc, Loc>,
 }
 
 impl<Name, Expr> Default for Program<Loc, Name, Expr> {
@@ -80,6 +135,140 @@ impl<Name, Expr> Default for Program<Loc, Name, Expr> {
             type_defs: Default::default(),
             declarations: Default::default(),
             definitions: Default::default(),
+            allows: Default::default(),
+            ends: Default::default(),
+        }
+    }
+}
+
+/// A `//@allow(name, ...)` comment on a line right above an item, turning off the
+/// warnings it names for that item.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allow<Loc> {
+    pub loc: Loc,
+    /// The item it's above, by the location its name has in the program.
+    pub item: Loc,
+    pub warnings: Vec<String>,
+}
+
+impl<Expr> Program<Loc, Name, Expr> {
+    /// Adds the builtin types, each unless the program defines one of the same name.
+    ///
+    /// `List<T>` is the type list literals build. It is the usual recursive encoding:
+    ///
+    /// ```text
+    /// type List<T> = recursive either {
+    ///   .empty!
+    ///   .item(T) self
+    /// }
+    /// ```
+    ///
+    /// `Bool` is the type of `true`, `false`, and the condition of an `if`:
+    ///
+    /// ```text
+    /// type Bool = either { .true!, .false! }
+    /// ```
+    pub fn add_builtin_types(&mut self) {
+        self.add_builtin_list();
+        self.add_builtin_bool();
+        self.add_builtin_numbers();
+    }
+
+    fn defines_type(&self, name: &Name) -> bool {
+        (self.type_defs.iter()).any(|(_, defined, _, _)| defined == name)
+    }
+
+    fn add_builtin_list(&mut self) {
+        let list = Name::from("List".to_owned());
+        if self.defines_type(&list) {
+            return;
+        }
+        let loc = Loc::External;
+        let param = Name::from("T".to_owned());
+        let branches = IndexMap::from([
+            (Name::from("empty".to_owned()), Type::Break(loc.clone())),
+            (
+                Name::from("item".to_owned()),
+                Type::Send(
+                    loc.clone(),
+                    Box::new(Type::Name(loc.clone(), param.clone(), vec![])),
+                    Box::new(Type::Self_(loc.clone(), None)),
+                ),
+            ),
+        ]);
+        let typ = Type::Recursive(
+            loc.clone(),
+            Default::default(),
+            None,
+            Box::new(Type::Either(loc.clone(), branches)),
+        );
+        self.type_defs.push((loc, list, vec![param], typ));
+    }
+
+    fn add_builtin_bool(&mut self) {
+        let bool = Name::from("Bool".to_owned());
+        if self.defines_type(&bool) {
+            return;
+        }
+        let loc = Loc::External;
+        let branches = ["true", "false"]
+            .map(|branch| (Name::from(branch.to_owned()), Type::Break(loc.clone())));
+        let typ = Type::Either(loc.clone(), IndexMap::from(branches));
+        self.type_defs.push((loc, bool, vec![], typ));
+    }
+
+    /// `Int` and `Nat` name the types of numbers, which have no definition in Par itself.
+    fn add_builtin_numbers(&mut self) {
+        let loc = Loc::External;
+        for (name, typ) in [
+            ("Int", Type::Int(loc.clone())),
+            ("Nat", Type::Nat(loc.clone())),
+        ] {
+            let name = Name::from(name.to_owned());
+            if !self.defines_type(&name) {
+                self.type_defs.push((loc.clone(), name, vec![], typ));
+            }
+        }
+    }
+}
+
+impl<Name, Expr> Program<Loc, Name, Expr> {
+    /// Finds the `//@allow` comments above each item. Comments never make it past the
+    /// lexer, so these are read from the source itself.
+    fn find_allows(&mut self, code: &str) {
+        let items = (self.type_defs.iter().map(|(loc, ..)| loc))
+            .chain(self.declarations.iter().map(|(loc, ..)| loc))
+            .chain(self.definitions.iter().map(|(loc, ..)| loc));
+        let mut seen = HashSet::new();
+        for item in items {
+            let Loc::Code { offset, .. } = item else {
+                continue;
+            };
+            if !seen.insert(*offset) {
+                continue;
+            }
+            let mut end = code[..*offset].rfind('\n').map_or(0, |newline| newline + 1);
+            while end > 0 {
+                let start = code[..end - 1].rfind('\n').map_or(0, |newline| newline + 1);
+                let line = &code[start..end - 1];
+                let Some(names) = (line.trim())
+                    .strip_prefix("//@allow(")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                else {
+                    break;
+                };
+                let indent = line.len() - line.trim_start().len();
+                self.allows.push(Allow {
+                    loc: Loc::from_offset(code, start + indent),
+                    item: item.clone(),
+                    warnings: (names.split(','))
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_owned)
+                        .collect(),
+                });
+                end = start;
+            }
         }
     }
 }
@@ -148,7 +337,7 @@ impl<I: Stream, C> AddContext<I, C> for MyError<C> {
 pub type Result<O, E = MyError> = core::result::Result<O, ErrMode<E>>;
 
 /// Token with additional context of expecting the `token` value
-fn t<'i, I, E>(token: &'static str) -> impl Parser<I, I::Slice, E>
+pub(super) fn t<'i, I, E>(token: &'static str) -> impl Parser<I, I::Slice, E>
 where
     I: Stream + StreamIsPartial + for<'s> Compare<&'s str>,
     E: AddContext<I, StrContext> + ParserError<I>,
@@ -166,7 +355,7 @@ macro_rules! tn {
 }
 
 /// Like regular `preceded` but cuts if `parser` after `ignored` fails, assuming that it should be unambiguous.
-fn commit_after<Input, Ignored, Output, Error, IgnoredParser, ParseNext>(
+pub(super) fn commit_after<Input, Ignored, Output, Error, IgnoredParser, ParseNext>(
     mut ignored: IgnoredParser,
     parser: ParseNext,
 ) -> impl Parser<Input, Output, Error>
@@ -252,7 +441,7 @@ where
     .take()
 }
 
-fn keyword<I>() -> impl Parser<I, I::Slice, Error>
+pub(super) fn keyword<I>() -> impl Parser<I, I::Slice, Error>
 where
     I: Stream + StreamIsPartial + for<'s> Compare<&'s str>,
 {
@@ -272,11 +461,14 @@ where
         "recursive",
         "iterative",
         "self",
+        "if",
+        "then",
+        "else",
     ))
     .context(StrContext::Label("keyword"))
 }
 
-fn with_loc<'a, O, E>(
+pub(super) fn with_loc<'a, O, E>(
     mut parser: impl Parser<Input<'a>, O, E>,
 ) -> impl Parser<Input<'a>, (O, Loc), E>
 where
@@ -291,10 +483,12 @@ where
                 let Some(last) = input.last() else {
                     return Err(e);
                 };
+                // Just past the end of the last token.
                 let res = match last.loc {
-                    Loc::Code { line, column } => Loc::Code {
+                    Loc::Code { line, column, .. } => Loc::Code {
+                        offset: last.span.end,
                         line,
-                        column: column + 1,
+                        column: column + last.raw.chars().count(),
                     },
                     Loc::External => Loc::External,
                 };
@@ -306,6 +500,23 @@ where
         Ok((out, loc))
     }
 }
+/// Runs `parser`, along with the location of the last token it took.
+fn with_end<'a, O, E>(
+    mut parser: impl Parser<Input<'a>, O, E>,
+) -> impl Parser<Input<'a>, (O, Loc), E>
+where
+    E: ParserError<Input<'a>>,
+{
+    move |input: &mut Input<'a>| -> core::result::Result<(O, Loc), E> {
+        let start = input.checkpoint();
+        let out = parser.parse_next(input)?;
+        let taken = winnow::stream::Offset::offset_from(&*input, &start);
+        input.reset(&start);
+        let end = (input.next_slice(taken).last()).map_or(Loc::External, |tok| tok.loc.clone());
+        Ok((out, end))
+    }
+}
+
 #[allow(dead_code)]
 fn with_span<'a, O, E>(
     mut parser: impl Parser<Input<'a>, O, E>,
@@ -326,7 +537,7 @@ where
     }
 }
 
-fn name<'s>(input: &mut Input<'s>) -> Result<Name> {
+pub(super) fn name<'s>(input: &mut Input<'s>) -> Result<Name> {
     preceded(not(keyword()), TokenKind::Ident.parse_to::<Name>())
         .context(StrContext::Expected(StrContextValue::CharLiteral('_')))
         .context(StrContext::Expected(StrContextValue::Description(
@@ -339,55 +550,64 @@ fn name<'s>(input: &mut Input<'s>) -> Result<Name> {
         .parse_next(input)
 }
 
-struct ProgramParseError {
-    offset: usize,
-    error: Error_,
+/// A branch's name, after a `.`. Any name goes there, keywords included, so programs
+/// using `.if`, `.then` or `.else` as branches keep working as keywords are added.
+pub(super) fn label<'s>(input: &mut Input<'s>) -> Result<Name> {
+    TokenKind::Ident
+        .parse_to::<Name>()
+        .context(StrContext::Label("branch"))
+        .parse_next(input)
+}
+
+enum Item {
+    TypeDef((Loc, Name, Vec<Name>, Type<Loc, Name>)),
+    Declaration((Loc, Name, Type<Loc, Name>)),
+    Definition(DefinitionHeader, Box<Expression<Loc, Name>>),
 }
-impl ProgramParseError {
-    fn offset(&self) -> usize {
-        self.offset
-    }
-    fn inner(&self) -> &Error_ {
-        &self.error
-    }
+
+fn item(input: &mut Input) -> Result<Item> {
+    alt((
+        type_def.map(Item::TypeDef),
+        declaration.map(Item::Declaration),
+        definition.map(|(loc, name, annotation, expression)| {
+            Item::Definition((loc, name, annotation), Box::new(expression))
+        }),
+    ))
+    .context(StrContext::Label("item"))
+    .parse_next(input)
 }
-fn program(
-    mut input: Input,
-) -> std::result::Result<Program<Loc, Name, Expression<Loc, Name>>, ProgramParseError> {
-    enum Either<A, B, C> {
-        A(A),
-        B(B),
-        C(C),
-    }
 
-    let parser = repeat(
-        0..,
-        alt((
-            type_def.map(Either::A),
-            declaration.map(Either::B),
-            definition.map(Either::C),
-        ))
-        .context(StrContext::Label("item")),
-    )
-    .fold(Program::default, |mut acc, item| {
+impl Program<Loc, Name, Expression<Loc, Name>> {
+    /// Adds `item`, whose last token is at `end`.
+    fn add_item(&mut self, item: Item, end: Loc) {
+        let loc = match &item {
+            Item::TypeDef((loc, ..)) | Item::Declaration((loc, ..)) => loc,
+            Item::Definition((loc, ..), _) => loc,
+        };
+        self.ends.insert(loc.clone(), end);
         match item {
-            Either::A(type_def) => {
-                acc.type_defs.push(type_def);
+            Item::TypeDef(type_def) => {
+                self.type_defs.push(type_def);
             }
-            Either::B(dec) => {
-                acc.declarations.push(dec);
+            Item::Declaration(dec) => {
+                self.declarations.push(dec);
             }
-            Either::C((loc, name, annotation, expression)) => {
+            Item::Definition((loc, name, annotation), expression) => {
                 if let Some(typ) = annotation {
-                    acc.declarations.push((loc.clone(), name.clone(), typ));
+                    self.declarations.push((loc.clone(), name.clone(), typ));
                 }
-                acc.definitions.push((loc, name, expression));
+                self.definitions.push((loc, name, *expression));
             }
-        };
+        }
+    }
+}
+
+fn program(input: &mut Input) -> Result<Program<Loc, Name, Expression<Loc, Name>>> {
+    let parser = repeat(0.., with_end(item)).fold(Program::default, |mut acc, (item, end)| {
+        acc.add_item(item, end);
         acc
     });
 
-    let start = input.checkpoint();
     (
         parser,
         winnow::combinator::eof
@@ -398,18 +618,8 @@ fn program(
                 "end of file",
             ))),
     )
-        .parse_next(&mut input)
         .map(|(x, _eof)| x)
-        .map_err(|e| {
-            let e = e.into_inner().unwrap_or_else(|_err| {
-                panic!("complete parsers should not report `ErrMode::Incomplete(_)`")
-            });
-
-            ProgramParseError {
-                offset: winnow::stream::Offset::offset_from(&input, &start),
-                error: ParserError::append(e, &input, &start),
-            }
-        })
+        .parse_next(input)
 }
 
 #[derive(Debug, Clone, miette::Diagnostic)]
@@ -444,37 +654,182 @@ pub fn set_miette_hook() {
     }));
 }
 
+/// Parses the whole program, stopping at the first error. Front ends use
+/// [`parse_program_recovering`] instead, to report every error at once.
 pub fn parse_program(
     input: &str,
 ) -> std::result::Result<Program<Loc, Name, Expression<Loc, Name>>, SyntaxError> {
     let toks = lex(&input);
-    let e = match program(Input::new(&toks)) {
+    let mut program = parse_all(&toks, program)?;
+    program.find_allows(input);
+    Ok(program)
+}
+
+/// Parses a lone expression, such as `chan x { x! }`, without a `def` around it.
+pub fn parse_expression(input: &str) -> std::result::Result<Expression<Loc, Name>, SyntaxError> {
+    parse_fragment(input, expression, "end of expression")
+}
+
+/// Parses a lone process, the commands that go between `{` and `}`.
+pub fn parse_process(input: &str) -> std::result::Result<Process<Loc, Name>, SyntaxError> {
+    parse_fragment(input, process, "end of process")
+}
+
+/// Parses a lone type, such as `recursive either { .empty!, .item(T) self }`.
+pub fn parse_type(input: &str) -> std::result::Result<Type<Loc, Name>, SyntaxError> {
+    parse_fragment(input, typ, "end of type")
+}
+
+/// Runs `parser` on the whole of `input`, which must end where the fragment does.
+fn parse_fragment<O>(
+    input: &str,
+    parser: impl for<'i> Parser<Input<'i>, O, Error>,
+    end: &'static str,
+) -> std::result::Result<O, SyntaxError> {
+    let toks = lex(input);
+    parse_all(
+        &toks,
+        (
+            parser,
+            winnow::combinator::eof
+                .context(StrContext::Expected(StrContextValue::Description(end))),
+        )
+            .map(|(x, _eof)| x),
+    )
+}
+
+/// Runs `parser` on the tokens, which it is expected to consume completely,
+/// and turns a failure into a [`SyntaxError`] pointing at the offending token.
+pub(super) fn parse_all<'i, O>(
+    toks: &'i [Token<'i>],
+    mut parser: impl Parser<Input<'i>, O, Error>,
+) -> std::result::Result<O, SyntaxError> {
+    let mut input = Input::new(toks);
+    let start = input.checkpoint();
+    let e = match parser.parse_next(&mut input) {
         Ok(x) => return Ok(x),
         Err(e) => e,
     };
-    // Empty input doesn't error so this won't panic.
-    let error_tok = toks.get(e.offset()).unwrap_or(toks.last().unwrap()).clone();
-    Err(SyntaxError {
-        span: SourceSpan::new(SourceOffset::from(error_tok.span.start), {
+    let e = e.into_inner().unwrap_or_else(|_err| {
+        panic!("complete parsers should not report `ErrMode::Incomplete(_)`")
+    });
+    let offset = winnow::stream::Offset::offset_from(&input, &start);
+    let e: Error_ = ParserError::append(e, &input, &start);
+    Err(syntax_error(toks, offset, e))
+}
+
+/// Points a parser error at the token `offset` tokens into the input.
+fn syntax_error(toks: &[Token], offset: usize, e: Error_) -> SyntaxError {
+    let span = match toks.get(offset).or(toks.last()) {
+        Some(error_tok) => SourceSpan::new(SourceOffset::from(error_tok.span.start), {
             match error_tok.span.len() {
                 // miette unicode format for 1 length span is a hard-to-notice line, so don't set length to 1.
                 x if x == 1 => 0,
                 x => x,
             }
         }),
-        help: e
-            .inner()
-            .context
-            .iter()
-            .map(|x| x.1.to_string().chars().chain(['\n']).collect::<String>())
-            .collect::<String>(),
-    })
+        // Nothing to point at, the input had no tokens.
+        None => SourceSpan::new(SourceOffset::from(0), 0),
+    };
+    let expected = e
+        .context
+        .iter()
+        .map(|x| x.1.to_string().chars().chain(['\n']).collect::<String>())
+        .collect::<String>();
+    SyntaxError {
+        span,
+        help: match mistakes::in_syntax(toks, offset) {
+            Some(hint) => format!("{}\n{}", hint.to_help(), expected),
+            None => expected,
+        },
+    }
+}
+
+/// Parses as much of the program as it can, never giving up on the whole file.
+///
+/// When an item fails to parse, its error is recorded and parsing resumes at the next
+/// `type`, `dec`, or `def` that starts an item. A definition that broke after its `=`
+/// stays in the program, with an [`Expression::Placeholder`] at the error as its body,
+/// so the rest of the program can still refer to it and be checked. Other items that
+/// broke are left out.
+pub fn parse_program_recovering(
+    input: &str,
+) -> (Program<Loc, Name, Expression<Loc, Name>>, Vec<SyntaxError>) {
+    let code = input;
+    let toks = lex(code);
+    let mut input = Input::new(&toks);
+    let mut program = Program::default();
+    let mut errors = Vec::new();
+
+    while input.eof_offset() > 0 {
+        let start = input.checkpoint();
+        let e = match with_end(item).parse_next(&mut input) {
+            Ok((item, end)) => {
+                program.add_item(item, end);
+                continue;
+            }
+            Err(e) => e.into_inner().unwrap_or_else(|_err| {
+                panic!("complete parsers should not report `ErrMode::Incomplete(_)`")
+            }),
+        };
+        let offset = toks.len() - input.eof_offset();
+        let error_loc = toks
+            .get(offset)
+            .or(toks.last())
+            .map_or(Loc::External, |tok| tok.loc.clone());
+        errors.push(syntax_error(&toks, offset, e));
+
+        input.reset(&start);
+        let header = definition_header.parse_next(&mut input).ok();
+
+        input.reset(&start);
+        let mut end = input.next_token().map(|tok| tok.loc.clone());
+        while let Some(tok) = input.peek_token() {
+            if starts_item(&toks, toks.len() - input.eof_offset(), tok) {
+                break;
+            }
+            end = input.next_token().map(|tok| tok.loc.clone());
+        }
+
+        if let (Some(header), Some(end)) = (header, end) {
+            let placeholder = Expression::Placeholder(error_loc);
+            program.add_item(Item::Definition(header, Box::new(placeholder)), end);
+        }
+    }
+
+    program.find_allows(code);
+    (program, errors)
+}
+
+/// Whether the token at `index` can start an item. `type` also appears inside
+/// `(type T)` and `[type T]`, where it does not.
+fn starts_item(toks: &[Token], index: usize, tok: &Token) -> bool {
+    match tok.raw {
+        "dec" | "def" => true,
+        "type" => !matches!(
+            index.checked_sub(1).map(|before| toks[before].kind),
+            Some(TokenKind::LParen | TokenKind::LBrack)
+        ),
+        _ => false,
+    }
 }
 
 fn type_def(input: &mut Input) -> Result<(Loc, Name, Vec<Name>, Type<Loc, Name>)> {
-    commit_after(t("type"), (with_loc(name), type_params, t("="), typ))
-        .map(|((name, loc), type_params, _, typ)| (loc, name, type_params, typ))
-        .context(StrContext::Label("type definition"))
+    commit_after(
+        t("type"),
+        (with_loc(name), type_params, t("="), alt((typ_dual, typ))),
+    )
+    .map(|((name, loc), type_params, _, typ)| (loc, name, type_params, typ))
+    .context(StrContext::Label("type definition"))
+    .parse_next(input)
+}
+
+/// `dual T` on the right of a type definition, standing for the other side of `T`.
+/// The checker computes it from `T` when needed, the same as `chan T`, so a protocol
+/// and its dual can't drift apart.
+fn typ_dual(input: &mut Input) -> Result<Type<Loc, Name>> {
+    with_loc(preceded(t("dual"), typ))
+        .map(|(typ, loc)| Type::Chan(loc, Box::new(typ)))
         .parse_next(input)
 }
 
@@ -488,13 +843,92 @@ fn declaration(input: &mut Input) -> Result<(Loc, Name, Type<Loc, Name>)> {
 fn definition(
     input: &mut Input,
 ) -> Result<(Loc, Name, Option<Type<Loc, Name>>, Expression<Loc, Name>)> {
-    commit_after(t("def"), (with_loc(name), annotation, t("="), expression))
-        .map(|((name, loc), annotation, _, expression)| (loc, name, annotation, expression))
+    commit_after(t("def"), (with_loc(name), signature, t("="), expression))
+        .map(|((name, loc), (parameters, annotation), _, expression)| {
+            let expression = match parameters {
+                None => expression,
+                Some((parameters, params_loc)) => Expression::Construction(
+                    params_loc.clone(),
+                    receive_all(
+                        params_loc.clone(),
+                        parameters,
+                        Construct::Then(params_loc, Box::new(expression)),
+                    ),
+                ),
+            };
+            (loc, name, annotation, expression)
+        })
         .context(StrContext::Label("definition"))
         .parse_next(input)
 }
 
-fn list<P, I, O>(item: P) -> impl Parser<I, Vec<O>, Error> + use<P, I, O>
+/// The location, name, and type annotation of a definition.
+type DefinitionHeader = (Loc, Name, Option<Type<Loc, Name>>);
+
+/// Everything in a definition up to its `=`.
+fn definition_header(input: &mut Input) -> Result<DefinitionHeader> {
+    (t("def"), with_loc(name), signature, t("="))
+        .map(|(_, (name, loc), (_, annotation), _)| (loc, name, annotation))
+        .parse_next(input)
+}
+
+/// The parameters of a definition, if it lists them, and its type annotation.
+type Signature = (
+    Option<(Vec<Pattern<Loc, Name>>, Loc)>,
+    Option<Type<Loc, Name>>,
+);
+
+/// Parses a definition's [`Signature`].
+///
+/// `def f(x: A, y: B) : R = body` is short for `def f : [A, B] R = [x: A, y: B] body`.
+/// With parameters, the annotation is the type of the result, so every parameter
+/// needs a type for it to be given.
+fn signature(input: &mut Input) -> Result<Signature> {
+    (
+        opt(with_loc(delimited(t("("), list(pattern), t(")")))),
+        annotation,
+    )
+        .verify_map(|(parameters, result)| {
+            let annotation = match (&parameters, result) {
+                (Some((parameters, loc)), Some(result)) => Some(parameters.iter().rev().try_fold(
+                    result,
+                    |result, parameter| {
+                        Some(Type::Receive(
+                            loc.clone(),
+                            Box::new(pattern_type(parameter)?),
+                            Box::new(result),
+                        ))
+                    },
+                )?),
+                (_, result) => result,
+            };
+            Some((parameters, annotation))
+        })
+        .context(StrContext::Expected(StrContextValue::Description(
+            "a type on every parameter",
+        )))
+        .parse_next(input)
+}
+
+/// The type a pattern's annotations give to what it receives, if they cover all of it.
+fn pattern_type(pattern: &Pattern<Loc, Name>) -> Option<Type<Loc, Name>> {
+    match pattern {
+        Pattern::Name(_, _, annotation) => annotation.clone(),
+        Pattern::Receive(loc, first, rest) => Some(Type::Send(
+            loc.clone(),
+            Box::new(pattern_type(first)?),
+            Box::new(pattern_type(rest)?),
+        )),
+        Pattern::Continue(loc) => Some(Type::Break(loc.clone())),
+        Pattern::ReceiveType(loc, name, rest) => Some(Type::SendType(
+            loc.clone(),
+            name.clone(),
+            Box::new(pattern_type(rest)?),
+        )),
+    }
+}
+
+pub(super) fn list<P, I, O>(item: P) -> impl Parser<I, Vec<O>, Error> + use<P, I, O>
 where
     P: Parser<I, O, Error>,
     I: Stream + StreamIsPartial + for<'a> Compare<&'a str>,
@@ -512,7 +946,7 @@ where
     commit_after(
         t("{"),
         terminated(
-            repeat(0.., (t("."), name, cut_err(branch), opt(t(",")))).fold(
+            repeat(0.., (t("."), label, cut_err(branch), opt(t(",")))).fold(
                 || IndexMap::new(),
                 |mut branches, (_, name, branch, _)| {
                     branches.insert(name, branch);
@@ -527,6 +961,7 @@ where
 
 fn typ(input: &mut Input) -> Result<Type<Loc, Name>> {
     alt((
+        typ_string,
         typ_name,
         typ_chan,
         typ_either,
@@ -545,6 +980,13 @@ fn typ(input: &mut Input) -> Result<Type<Loc, Name>> {
     .parse_next(input)
 }
 
+/// The builtin `String`, which takes the name from any type defined in the program.
+fn typ_string(input: &mut Input) -> Result<Type<Loc, Name>> {
+    with_loc(terminated(t("String"), not(t("<"))))
+        .map(|(_, loc)| Type::String(loc))
+        .parse_next(input)
+}
+
 fn typ_name(input: &mut Input) -> Result<Type<Loc, Name>> {
     trace(
         "typ_name",
@@ -769,7 +1211,10 @@ fn expression(input: &mut Input) -> Result<Expression<Loc, Name>> {
         expr_let,
         expr_do,
         expr_fork,
-        application,
+        expr_if,
+        expr_string,
+        expr_bool,
+        arithmetic,
         with_loc(construction).map(|(cons, loc)| Expression::Construction(loc, cons)),
         delimited(t("{"), expression, t("}")),
     ))
@@ -777,6 +1222,162 @@ fn expression(input: &mut Input) -> Result<Expression<Loc, Name>> {
     .parse_next(input)
 }
 
+/// Sums and differences of products, such as `a + b * c`, which multiplies first. An
+/// expression without any of them is just its one operand.
+fn arithmetic(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    let additive = alt((
+        t("+").value(Operator::Add),
+        t("-").value(Operator::Subtract),
+    ));
+    (term, repeat(0.., (with_loc(additive), cut_err(term))))
+        .map(|(first, rest): (_, Vec<_>)| operations(first, rest))
+        .parse_next(input)
+}
+
+fn term(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    (
+        operand,
+        repeat(
+            0..,
+            (with_loc(t("*").value(Operator::Multiply)), cut_err(operand)),
+        ),
+    )
+        .map(|(first, rest): (_, Vec<_>)| operations(first, rest))
+        .parse_next(input)
+}
+
+fn operand(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    alt((expr_int, expr_hole, application)).parse_next(input)
+}
+
+/// Applies each operator in turn, from left to right.
+fn operations(
+    first: Expression<Loc, Name>,
+    rest: Vec<((Operator, Loc), Expression<Loc, Name>)>,
+) -> Expression<Loc, Name> {
+    rest.into_iter()
+        .fold(first, |left, ((operator, loc), right)| {
+            Expression::Arithmetic(loc, operator, Box::new(left), Box::new(right))
+        })
+}
+
+/// A number written out in decimal digits, such as `42` or `-7`. A minus sign is only
+/// part of the number right before its digits, and is read along with them, so that
+/// the most negative number can be written too.
+pub(super) fn number(input: &mut Input) -> Result<i128> {
+    let digits = || {
+        TokenKind::Ident.verify(|token: &Token| token.raw.bytes().all(|byte| byte.is_ascii_digit()))
+    };
+    alt((
+        (TokenKind::Minus, digits()).verify_map(|(minus, digits): (&Token, &Token)| {
+            (minus.span.end == digits.span.start)
+                .then(|| format!("-{}", digits.raw).parse::<i128>().ok())
+                .flatten()
+        }),
+        digits().verify_map(|digits: &Token| digits.raw.parse::<i128>().ok()),
+    ))
+    .context(StrContext::Label("number"))
+    .parse_next(input)
+}
+
+fn expr_int(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    with_loc(number)
+        .map(|(number, loc)| Expression::IntLiteral(loc, number))
+        .parse_next(input)
+}
+
+/// `?name`, or just `?`, an expression to be written later. It type checks as
+/// whatever is needed where it is, and running it fails.
+fn expr_hole(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    with_loc(preceded(t("?"), opt(name)))
+        .map(|(name, loc)| Expression::Hole(loc, name))
+        .parse_next(input)
+}
+
+/// `true` or `false`, short for `.true!` and `.false!`. They aren't keywords, so that
+/// `.true` and `.false` stay usable as branches.
+fn expr_bool(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    TokenKind::Ident
+        .verify_map(|token: &Token| {
+            let value = match token.raw {
+                "true" | "false" => Name::from(token.raw.to_owned()),
+                _ => return None,
+            };
+            let loc = token.loc.clone();
+            let construct =
+                Construct::Choose(loc.clone(), value, Box::new(Construct::Break(loc.clone())));
+            Some(Expression::Construction(loc, construct))
+        })
+        .context(StrContext::Label("boolean"))
+        .parse_next(input)
+}
+
+/// `if condition then yes else no`, short for matching the condition as a `Bool`:
+/// `let c: Bool = condition in c { .true! => yes, .false! => no }`.
+fn expr_if(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    with_loc(commit_after(
+        t("if"),
+        (
+            with_loc(expression),
+            with_loc(t("then")),
+            expression,
+            with_loc(t("else")),
+            expression,
+        ),
+    ))
+    .map(
+        |(((condition, condition_loc), (_, yes_loc), yes, (_, no_loc), no), loc)| {
+            let branches = IndexMap::from([
+                (
+                    Name::from("true".to_owned()),
+                    ApplyBranch::Continue(yes_loc, yes),
+                ),
+                (
+                    Name::from("false".to_owned()),
+                    ApplyBranch::Continue(no_loc, no),
+                ),
+            ]);
+            // `#` can't appear in source names, so this never shadows a variable.
+            let subject = Name::from("#condition".to_owned());
+            let bool = Type::Name(condition_loc.clone(), Name::from("Bool".to_owned()), vec![]);
+            Expression::Let(
+                loc.clone(),
+                Pattern::Name(condition_loc, subject.clone(), Some(bool)),
+                Box::new(condition),
+                Box::new(Expression::Application(
+                    loc.clone(),
+                    Box::new(Expression::Reference(loc.clone(), subject)),
+                    Apply::Either(loc, ApplyBranches(branches)),
+                )),
+            )
+        },
+    )
+    .parse_next(input)
+}
+
+fn expr_string(input: &mut Input) -> Result<Expression<Loc, Name>> {
+    TokenKind::String
+        .verify_map(|token: &Token| {
+            let parts = (pieces(token.raw)?.into_iter())
+                .map(|piece| match piece {
+                    Piece::Text(text) => StringPart::Text(Arc::from(text)),
+                    Piece::Hole(name, at) => StringPart::Hole(
+                        token.loc.further(&token.raw[..at]),
+                        Name::from(name.to_owned()),
+                    ),
+                })
+                .collect::<Vec<_>>();
+            Some(match parts.as_slice() {
+                [StringPart::Text(text)] => {
+                    Expression::StringLiteral(token.loc.clone(), Arc::clone(text))
+                }
+                _ => Expression::Format(token.loc.clone(), parts),
+            })
+        })
+        .context(StrContext::Label("string literal"))
+        .parse_next(input)
+}
+
 fn expr_let(input: &mut Input) -> Result<Expression<Loc, Name>> {
     with_loc(commit_after(
         t("let"),
@@ -832,7 +1433,10 @@ fn cons_then(input: &mut Input) -> Result<Construct<Loc, Name>> {
         expr_fork,
         expr_let,
         expr_do,
-        application,
+        expr_if,
+        expr_string,
+        expr_bool,
+        arithmetic,
         delimited(t("{"), expression, t("}")),
     )))
     .map(|(expr, loc)| Construct::Then(loc, Box::new(expr)))
@@ -853,11 +1457,62 @@ fn cons_send(input: &mut Input) -> Result<Construct<Loc, Name>> {
     .parse_next(input)
 }
 
+/// Either a function, `[x, y] body`, or a list literal, `[a, b]`, which is one as long
+/// as no body follows the closing bracket.
 fn cons_receive(input: &mut Input) -> Result<Construct<Loc, Name>> {
+    let start = input.checkpoint();
+    let head = backtrack_err(with_loc(delimited(t("["), list(pattern), t("]")))).parse_next(input);
+    if let Ok((patterns, loc)) = head {
+        if let Some(construct) = opt(construction).parse_next(input)? {
+            return Ok(receive_all(loc, patterns, construct));
+        }
+        input.reset(&start);
+        return cons_list.parse_next(input);
+    }
+
+    input.reset(&start);
+    let list_literal = cons_list.parse_next(input)?;
+    if opt(peek(construction)).parse_next(input)?.is_none() {
+        return Ok(list_literal);
+    }
+    // A body follows, so this is a function with something off in its parameters.
+    input.reset(&start);
     with_loc(commit_after(t("["), (list(pattern), t("]"), construction)))
-        .map(|((patterns, _, mut construct), loc)| {
-            for pattern in patterns.into_iter().rev() {
-                construct = Construct::Receive(loc.clone(), pattern, Box::new(construct));
+        .map(|((patterns, _, construct), loc)| receive_all(loc, patterns, construct))
+        .parse_next(input)
+}
+
+fn receive_all(
+    loc: Loc,
+    patterns: Vec<Pattern<Loc, Name>>,
+    mut construct: Construct<Loc, Name>,
+) -> Construct<Loc, Name> {
+    for pattern in patterns.into_iter().rev() {
+        construct = Construct::Receive(loc.clone(), pattern, Box::new(construct));
+    }
+    construct
+}
+
+/// A list literal, `[a, b, c]`, short for `.item(a) .item(b) .item(c) .empty!`.
+fn cons_list(input: &mut Input) -> Result<Construct<Loc, Name>> {
+    with_loc(commit_after(t("["), (opt(list(expression)), t("]"))))
+        .map(|((items, _), loc)| {
+            let name = |name: &str| Name::from(name.to_owned());
+            let mut construct = Construct::Choose(
+                loc.clone(),
+                name("empty"),
+                Box::new(Construct::Break(loc.clone())),
+            );
+            for item in items.unwrap_or_default().into_iter().rev() {
+                construct = Construct::Choose(
+                    loc.clone(),
+                    name("item"),
+                    Box::new(Construct::Send(
+                        loc.clone(),
+                        Box::new(item),
+                        Box::new(construct),
+                    )),
+                );
             }
             construct
         })
@@ -865,9 +1520,14 @@ fn cons_receive(input: &mut Input) -> Result<Construct<Loc, Name>> {
 }
 
 fn cons_choose(input: &mut Input) -> Result<Construct<Loc, Name>> {
-    // Note this can't be a commit_after because its possible that this is not a choose construction, and instead a branch of an either.
-    with_loc(preceded(t("."), (name, construction)))
-        .map(|((chosen, construct), loc)| Construct::Choose(loc, chosen, Box::new(construct)))
+    // Note this can't cut after the selections because its possible that this is not a choose construction, and instead a branch of an either.
+    (selections, construction)
+        .map(|(selections, mut construct)| {
+            for (loc, chosen) in selections.into_iter().rev() {
+                construct = Construct::Choose(loc, chosen, Box::new(construct));
+            }
+            construct
+        })
         .parse_next(input)
 }
 
@@ -1001,8 +1661,13 @@ fn apply_send(input: &mut Input) -> Result<Apply<Loc, Name>> {
 }
 
 fn apply_choose(input: &mut Input) -> Result<Apply<Loc, Name>> {
-    with_loc(commit_after(t("."), (name, apply)))
-        .map(|((chosen, then), loc)| Apply::Choose(loc, chosen, Box::new(then)))
+    (selections, cut_err(apply))
+        .map(|(selections, mut then)| {
+            for (loc, chosen) in selections.into_iter().rev() {
+                then = Apply::Choose(loc, chosen, Box::new(then));
+            }
+            then
+        })
         .parse_next(input)
 }
 
@@ -1182,8 +1847,13 @@ fn cmd_receive(input: &mut Input) -> Result<Command<Loc, Name>> {
 }
 
 fn cmd_choose(input: &mut Input) -> Result<Command<Loc, Name>> {
-    with_loc(commit_after(t("."), (name, cmd)))
-        .map(|((name, cmd), loc)| Command::Choose(loc, name, Box::new(cmd)))
+    (selections, cut_err(cmd))
+        .map(|(selections, mut cmd)| {
+            for (loc, chosen) in selections.into_iter().rev() {
+                cmd = Command::Choose(loc, chosen, Box::new(cmd));
+            }
+            cmd
+        })
         .parse_next(input)
 }
 
@@ -1300,6 +1970,16 @@ fn cmd_branch_recv_type(input: &mut Input) -> Result<CommandBranch<Loc, Name>> {
     .parse_next(input)
 }
 
+/// One or more chained selections, like `.a.b.c`, parsed as a unit.
+fn selections(input: &mut Input) -> Result<Vec<(Loc, Name)>> {
+    repeat(
+        1..,
+        with_loc(commit_after(t("."), label)).map(|(name, loc)| (loc, name)),
+    )
+    .context(StrContext::Label("selection"))
+    .parse_next(input)
+}
+
 fn loop_label<'s>(input: &mut Input<'s>) -> Result<Option<Name>> {
     opt(preceded(t(":"), name)).parse_next(input)
 }
@@ -1308,6 +1988,7 @@ fn loop_label<'s>(input: &mut Input<'s>) -> Result<Option<Name>> {
 mod test {
     use super::*;
     use crate::par::lexer::lex;
+    use indexmap::IndexSet;
 
     #[test]
     fn test_list() {
@@ -1351,6 +2032,95 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_selections() {
+        let toks = lex(".a.b .c(x)");
+        let toks = Input::new(&toks);
+        let (_, (names, _)) = (selections, t("(")).parse_peek(toks).unwrap();
+        assert_eq!(
+            names
+                .into_iter()
+                .map(|(_, name)| name.string)
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_fragments() {
+        assert!(matches!(
+            parse_expression("chan x { x! }"),
+            Ok(Expression::Fork(..))
+        ));
+        assert!(matches!(parse_process("x.a x!"), Ok(Process::Command(..))));
+        assert!(matches!(
+            parse_type("recursive either { .empty!, .item(T) self }"),
+            Ok(Type::Recursive(..))
+        ));
+        assert!(parse_expression("chan x { x! } }").is_err());
+        assert!(parse_expression("").is_err());
+        assert!(parse_process("x! x.a").is_err());
+        assert!(parse_type("either {").is_err());
+    }
+
+    #[test]
+    fn test_keyword_branches() {
+        assert!(matches!(
+            parse_type("either { .if!, .then!, .else! }"),
+            Ok(Type::Either(..))
+        ));
+        assert!(parse_expression("x { .then! => .if!, .else! => .else! }").is_ok());
+        assert!(parse_process("x.then.else x!").is_ok());
+        assert!(parse_expression("if x then .then! else .else!").is_ok());
+    }
+
+    #[test]
+    fn test_definition_parameters() {
+        let program = parse_program("def f(x: A, (y: B) !) : R = x").unwrap();
+        let mut printed = String::new();
+        program.declarations[0].2.pretty(&mut printed, 0).unwrap();
+        assert_eq!(printed, "[A] [(B) !] R");
+        assert!(parse_program("def f(x, y) = x")
+            .unwrap()
+            .declarations
+            .is_empty());
+        assert!(parse_program("def f(x, y: B) : R = x").is_err());
+        assert!(parse_program("def f() = !").is_err());
+    }
+
+    #[test]
+    fn test_recovery() {
+        let input = "
+            def a = chan x { x( }
+            dec b : either { .t! }
+            type = !
+            def c = chan y { y[type T] ) }
+            def d = .t!
+        ";
+        let (program, errors) = parse_program_recovering(input);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            program
+                .definitions
+                .iter()
+                .map(|(_, name, expression)| (
+                    name.string.as_str(),
+                    matches!(expression, Expression::Placeholder(_))
+                ))
+                .collect::<Vec<_>>(),
+            vec![("a", true), ("c", true), ("d", false)]
+        );
+        assert_eq!(program.declarations.len(), 1);
+
+        let input = include_str!("../../examples/sample.par");
+        let (program, errors) = parse_program_recovering(input);
+        assert!(errors.is_empty());
+        assert_eq!(
+            program.definitions.len(),
+            parse_program(input).unwrap().definitions.len()
+        );
+    }
+
     #[test]
     fn test_parse_examples() {
         let input = include_str!("../../examples/sample.par");
@@ -1368,4 +2138,133 @@ mod test {
         let input = "begin the errors";
         assert!(parse_program(input).is_err());
     }
+
+    #[test]
+    fn test_type_round_trip() {
+        let pretty = |typ: &Type<Loc, Name>| {
+            let mut printed = String::new();
+            typ.pretty(&mut printed, 0).unwrap();
+            printed
+        };
+        for input in [
+            include_str!("../../examples/sample.par"),
+            include_str!("../../examples/semigroup_queue.par"),
+            include_str!("../../examples/rock_paper_scissors.par"),
+            include_str!("../../examples/flatten.par"),
+            include_str!("../../examples/fibonacci.par"),
+            include_str!("../../examples/bubble_sort.par"),
+        ] {
+            let mut program = parse_program(input).unwrap();
+            program.add_builtin_types();
+            let types = (program.type_defs.iter().map(|(_, _, _, typ)| typ))
+                .chain(program.declarations.iter().map(|(_, _, typ)| typ));
+            for typ in types {
+                let printed = pretty(typ);
+                let reparsed = parse_type(&printed)
+                    .unwrap_or_else(|error| panic!("{:?} in\n{}", error, printed));
+                assert_eq!(pretty(&reparsed), printed);
+            }
+        }
+    }
+
+    /// A xorshift generator, so that the random types are the same on every run.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn name(&mut self, names: &[&str]) -> Name {
+            Name::from(names[self.below(names.len())].to_owned())
+        }
+
+        fn label(&mut self) -> Option<Name> {
+            (self.below(2) == 0).then(|| self.name(&["a", "next"]))
+        }
+
+        /// A type nested at most `depth` deep, written the way the parser builds it.
+        fn typ(&mut self, depth: usize) -> Type<Loc, Name> {
+            let loc = Loc::External;
+            let names = ["T", "List", "x1", "Option_"];
+            if depth == 0 {
+                return match self.below(4) {
+                    0 => Type::Break(loc),
+                    1 => Type::Continue(loc),
+                    2 => Type::Self_(loc, self.label()),
+                    _ => Type::Name(loc, self.name(&names), vec![]),
+                };
+            }
+            let inner = Box::new(self.typ(depth - 1));
+            match self.below(10) {
+                0 => Type::Chan(loc, inner),
+                1 => Type::Send(loc, inner, Box::new(self.typ(depth - 1))),
+                2 => Type::Receive(loc, inner, Box::new(self.typ(depth - 1))),
+                3 | 4 => {
+                    let branches = (0..1 + self.below(3))
+                        .map(|_| (self.name(&["a", "b", "item", "end"]), self.typ(depth - 1)))
+                        .collect();
+                    match self.below(2) {
+                        0 => Type::Either(loc, branches),
+                        _ => Type::Choice(loc, branches),
+                    }
+                }
+                5 => Type::Recursive(loc, IndexSet::new(), self.label(), inner),
+                6 => Type::Iterative(loc, IndexSet::new(), self.label(), inner),
+                7 => Type::SendType(loc, self.name(&names), inner),
+                8 => Type::ReceiveType(loc, self.name(&names), inner),
+                _ => {
+                    let args = (0..self.below(3)).map(|_| self.typ(depth - 1)).collect();
+                    Type::Name(loc, self.name(&names), args)
+                }
+            }
+        }
+    }
+
+    /// The types directly inside `typ`, which are tried in its place to find a smaller
+    /// one that fails the same way.
+    fn shrink(typ: &Type<Loc, Name>) -> Vec<Type<Loc, Name>> {
+        match typ {
+            Type::Chan(_, body)
+            | Type::Recursive(_, _, _, body)
+            | Type::Iterative(_, _, _, body)
+            | Type::SendType(_, _, body)
+            | Type::ReceiveType(_, _, body) => vec![(**body).clone()],
+            Type::Send(_, first, then) | Type::Receive(_, first, then) => {
+                vec![(**first).clone(), (**then).clone()]
+            }
+            Type::Either(_, branches) | Type::Choice(_, branches) => {
+                branches.values().cloned().collect()
+            }
+            Type::Name(_, _, args) => args.clone(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn test_random_type_round_trip() {
+        let pretty = |typ: &Type<Loc, Name>| {
+            let mut printed = String::new();
+            typ.pretty(&mut printed, 0).unwrap();
+            printed
+        };
+        let fails = |typ: &Type<Loc, Name>| {
+            let printed = pretty(typ);
+            parse_type(&printed).map_or(true, |reparsed| pretty(&reparsed) != printed)
+        };
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..500 {
+            let mut typ = random.typ(4);
+            if !fails(&typ) {
+                continue;
+            }
+            while let Some(smaller) = shrink(&typ).into_iter().find(|smaller| fails(smaller)) {
+                typ = smaller;
+            }
+            panic!("doesn't survive printing and parsing:\n{}", pretty(&typ));
+        }
+    }
 }
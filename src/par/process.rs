@@ -1,3 +1,25 @@
+//! The core language every program is lowered to, and what the type checker and the
+//! runtime work on.
+//!
+//! It is a process calculus with one command per step, in the spirit of CP. All the
+//! surface language's sugar is gone by this point: applications, constructions, and
+//! pattern matching have become sequences of [`Command`]s on named channels, and
+//! every expression is a [`Reference`] to a definition, a [`Fork`] of a new process,
+//! a string literal, a string [`Format`]ted from other strings, a number literal, the
+//! [`Arithmetic`] of two other numbers, or a [`Hole`] still to be filled in. After
+//! [`Expression::fix_captures`], each fork lists the variables it captures, so running
+//! it needs nothing from the enclosing scope beyond those.
+//!
+//! Every node carries the `Loc` of the source it came from, for diagnostics. The
+//! `Typ` parameter is `()` as produced by lowering, and [`Type`] once checked, with
+//! the type of each channel at each step filled in.
+//!
+//! [`Reference`]: Expression::Reference
+//! [`Fork`]: Expression::Fork
+//! [`Format`]: Expression::Format
+//! [`Arithmetic`]: Expression::Arithmetic
+//! [`Hole`]: Expression::Hole
+
 use indexmap::IndexMap;
 use std::{
     fmt::{self, Display, Write},
@@ -5,7 +27,7 @@ use std::{
     sync::Arc,
 };
 
-use super::types::Type;
+use super::{lexer::quote, types::Type};
 
 #[derive(Clone, Debug)]
 pub enum Process<Loc, Name, Typ> {
@@ -51,8 +73,62 @@ pub enum Expression<Loc, Name, Typ> {
         Typ,
         Arc<Process<Loc, Name, Typ>>,
     ),
+    StringLiteral(Loc, Arc<str>),
+    /// A string made by filling the holes of a literal with string variables.
+    Format(Loc, Arc<[StringPart<Loc, Name>]>),
+    /// An expression still to be written. It takes every variable left in the process
+    /// it's in, and fails when run.
+    Hole(Loc, Option<Name>),
+    /// Stands in for an expression that failed to parse, so the rest of the program can
+    /// still be checked. Like a hole, it takes every variable left, and fails when run.
+    Placeholder(Loc),
+    IntLiteral(Loc, i128),
+    /// A number made from two others, each waited for before it's worked out.
+    Arithmetic(Loc, Operator, Arc<Self>, Arc<Self>),
+}
+
+#[derive(Clone, Debug)]
+pub enum StringPart<Loc, Name> {
+    Text(Arc<str>),
+    Hole(Loc, Name),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+impl Operator {
+    /// The result of the operation, or `None` if it doesn't fit.
+    pub fn apply(self, left: i128, right: i128) -> Option<i128> {
+        match self {
+            Self::Add => left.checked_add(right),
+            Self::Subtract => left.checked_sub(right),
+            Self::Multiply => left.checked_mul(right),
+        }
+    }
+
+    /// Whether the result is never negative when both operands aren't.
+    pub fn keeps_natural(self) -> bool {
+        matches!(self, Self::Add | Self::Multiply)
+    }
 }
 
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Add => write!(f, "+"),
+            Self::Subtract => write!(f, "-"),
+            Self::Multiply => write!(f, "*"),
+        }
+    }
+}
+
+/// Global definitions by name, as the runtime looks them up.
+pub type Definitions<Loc, Name, Typ> = IndexMap<Name, Arc<Expression<Loc, Name, Typ>>>;
+
 #[derive(Clone, Debug)]
 pub struct Captures<Loc, Name> {
     pub names: IndexMap<Name, Loc>,
@@ -284,6 +360,37 @@ impl<Loc: Clone, Name: Clone + Hash + Eq, Typ: Clone> Expression<Loc, Name, Typ>
                     caps,
                 )
             }
+            Self::StringLiteral(loc, text) => (
+                Arc::new(Self::StringLiteral(loc.clone(), Arc::clone(text))),
+                Captures::new(),
+            ),
+            Self::Format(loc, parts) => {
+                let mut caps = Captures::new();
+                for part in parts.iter() {
+                    if let StringPart::Hole(loc, name) = part {
+                        caps.add(name.clone(), loc.clone());
+                    }
+                }
+                (Arc::new(Self::Format(loc.clone(), Arc::clone(parts))), caps)
+            }
+            Self::Hole(loc, name) => (
+                Arc::new(Self::Hole(loc.clone(), name.clone())),
+                Captures::new(),
+            ),
+            Self::Placeholder(loc) => (Arc::new(Self::Placeholder(loc.clone())), Captures::new()),
+            Self::IntLiteral(loc, number) => (
+                Arc::new(Self::IntLiteral(loc.clone(), *number)),
+                Captures::new(),
+            ),
+            Self::Arithmetic(loc, operator, left, right) => {
+                let (left, mut caps) = left.fix_captures(loop_points);
+                let (right, caps1) = right.fix_captures(loop_points);
+                caps.extend(caps1);
+                (
+                    Arc::new(Self::Arithmetic(loc.clone(), *operator, left, right)),
+                    caps,
+                )
+            }
         }
     }
 
@@ -300,6 +407,19 @@ impl<Loc: Clone, Name: Clone + Hash + Eq, Typ: Clone> Expression<Loc, Name, Typ>
                 typ.clone(),
                 process.optimize(),
             )),
+            Self::StringLiteral(loc, text) => {
+                Arc::new(Self::StringLiteral(loc.clone(), Arc::clone(text)))
+            }
+            Self::Format(loc, parts) => Arc::new(Self::Format(loc.clone(), Arc::clone(parts))),
+            Self::Hole(loc, name) => Arc::new(Self::Hole(loc.clone(), name.clone())),
+            Self::Placeholder(loc) => Arc::new(Self::Placeholder(loc.clone())),
+            Self::IntLiteral(loc, number) => Arc::new(Self::IntLiteral(loc.clone(), *number)),
+            Self::Arithmetic(loc, operator, left, right) => Arc::new(Self::Arithmetic(
+                loc.clone(),
+                *operator,
+                left.optimize(),
+                right.optimize(),
+            )),
         }
     }
 }
@@ -405,6 +525,21 @@ impl<Loc, Name: Display, Typ> Process<Loc, Name, Typ> {
     }
 }
 
+impl<Loc, Name, Typ> Expression<Loc, Name, Typ> {
+    pub fn loc(&self) -> &Loc {
+        match self {
+            Self::Reference(loc, _, _)
+            | Self::Fork(loc, _, _, _, _, _)
+            | Self::StringLiteral(loc, _)
+            | Self::Format(loc, _)
+            | Self::Hole(loc, _)
+            | Self::Placeholder(loc)
+            | Self::IntLiteral(loc, _)
+            | Self::Arithmetic(loc, _, _, _) => loc,
+        }
+    }
+}
+
 impl<Loc, Name: Display, Typ> Expression<Loc, Name, Typ> {
     pub fn pretty(&self, f: &mut impl Write, indent: usize) -> fmt::Result {
         match self {
@@ -425,6 +560,42 @@ impl<Loc, Name: Display, Typ> Expression<Loc, Name, Typ> {
                 indentation(f, indent)?;
                 write!(f, "}}")
             }
+
+            Self::StringLiteral(_, text) => write!(f, "{}", quote(text)),
+
+            Self::Format(_, parts) => {
+                write!(f, "\"")?;
+                for part in parts.iter() {
+                    match part {
+                        StringPart::Text(text) => {
+                            let quoted = quote(text);
+                            write!(f, "{}", &quoted[1..quoted.len() - 1])?;
+                        }
+                        StringPart::Hole(_, name) => write!(f, "{{{}}}", name)?,
+                    }
+                }
+                write!(f, "\"")
+            }
+
+            Self::Hole(_, name) => {
+                write!(f, "?")?;
+                if let Some(name) = name {
+                    write!(f, "{}", name)?;
+                }
+                Ok(())
+            }
+
+            Self::Placeholder(_) => write!(f, "<did not parse>"),
+
+            Self::IntLiteral(_, number) => write!(f, "{}", number),
+
+            Self::Arithmetic(_, operator, left, right) => {
+                write!(f, "{{ ")?;
+                left.pretty(f, indent)?;
+                write!(f, " {} ", operator)?;
+                right.pretty(f, indent)?;
+                write!(f, " }}")
+            }
         }
     }
 }
@@ -0,0 +1,471 @@
+use std::{
+    fmt::{self, Display, Write},
+    hash::Hash,
+    sync::Arc,
+};
+
+use super::{
+    process::{Command, Expression, Process, StringPart},
+    types::Type,
+};
+
+/// One thing a process does with a channel.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step<Name> {
+    Send,
+    Receive(Name),
+    SendType,
+    ReceiveType(Name),
+    Choose(Name),
+    /// Matching on the channel, with what is done with it in each branch.
+    Match(Vec<(Name, Vec<Self>)>),
+    /// What is done with the channel depends on a branch matched on another one. Only
+    /// the branches that do something with it are listed.
+    Depends(Name, Vec<(Name, Vec<Self>)>),
+    Break,
+    Continue,
+    Begin(Option<Name>),
+    Loop(Option<Name>),
+    Link,
+    /// Handed over whole: sent away, linked to, or captured by a `chan` expression.
+    Moved,
+}
+
+/// Everything a process does with one channel, in order.
+#[derive(Clone, Debug)]
+pub struct Channel<Name, Typ> {
+    pub name: Name,
+    /// The channel's type right before the first thing done with it, if anything is.
+    pub typ: Option<Typ>,
+    pub steps: Vec<Step<Name>>,
+}
+
+/// The channels of a single `chan` expression: its own channel first, then the ones
+/// it captures, then the ones bound inside it, in the order they appear.
+#[derive(Clone, Debug)]
+pub struct Protocol<Name, Typ> {
+    pub channel: Name,
+    pub channels: Vec<Channel<Name, Typ>>,
+}
+
+/// Summarizes every `chan` expression in `expression`, outermost first. A nested one
+/// is summarized on its own, and only shows up as [`Step::Moved`] on what it captures
+/// in the one around it.
+pub fn protocols<Loc, Name, Typ>(
+    expression: &Expression<Loc, Name, Typ>,
+) -> Vec<Protocol<Name, Typ>>
+where
+    Name: Clone + Eq + Hash,
+    Typ: Clone,
+{
+    let mut protocols = Vec::new();
+    collect_protocols(expression, &mut protocols);
+    protocols
+}
+
+fn collect_protocols<Loc, Name, Typ>(
+    expression: &Expression<Loc, Name, Typ>,
+    protocols: &mut Vec<Protocol<Name, Typ>>,
+) where
+    Name: Clone + Eq + Hash,
+    Typ: Clone,
+{
+    let (captures, channel, process) = match expression {
+        Expression::Fork(_, captures, channel, _, _, process) => (captures, channel, process),
+        // The operands of arithmetic may be forks of their own.
+        Expression::Arithmetic(_, _, left, right) => {
+            collect_protocols(left, protocols);
+            collect_protocols(right, protocols);
+            return;
+        }
+        _ => return,
+    };
+    let mut names = vec![channel.clone()];
+    names.extend(captures.names.keys().cloned());
+    bound_names(process, &mut names);
+
+    let channels = names
+        .into_iter()
+        .map(|name| {
+            let mut typ = None;
+            let steps = steps(process, &name, &mut typ);
+            Channel { name, typ, steps }
+        })
+        .collect();
+    protocols.push(Protocol {
+        channel: channel.clone(),
+        channels,
+    });
+
+    nested_forks(process, &mut |expression| {
+        collect_protocols(expression, protocols)
+    });
+}
+
+fn bound_names<Loc, Name: Clone + Eq, Typ>(
+    process: &Process<Loc, Name, Typ>,
+    names: &mut Vec<Name>,
+) {
+    let mut add = |name: &Name| {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    };
+    match process {
+        Process::Let(_, name, _, _, _, process) => {
+            add(name);
+            bound_names(process, names);
+        }
+        Process::Do(_, subject, _, command) => {
+            add(subject);
+            match command {
+                Command::Receive(parameter, _, process) => {
+                    add(parameter);
+                    bound_names(process, names);
+                }
+                Command::Match(_, branches) => {
+                    for process in branches.iter() {
+                        bound_names(process, names);
+                    }
+                }
+                Command::Send(_, process)
+                | Command::Choose(_, process)
+                | Command::Continue(process)
+                | Command::Begin(_, _, process)
+                | Command::SendType(_, process)
+                | Command::ReceiveType(_, process) => bound_names(process, names),
+                Command::Link(_) | Command::Break | Command::Loop(_) => {}
+            }
+        }
+        Process::Telltypes(_, process) => bound_names(process, names),
+    }
+}
+
+fn nested_forks<Loc, Name, Typ>(
+    process: &Process<Loc, Name, Typ>,
+    visit: &mut impl FnMut(&Expression<Loc, Name, Typ>),
+) {
+    match process {
+        Process::Let(_, _, _, _, expression, process) => {
+            visit(expression);
+            nested_forks(process, visit);
+        }
+        Process::Do(_, _, _, command) => match command {
+            Command::Link(expression) => visit(expression),
+            Command::Send(argument, process) => {
+                visit(argument);
+                nested_forks(process, visit);
+            }
+            Command::Match(_, branches) => {
+                for process in branches.iter() {
+                    nested_forks(process, visit);
+                }
+            }
+            Command::Receive(_, _, process)
+            | Command::Choose(_, process)
+            | Command::Continue(process)
+            | Command::Begin(_, _, process)
+            | Command::SendType(_, process)
+            | Command::ReceiveType(_, process) => nested_forks(process, visit),
+            Command::Break | Command::Loop(_) => {}
+        },
+        Process::Telltypes(_, process) => nested_forks(process, visit),
+    }
+}
+
+fn mentions<Loc, Name: Eq + Hash, Typ>(
+    expression: &Expression<Loc, Name, Typ>,
+    name: &Name,
+) -> bool {
+    match expression {
+        Expression::Reference(_, referenced, _) => referenced == name,
+        Expression::Fork(_, captures, _, _, _, _) => captures.names.contains_key(name),
+        Expression::StringLiteral(_, _) | Expression::IntLiteral(_, _) => false,
+        Expression::Arithmetic(_, _, left, right) => mentions(left, name) || mentions(right, name),
+        // A hole takes everything left in its process, and so does a placeholder.
+        Expression::Hole(_, _) | Expression::Placeholder(_) => true,
+        Expression::Format(_, parts) => parts
+            .iter()
+            .any(|part| matches!(part, StringPart::Hole(_, hole) if hole == name)),
+    }
+}
+
+fn steps<Loc, Name, Typ>(
+    mut process: &Process<Loc, Name, Typ>,
+    channel: &Name,
+    typ: &mut Option<Typ>,
+) -> Vec<Step<Name>>
+where
+    Name: Clone + Eq + Hash,
+    Typ: Clone,
+{
+    let mut steps = Vec::new();
+    loop {
+        let next = match process {
+            Process::Let(_, _, _, _, expression, next) => {
+                if mentions(expression, channel) {
+                    steps.push(Step::Moved);
+                }
+                next
+            }
+            Process::Telltypes(_, next) => next,
+
+            Process::Do(_, subject, subject_type, command) if subject == channel => {
+                if typ.is_none() {
+                    *typ = Some(subject_type.clone());
+                }
+                match command {
+                    Command::Link(_) => {
+                        steps.push(Step::Link);
+                        break;
+                    }
+                    Command::Send(argument, next) => {
+                        if mentions(argument, channel) {
+                            steps.push(Step::Moved);
+                        }
+                        steps.push(Step::Send);
+                        next
+                    }
+                    Command::Receive(parameter, _, next) => {
+                        steps.push(Step::Receive(parameter.clone()));
+                        next
+                    }
+                    Command::Choose(chosen, next) => {
+                        steps.push(Step::Choose(chosen.clone()));
+                        next
+                    }
+                    Command::Match(choices, branches) => {
+                        steps.push(Step::Match(branch_steps(choices, branches, channel, typ)));
+                        break;
+                    }
+                    Command::Break => {
+                        steps.push(Step::Break);
+                        break;
+                    }
+                    Command::Continue(next) => {
+                        steps.push(Step::Continue);
+                        next
+                    }
+                    Command::Begin(_, label, next) => {
+                        steps.push(Step::Begin(label.clone()));
+                        next
+                    }
+                    Command::Loop(label) => {
+                        steps.push(Step::Loop(label.clone()));
+                        break;
+                    }
+                    Command::SendType(_, next) => {
+                        steps.push(Step::SendType);
+                        next
+                    }
+                    Command::ReceiveType(parameter, next) => {
+                        steps.push(Step::ReceiveType(parameter.clone()));
+                        next
+                    }
+                }
+            }
+
+            Process::Do(_, subject, _, command) => match command {
+                Command::Link(expression) => {
+                    if mentions(expression, channel) {
+                        steps.push(Step::Moved);
+                    }
+                    break;
+                }
+                Command::Send(argument, next) => {
+                    if mentions(argument, channel) {
+                        steps.push(Step::Moved);
+                    }
+                    next
+                }
+                Command::Match(choices, branches) => {
+                    let mut branches = branch_steps(choices, branches, channel, typ);
+                    branches.retain(|(_, steps)| !steps.is_empty());
+                    if !branches.is_empty() {
+                        steps.push(Step::Depends(subject.clone(), branches));
+                    }
+                    break;
+                }
+                Command::Receive(_, _, next)
+                | Command::Choose(_, next)
+                | Command::Continue(next)
+                | Command::Begin(_, _, next)
+                | Command::SendType(_, next)
+                | Command::ReceiveType(_, next) => next,
+                Command::Break | Command::Loop(_) => break,
+            },
+        };
+        process = next;
+    }
+    steps
+}
+
+fn branch_steps<Loc, Name, Typ>(
+    choices: &[Name],
+    branches: &[Arc<Process<Loc, Name, Typ>>],
+    channel: &Name,
+    typ: &mut Option<Typ>,
+) -> Vec<(Name, Vec<Step<Name>>)>
+where
+    Name: Clone + Eq + Hash,
+    Typ: Clone,
+{
+    choices
+        .iter()
+        .zip(branches)
+        .map(|(choice, process)| (choice.clone(), steps(process, channel, typ)))
+        .collect()
+}
+
+impl<Loc, Name: Display> Protocol<Name, Type<Loc, Name>> {
+    pub fn pretty(&self, f: &mut impl Write) -> fmt::Result {
+        write!(f, "chan {}", self.channel)?;
+        for channel in &self.channels {
+            indentation(f, 1)?;
+            write!(f, "{}", channel.name)?;
+            if let Some(typ) = &channel.typ {
+                write!(f, " : ")?;
+                typ.pretty(f, 2)?;
+            }
+            if channel.steps.is_empty() {
+                indentation(f, 2)?;
+                write!(f, "unused")?;
+            }
+            for step in &channel.steps {
+                step.pretty(f, 2)?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+impl<Name: Display> Step<Name> {
+    fn pretty(&self, f: &mut impl Write, indent: usize) -> fmt::Result {
+        indentation(f, indent)?;
+        match self {
+            Self::Send => write!(f, "send"),
+            Self::Receive(parameter) => write!(f, "receive {}", parameter),
+            Self::SendType => write!(f, "send type"),
+            Self::ReceiveType(parameter) => write!(f, "receive type {}", parameter),
+            Self::Choose(chosen) => write!(f, "choose .{}", chosen),
+            Self::Match(cases) => {
+                write!(f, "match")?;
+                Self::pretty_branches(f, cases, indent)
+            }
+            Self::Depends(subject, cases) => {
+                write!(f, "depending on {}", subject)?;
+                Self::pretty_branches(f, cases, indent)
+            }
+            Self::Break => write!(f, "break"),
+            Self::Continue => write!(f, "continue"),
+            Self::Begin(None) => write!(f, "begin"),
+            Self::Begin(Some(label)) => write!(f, "begin {}", label),
+            Self::Loop(None) => write!(f, "loop"),
+            Self::Loop(Some(label)) => write!(f, "loop {}", label),
+            Self::Link => write!(f, "link"),
+            Self::Moved => write!(f, "moved"),
+        }
+    }
+
+    fn pretty_branches(
+        f: &mut impl Write,
+        branches: &[(Name, Vec<Self>)],
+        indent: usize,
+    ) -> fmt::Result {
+        for (choice, steps) in branches {
+            indentation(f, indent + 1)?;
+            write!(f, ".{} =>", choice)?;
+            for step in steps {
+                step.pretty(f, indent + 2)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn indentation(f: &mut impl Write, indent: usize) -> fmt::Result {
+    writeln!(f)?;
+    for _ in 0..indent {
+        write!(f, "  ")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{compiled::Compiled, par::frontend::Par};
+
+    fn protocols_of(code: &str, definition: &str) -> Vec<String> {
+        let Ok(compiled) = Compiled::from_source(&Par, code) else {
+            panic!("should compile");
+        };
+        let Ok(checked) = compiled.checked else {
+            panic!("should type check");
+        };
+        let (_, _, expression) = (checked.program.definitions.iter())
+            .find(|(_, name, _)| name.to_string() == definition)
+            .expect("definition exists");
+        (protocols(expression).iter())
+            .map(|protocol| {
+                let mut buf = String::new();
+                protocol.pretty(&mut buf).unwrap();
+                buf
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_protocol() {
+        let code = "
+            type Bool = either { .true!, .false! }
+            dec pick : [Bool, Bool] (Bool) !
+            def pick = [b, other] chan result {
+              b {
+                .true! => { result(other)! }
+                .false! => {
+                  other { .true! => {}, .false! => {} }
+                  result(.true!)!
+                }
+              }
+            }
+        ";
+        let protocols = protocols_of(code, "pick");
+        // `[b, other]` itself, `chan result`, and `.true!` built in two places.
+        assert_eq!(protocols.len(), 4);
+        assert_eq!(
+            protocols[1],
+            "chan result
+  result : [Bool] ?
+    depending on b
+      .true =>
+        send
+        break
+      .false =>
+        depending on other
+          .true =>
+            send
+            break
+          .false =>
+            send
+            break
+  other : Bool
+    depending on b
+      .true =>
+        moved
+      .false =>
+        match
+          .true =>
+            continue
+          .false =>
+            continue
+  b : Bool
+    match
+      .true =>
+        continue
+      .false =>
+        continue
+"
+        );
+    }
+}
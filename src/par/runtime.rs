@@ -3,9 +3,17 @@ use futures::{
     task::{Spawn, SpawnExt},
 };
 use indexmap::IndexMap;
-use std::{hash::Hash, sync::Arc};
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    hash::Hash,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use super::process::{Captures, Command, Expression, Process};
+use super::process::{Captures, Command, Definitions, Expression, Process, StringPart};
 
 #[derive(Clone, Debug)]
 pub enum Error<Loc, Name> {
@@ -14,6 +22,14 @@ pub enum Error<Loc, Name> {
     UnfulfilledObligations(Loc, Vec<Name>),
     IncompatibleOperations(Operation<Loc, Name>, Operation<Loc, Name>),
     NoSuchLoopPoint(Loc, Option<Name>),
+    /// A hole was run before it was filled in.
+    Hole(Loc, Option<Name>),
+    /// Something that failed to parse was run.
+    Placeholder(Loc),
+    /// The run took all the steps it was allowed, and was stopped here.
+    OutOfSteps(Loc, usize),
+    /// Arithmetic made a number too large to hold.
+    Overflow(Loc),
     Multiple(Box<Self>, Box<Self>),
 }
 
@@ -26,6 +42,8 @@ pub enum Operation<Loc, Name> {
     Match(Loc, Arc<[Name]>),
     Break(Loc),
     Continue(Loc),
+    String(Loc),
+    Int(Loc),
 }
 
 pub enum Message<Loc, Name> {
@@ -33,6 +51,8 @@ pub enum Message<Loc, Name> {
     Send(Loc, Value<Loc, Name>, oneshot::Receiver<Self>),
     Choose(Loc, Name, oneshot::Receiver<Self>),
     Break(Loc),
+    String(Loc, Arc<str>),
+    Int(Loc, i128),
     Error(Error<Loc, Name>),
 }
 
@@ -49,6 +69,8 @@ impl<Loc, Name> Message<Loc, Name> {
                 Ok((Operation::Choose(loc, chosen), vec![Value::Receiver(rx)]))
             }
             Message::Break(loc) => Ok((Operation::Break(loc), vec![])),
+            Message::String(loc, _) => Ok((Operation::String(loc), vec![])),
+            Message::Int(loc, _) => Ok((Operation::Int(loc), vec![])),
             Message::Error(error) => Err(error),
         }
     }
@@ -59,15 +81,44 @@ pub enum Request<Loc, Name> {
     Receive(Loc),
     Match(Loc, Arc<[Name]>),
     Continue(Loc),
+    /// Asks for a string, as a string's contents are needed to format another one.
+    String(Loc),
+    /// Asks for a number, as its value is needed for arithmetic.
+    Int(Loc, Number),
     Dynamic(Loc),
 }
 
+/// Which numbers a request for one takes, as the type checker found them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Number {
+    Int,
+    Nat,
+}
+
+impl Number {
+    /// Whether `number` is one of these.
+    pub fn contains(self, number: i128) -> bool {
+        self == Self::Int || number >= 0
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int => write!(f, "number"),
+            Self::Nat => write!(f, "natural number"),
+        }
+    }
+}
+
 impl<Loc, Name> Request<Loc, Name> {
     pub fn into_operation(self) -> Operation<Loc, Name> {
         match self {
             Request::Receive(loc) => Operation::Receive(loc),
             Request::Match(loc, choices) => Operation::Match(loc, choices),
             Request::Continue(loc) => Operation::Continue(loc),
+            Request::String(loc) => Operation::String(loc),
+            Request::Int(loc, _) => Operation::Int(loc),
             Request::Dynamic(loc) => Operation::Unknown(loc),
         }
     }
@@ -80,6 +131,8 @@ impl<Loc, Name> Request<Loc, Name> {
             (Self::Receive(_), Self::Receive(_)) => true,
             (Self::Match(_, _), Self::Match(_, _)) => true,
             (Self::Continue(_), Self::Continue(_)) => true,
+            (Self::String(_), Self::String(_)) => true,
+            (Self::Int(_, _), Self::Int(_, _)) => true,
             (_, _) => false,
         }
     }
@@ -92,9 +145,15 @@ pub enum Value<Loc, Name> {
 
 pub struct Context<Loc, Name, Typ> {
     spawner: Arc<dyn Spawn + Send + Sync>,
-    globals: Arc<IndexMap<Name, Arc<Expression<Loc, Name, Typ>>>>,
+    globals: Arc<Definitions<Loc, Name, Typ>>,
     variables: IndexMap<Name, Value<Loc, Name>>,
     loop_points: IndexMap<Option<Name>, (Name, Arc<Process<Loc, Name, Typ>>)>,
+    max_steps: Option<usize>,
+    /// Commands run so far, by this context and every one split from it.
+    steps: Arc<AtomicUsize>,
+    /// Where arithmetic takes a number the type checker found to be a `Nat`, by the
+    /// location of the operand.
+    naturals: Arc<HashSet<Loc>>,
 }
 
 impl<Loc, Name, Typ> Context<Loc, Name, Typ>
@@ -105,16 +164,31 @@ where
 {
     pub fn new(
         spawner: Arc<dyn Spawn + Send + Sync>,
-        globals: Arc<IndexMap<Name, Arc<Expression<Loc, Name, Typ>>>>,
+        globals: Arc<Definitions<Loc, Name, Typ>>,
     ) -> Self {
         Self {
             spawner,
             globals,
             variables: IndexMap::new(),
             loop_points: IndexMap::new(),
+            max_steps: None,
+            steps: Arc::new(AtomicUsize::new(0)),
+            naturals: Arc::default(),
         }
     }
 
+    /// Stops the run with an error once it has run `max_steps` commands, counting
+    /// every process in it, so that a program that doesn't finish can't run forever.
+    pub fn with_max_steps(self, max_steps: Option<usize>) -> Self {
+        Self { max_steps, ..self }
+    }
+
+    /// Asks for a `Nat` wherever arithmetic takes one of `naturals`, instead of any
+    /// number.
+    pub fn with_naturals(self, naturals: Arc<HashSet<Loc>>) -> Self {
+        Self { naturals, ..self }
+    }
+
     pub fn spawner(&self) -> Arc<dyn Spawn + Send + Sync> {
         Arc::clone(&self.spawner)
     }
@@ -125,6 +199,9 @@ where
             globals: Arc::clone(&self.globals),
             variables: IndexMap::new(),
             loop_points: self.loop_points.clone(),
+            max_steps: self.max_steps,
+            steps: Arc::clone(&self.steps),
+            naturals: Arc::clone(&self.naturals),
         }
     }
 
@@ -197,6 +274,114 @@ where
 
                 Ok(Value::Receiver(rx))
             }
+
+            Expression::StringLiteral(loc, text) => {
+                let (tx, rx) = oneshot::channel();
+                tx.send(Message::String(loc.clone(), Arc::clone(text)))
+                    .ok()
+                    .expect("receiver dropped");
+                Ok(Value::Receiver(rx))
+            }
+
+            Expression::Hole(loc, name) => Err(Error::Hole(loc.clone(), name.clone())),
+
+            Expression::Placeholder(loc) => Err(Error::Placeholder(loc.clone())),
+
+            Expression::IntLiteral(loc, number) => {
+                let (tx, rx) = oneshot::channel();
+                tx.send(Message::Int(loc.clone(), *number))
+                    .ok()
+                    .expect("receiver dropped");
+                Ok(Value::Receiver(rx))
+            }
+
+            Expression::Arithmetic(loc, operator, left, right) => {
+                let [left_number, right_number] = [left, right].map(|operand| {
+                    if self.naturals.contains(operand.loc()) {
+                        Number::Nat
+                    } else {
+                        Number::Int
+                    }
+                });
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+
+                let (tx, rx) = oneshot::channel();
+                let mut context = self.split();
+                let (loc, operator) = (loc.clone(), *operator);
+                self.spawner
+                    .spawn(async move {
+                        let result = match context.int_of(loc.clone(), left_number, left).await {
+                            Ok(left) => {
+                                match context.int_of(loc.clone(), right_number, right).await {
+                                    Ok(right) => operator
+                                        .apply(left, right)
+                                        .ok_or_else(|| Error::Overflow(loc.clone())),
+                                    Err(error) => Err(error),
+                                }
+                            }
+                            Err(error) => context.throw([right], error),
+                        };
+                        let message = match result {
+                            Ok(number) => Message::Int(loc, number),
+                            Err(error) => Message::Error(error),
+                        };
+                        tx.send(message).ok().expect("receiver dropped");
+                    })
+                    .expect("could not spawn");
+
+                Ok(Value::Receiver(rx))
+            }
+
+            Expression::Format(loc, parts) => {
+                let mut pieces = Vec::new();
+                for part in parts.iter() {
+                    pieces.push(match part {
+                        StringPart::Text(text) => StringPart::Text(Arc::clone(text)),
+                        StringPart::Hole(loc, name) => {
+                            StringPart::Hole(loc.clone(), self.get(loc, name)?)
+                        }
+                    });
+                }
+
+                let (tx, rx) = oneshot::channel();
+                let mut context = self.split();
+                let loc = loc.clone();
+                self.spawner
+                    .spawn(async move {
+                        let mut formatted = String::new();
+                        let mut pieces = pieces.into_iter();
+                        while let Some(piece) = pieces.next() {
+                            let (loc, value) = match piece {
+                                StringPart::Text(text) => {
+                                    formatted.push_str(&text);
+                                    continue;
+                                }
+                                StringPart::Hole(loc, value) => (loc, value),
+                            };
+                            match context.string_of(loc, value).await {
+                                Ok(text) => formatted.push_str(&text),
+                                Err(error) => {
+                                    let rest = pieces.filter_map(|piece| match piece {
+                                        StringPart::Hole(_, value) => Some(value),
+                                        StringPart::Text(_) => None,
+                                    });
+                                    let error = context.throw::<()>(rest, error).unwrap_err();
+                                    tx.send(Message::Error(error))
+                                        .ok()
+                                        .expect("receiver dropped");
+                                    return;
+                                }
+                            }
+                        }
+                        tx.send(Message::String(loc, Arc::from(formatted)))
+                            .ok()
+                            .expect("receiver dropped");
+                    })
+                    .expect("could not spawn");
+
+                Ok(Value::Receiver(rx))
+            }
         }
     }
 
@@ -215,6 +400,11 @@ where
 
                 Process::Do(loc, object_name, _, command) => {
                     let object = self.get(loc, object_name)?;
+                    if let Some(max_steps) = self.max_steps {
+                        if self.steps.fetch_add(1, Ordering::Relaxed) >= max_steps {
+                            return self.throw([object], Error::OutOfSteps(loc.clone(), max_steps));
+                        }
+                    }
 
                     match command {
                         Command::Link(expression) => {
@@ -462,6 +652,53 @@ where
         }
     }
 
+    /// Waits for the string `object` stands for.
+    pub async fn string_of(
+        &mut self,
+        loc: Loc,
+        object: Value<Loc, Name>,
+    ) -> Result<Arc<str>, Error<Loc, Name>> {
+        let mut rx = match object {
+            Value::Receiver(rx) => rx,
+            Value::Sender(tx) => self.swap(Request::String(loc.clone()), tx),
+        };
+        loop {
+            match rx.await.ok().expect("sender dropped") {
+                Message::Swap(Request::Dynamic(_), tx) => {
+                    rx = self.swap(Request::String(loc.clone()), tx);
+                    continue;
+                }
+                Message::String(_, text) => return Ok(text),
+                message => return self.invalid_message_and_request(message, Request::String(loc)),
+            }
+        }
+    }
+
+    /// Waits for the number `object` stands for, asking for a `number` if it's asked for.
+    pub async fn int_of(
+        &mut self,
+        loc: Loc,
+        number: Number,
+        object: Value<Loc, Name>,
+    ) -> Result<i128, Error<Loc, Name>> {
+        let mut rx = match object {
+            Value::Receiver(rx) => rx,
+            Value::Sender(tx) => self.swap(Request::Int(loc.clone(), number), tx),
+        };
+        loop {
+            match rx.await.ok().expect("sender dropped") {
+                Message::Swap(Request::Dynamic(_), tx) => {
+                    rx = self.swap(Request::Int(loc.clone(), number), tx);
+                    continue;
+                }
+                Message::Int(_, number) => return Ok(number),
+                message => {
+                    return self.invalid_message_and_request(message, Request::Int(loc, number))
+                }
+            }
+        }
+    }
+
     async fn cannot_have_obligations<V: IntoIterator<Item = Value<Loc, Name>>>(
         &mut self,
         loc: &Loc,
@@ -580,7 +817,7 @@ where
                                     pending.push(Value::Receiver(rx));
                                 }
                                 Message::Choose(_, _, rx) => pending.push(Value::Receiver(rx)),
-                                Message::Break(_) => (),
+                                Message::Break(_) | Message::String(_, _) | Message::Int(_, _) => {}
                                 Message::Error(error1) => {
                                     error = Error::Multiple(Box::new(error), Box::new(error1))
                                 }
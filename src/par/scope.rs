@@ -0,0 +1,353 @@
+//! Works out which binding each variable in a definition refers to.
+//!
+//! Type checking already rejects a variable that isn't in scope. This pass only finds
+//! out where such a variable *is* bound, such as in another branch of the same match,
+//! so that the error can point there.
+
+use super::{
+    language::{
+        Apply, ApplyBranch, Command, CommandBranch, Construct, ConstructBranch, Expression,
+        Pattern, Process,
+    },
+    process::StringPart,
+};
+
+/// The variables used and bound in one definition.
+#[derive(Clone, Debug)]
+pub struct Scopes<Loc, Name> {
+    /// Every variable used in an expression.
+    pub uses: Vec<Use<Loc, Name>>,
+    /// Every variable bound, by a pattern, a `chan`, or a branch.
+    pub binders: Vec<(Loc, Name)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Use<Loc, Name> {
+    pub loc: Loc,
+    pub name: Name,
+    /// Where the variable is bound, if it's in the scope of a binding at all. One that
+    /// isn't may still be a global definition.
+    pub binder: Option<Loc>,
+}
+
+impl<Loc: Clone + Eq, Name: Clone + Eq> Scopes<Loc, Name> {
+    pub fn of(expression: &Expression<Loc, Name>) -> Self {
+        let mut resolver = Resolver {
+            bound: Vec::new(),
+            scopes: Self {
+                uses: Vec::new(),
+                binders: Vec::new(),
+            },
+        };
+        resolver.expression(expression);
+        resolver.scopes
+    }
+
+    /// Where `name`, used at `loc` outside the scope of any binding, is bound elsewhere.
+    pub fn out_of_reach(&self, loc: &Loc, name: &Name) -> Vec<Loc> {
+        let unbound = (self.uses.iter())
+            .any(|used| &used.loc == loc && &used.name == name && used.binder.is_none());
+        if !unbound {
+            return Vec::new();
+        }
+        (self.binders.iter())
+            .filter(|(_, bound)| bound == name)
+            .map(|(binder, _)| binder.clone())
+            .collect()
+    }
+}
+
+struct Resolver<Loc, Name> {
+    /// The variables in scope, innermost last.
+    bound: Vec<(Loc, Name)>,
+    scopes: Scopes<Loc, Name>,
+}
+
+impl<Loc: Clone + Eq, Name: Clone + Eq> Resolver<Loc, Name> {
+    /// Runs `f`, then forgets everything it bound.
+    fn scoped(&mut self, f: impl FnOnce(&mut Self)) {
+        let bound = self.bound.len();
+        f(self);
+        self.bound.truncate(bound);
+    }
+
+    fn bind(&mut self, loc: &Loc, name: &Name) {
+        self.bound.push((loc.clone(), name.clone()));
+        self.scopes.binders.push((loc.clone(), name.clone()));
+    }
+
+    fn refer(&mut self, loc: &Loc, name: &Name) {
+        let binder = (self.bound.iter().rev())
+            .find(|(_, bound)| bound == name)
+            .map(|(binder, _)| binder.clone());
+        self.scopes.uses.push(Use {
+            loc: loc.clone(),
+            name: name.clone(),
+            binder,
+        });
+    }
+
+    fn pattern(&mut self, pattern: &Pattern<Loc, Name>) {
+        match pattern {
+            Pattern::Name(loc, name, _) => self.bind(loc, name),
+            Pattern::Receive(_, first, rest) => {
+                self.pattern(first);
+                self.pattern(rest);
+            }
+            Pattern::Continue(_) => {}
+            Pattern::ReceiveType(_, _, rest) => self.pattern(rest),
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression<Loc, Name>) {
+        match expression {
+            Expression::Reference(loc, name) => self.refer(loc, name),
+            Expression::Format(_, parts) => {
+                for part in parts {
+                    if let StringPart::Hole(loc, name) = part {
+                        self.refer(loc, name);
+                    }
+                }
+            }
+            Expression::StringLiteral(_, _)
+            | Expression::IntLiteral(_, _)
+            | Expression::Placeholder(_)
+            | Expression::Hole(_, _) => {}
+            Expression::Arithmetic(_, _, left, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            Expression::Let(_, pattern, value, body) => {
+                self.expression(value);
+                self.scoped(|this| {
+                    this.pattern(pattern);
+                    this.expression(body);
+                });
+            }
+            Expression::Do(_, process, body) => self.scoped(|this| {
+                this.process(process);
+                this.expression(body);
+            }),
+            Expression::Fork(loc, name, _, process) => self.scoped(|this| {
+                this.bind(loc, name);
+                this.process(process);
+            }),
+            Expression::Construction(_, construct) => self.scoped(|this| this.construct(construct)),
+            Expression::Application(_, expression, apply) => {
+                self.expression(expression);
+                self.scoped(|this| this.apply(apply));
+            }
+        }
+    }
+
+    fn construct(&mut self, construct: &Construct<Loc, Name>) {
+        match construct {
+            Construct::Then(_, expression) => self.expression(expression),
+            Construct::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.construct(rest);
+            }
+            Construct::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.construct(rest);
+            }
+            Construct::Choose(_, _, rest)
+            | Construct::Begin(_, _, _, rest)
+            | Construct::SendType(_, _, rest)
+            | Construct::ReceiveType(_, _, rest) => self.construct(rest),
+            Construct::Either(_, branches) => {
+                for branch in branches.0.values() {
+                    self.scoped(|this| this.construct_branch(branch));
+                }
+            }
+            Construct::Break(_) | Construct::Loop(_, _) => {}
+        }
+    }
+
+    fn construct_branch(&mut self, branch: &ConstructBranch<Loc, Name>) {
+        match branch {
+            ConstructBranch::Then(_, expression) => self.expression(expression),
+            ConstructBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.construct_branch(rest);
+            }
+            ConstructBranch::ReceiveType(_, _, rest) => self.construct_branch(rest),
+        }
+    }
+
+    fn apply(&mut self, apply: &Apply<Loc, Name>) {
+        match apply {
+            Apply::Noop(_) | Apply::Loop(_, _) => {}
+            Apply::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.apply(rest);
+            }
+            Apply::Choose(_, _, rest)
+            | Apply::Begin(_, _, _, rest)
+            | Apply::SendType(_, _, rest) => self.apply(rest),
+            Apply::Either(_, branches) => {
+                for branch in branches.0.values() {
+                    self.scoped(|this| this.apply_branch(branch));
+                }
+            }
+        }
+    }
+
+    fn apply_branch(&mut self, branch: &ApplyBranch<Loc, Name>) {
+        match branch {
+            ApplyBranch::Then(loc, name, expression) => {
+                self.bind(loc, name);
+                self.expression(expression);
+            }
+            ApplyBranch::Continue(_, expression) => self.expression(expression),
+            ApplyBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.apply_branch(rest);
+            }
+            ApplyBranch::ReceiveType(_, _, rest) => self.apply_branch(rest),
+        }
+    }
+
+    /// Names a process binds stay in scope for the rest of the scope it's in.
+    fn process(&mut self, process: &Process<Loc, Name>) {
+        match process {
+            Process::Let(_, pattern, value, rest) => {
+                self.expression(value);
+                self.pattern(pattern);
+                self.process(rest);
+            }
+            Process::Command(_, command) => self.command(command),
+            Process::Telltypes(_, rest) => self.process(rest),
+            Process::Noop(_) => {}
+        }
+    }
+
+    fn command(&mut self, command: &Command<Loc, Name>) {
+        match command {
+            Command::Then(process) | Command::Continue(_, process) => self.process(process),
+            Command::Link(_, expression) => self.expression(expression),
+            Command::Send(_, argument, rest) => {
+                self.expression(argument);
+                self.command(rest);
+            }
+            Command::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.command(rest);
+            }
+            Command::Choose(_, _, rest)
+            | Command::Begin(_, _, _, rest)
+            | Command::SendType(_, _, rest)
+            | Command::ReceiveType(_, _, rest) => self.command(rest),
+            Command::Either(_, branches, pass) => {
+                // The process after the branches continues each of them, so it sees
+                // whatever any of them bound.
+                let outer = self.bound.len();
+                let mut left = Vec::new();
+                for branch in branches.0.values() {
+                    self.command_branch(branch);
+                    left.extend(self.bound.drain(outer..));
+                }
+                if let Some(process) = pass {
+                    self.bound.extend(left);
+                    self.process(process);
+                }
+            }
+            Command::Break(_) | Command::Loop(_, _) => {}
+        }
+    }
+
+    fn command_branch(&mut self, branch: &CommandBranch<Loc, Name>) {
+        match branch {
+            CommandBranch::Then(process) | CommandBranch::Continue(_, process) => {
+                self.process(process)
+            }
+            CommandBranch::Receive(_, pattern, rest) => {
+                self.pattern(pattern);
+                self.command_branch(rest);
+            }
+            CommandBranch::ReceiveType(_, _, rest) => self.command_branch(rest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::par::parse::{parse_program, Loc, Name};
+
+    fn scopes(source: &str) -> Scopes<Loc, Name> {
+        let program = parse_program(source).unwrap();
+        Scopes::of(&program.definitions[0].2)
+    }
+
+    /// The uses in the first definition, each with the column its binder is at.
+    fn uses(source: &str) -> Vec<(String, Option<usize>)> {
+        (scopes(source).uses.iter())
+            .map(|used| {
+                let column = used.binder.as_ref().map(|binder| match binder {
+                    Loc::Code { column, .. } => *column,
+                    Loc::External => 0,
+                });
+                (used.name.to_string(), column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let found = uses("def f = [x] [x] x");
+        assert_eq!(found, vec![("x".to_owned(), Some(14))]);
+        let found = uses("def f = [x] let x = x in x");
+        assert_eq!(
+            found,
+            vec![("x".to_owned(), Some(10)), ("x".to_owned(), Some(17))]
+        );
+        let found = uses("def f = [y] g(y) { .a y => y, .b! => y }");
+        assert_eq!(
+            found,
+            vec![
+                ("g".to_owned(), None),
+                ("y".to_owned(), Some(10)),
+                ("y".to_owned(), Some(23)),
+                ("y".to_owned(), Some(10)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branches_dont_leak() {
+        let source = "def f = [o] o { .some x => x, .none! => x }";
+        let found = uses(source);
+        assert_eq!(
+            found,
+            vec![
+                ("o".to_owned(), Some(10)),
+                ("x".to_owned(), Some(23)),
+                ("x".to_owned(), None),
+            ]
+        );
+        let scopes = scopes(source);
+        let used = &scopes.uses[2];
+        assert_eq!(scopes.out_of_reach(&used.loc, &used.name).len(), 1);
+        let used = &scopes.uses[1];
+        assert!(scopes.out_of_reach(&used.loc, &used.name).is_empty());
+
+        let found = uses("def f = { .a => [x] !, .b => x }");
+        assert_eq!(found, vec![("x".to_owned(), None)]);
+        let found = uses("def f = let y = [x] x in x");
+        assert_eq!(
+            found,
+            vec![("x".to_owned(), Some(18)), ("x".to_owned(), None)]
+        );
+    }
+
+    #[test]
+    fn test_process_scopes() {
+        // Names bound in a branch are seen after the branches, but not in the others.
+        let found = uses("def f = chan c { c { .a => { let x = ! }, .b => { c <> x } } c <> x }");
+        assert_eq!(
+            found,
+            vec![("x".to_owned(), None), ("x".to_owned(), Some(34))]
+        );
+    }
+}
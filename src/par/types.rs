@@ -7,8 +7,10 @@ use std::{
 };
 
 use super::{
+    mistakes,
     parse::Program,
-    process::{Captures, Command, Expression, Process},
+    process::{Captures, Command, Expression, Operator, Process, StringPart},
+    suggest,
 };
 use crate::par::parse::Loc;
 use miette::LabeledSpan;
@@ -25,21 +27,31 @@ pub enum TypeError<Loc, Name> {
     DependencyCycle(Loc, Vec<Name>),
     WrongNumberOfTypeArgs(Loc, Name, usize, usize),
     NameNotDefined(Loc, Name),
+    /// A name used outside the scope of the places it's bound in, which are listed.
+    NameNotInScope(Loc, Name, Vec<Loc>),
     ShadowedObligation(Loc, Name),
     TypeMustBeKnownAtThisPoint(Loc, Name),
     ParameterTypeMustBeKnown(Loc, Name, Name),
     CannotAssignFromTo(Loc, Type<Loc, Name>, Type<Loc, Name>),
     UnfulfilledObligations(Loc, Vec<Name>),
     InvalidOperation(Loc, Operation<Loc, Name>, Type<Loc, Name>),
+    /// More values were sent or received at once than the type has room for: how many
+    /// it has, how many were given, and what the type is after the ones that fit.
+    WrongNumberOfValues(Loc, usize, usize, Type<Loc, Name>),
     InvalidBranch(Loc, Name, Type<Loc, Name>),
-    MissingBranch(Loc, Name, Type<Loc, Name>),
-    RedundantBranch(Loc, Name, Type<Loc, Name>),
+    InvalidBranchAfterSelection(Loc, Vec<Name>, Name, Type<Loc, Name>),
+    MissingBranch(Loc, Vec<Name>, Type<Loc, Name>),
+    RedundantBranch(Loc, Vec<Name>, Type<Loc, Name>),
     TypesCannotBeUnified(Type<Loc, Name>, Type<Loc, Name>),
     NoSuchLoopPoint(Loc, Option<Name>),
     DoesNotDescendSubjectOfBegin(Loc, Option<Name>),
     LoopVariableNotPreserved(Loc, Name),
     LoopVariableChangedType(Loc, Name, Type<Loc, Name>, Type<Loc, Name>),
     Telltypes(Loc, IndexMap<Name, Type<Loc, Name>>),
+    /// A hole where nothing says what type it needs to be.
+    HoleTypeMustBeKnown(Loc),
+    /// Something that failed to parse where nothing says what type it was meant to be.
+    PlaceholderTypeMustBeKnown(Loc),
 }
 
 #[derive(Clone, Debug)]
@@ -72,6 +84,13 @@ pub enum Type<Loc, Name> {
     Self_(Loc, Option<Name>),
     SendType(Loc, Name, Box<Self>),
     ReceiveType(Loc, Name, Box<Self>),
+    /// The builtin type of string literals. Nothing can be done with a string but
+    /// pass it on, so its dual is only ever linked with one.
+    String(Loc),
+    /// The builtin numbers, which take part in arithmetic. A `Nat` is never negative,
+    /// and goes wherever an `Int` does.
+    Int(Loc),
+    Nat(Loc),
 }
 
 #[derive(Clone, Debug)]
@@ -233,7 +252,7 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> TypeDefs<Loc, Name> {
                     self.validate_type(t, deps, self_pos, self_neg)?;
                 }
             }
-            Type::Break(_) | Type::Continue(_) => (),
+            Type::Break(_) | Type::Continue(_) | Type::String(_) | Type::Int(_) | Type::Nat(_) => {}
             Type::Recursive(_, _, label, body) | Type::Iterative(_, _, label, body) => {
                 let (mut self_pos, mut self_neg) = (self_pos.clone(), self_neg.clone());
                 self_pos.insert(label.clone());
@@ -280,6 +299,9 @@ impl<Loc, Name> Type<Loc, Name> {
             Self::Self_(loc, _) => loc,
             Self::SendType(loc, _, _) => loc,
             Self::ReceiveType(loc, _, _) => loc,
+            Self::String(loc) => loc,
+            Self::Int(loc) => loc,
+            Self::Nat(loc) => loc,
         }
     }
 }
@@ -315,6 +337,9 @@ impl<Loc, Name: Eq + Hash> Type<Loc, Name> {
                     .collect(),
             ),
             Self::Break(loc) => Type::Break(loc),
+            Self::String(loc) => Type::String(loc),
+            Self::Int(loc) => Type::Int(loc),
+            Self::Nat(loc) => Type::Nat(loc),
             Self::Continue(loc) => Type::Continue(loc),
             Self::Recursive(loc, asc, label, body) => Type::Recursive(
                 loc,
@@ -398,6 +423,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
             ),
             Self::Break(loc) => Self::Break(loc),
             Self::Continue(loc) => Self::Continue(loc),
+            Self::String(loc) => Self::String(loc),
+            Self::Int(loc) => Self::Int(loc),
+            Self::Nat(loc) => Self::Nat(loc),
 
             Self::Recursive(loc, asc, label, body) => {
                 Self::Recursive(loc, asc, label, Box::new(body.substitute(var, typ)?))
@@ -509,6 +537,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
             }
             (Self::Break(_), Self::Break(_)) => true,
             (Self::Continue(_), Self::Continue(_)) => true,
+            (Self::String(_), Self::String(_)) => true,
+            (Self::Int(_), Self::Int(_)) => true,
+            (Self::Nat(_), Self::Nat(_) | Self::Int(_)) => true,
 
             (Self::Recursive(_, asc1, label1, body1), Self::Recursive(_, asc2, label2, body2)) => {
                 if !asc2.iter().all(|label| asc1.contains(label)) {
@@ -568,6 +599,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
             Self::Var(loc, name) => {
                 Self::Chan(loc.clone(), Box::new(Self::Var(loc.clone(), name.clone())))
             }
+            Self::String(loc) => Self::Chan(loc.clone(), Box::new(Self::String(loc.clone()))),
+            Self::Int(loc) => Self::Chan(loc.clone(), Box::new(Self::Int(loc.clone()))),
+            Self::Nat(loc) => Self::Chan(loc.clone(), Box::new(Self::Nat(loc.clone()))),
             Self::Name(loc, name, args) => match type_defs.get_dual(loc, name, args) {
                 Ok(dual) => dual,
                 Err(_) => Self::Chan(
@@ -664,6 +698,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
                     .collect(),
             ),
             Self::Break(loc) => Self::Break(loc.clone()),
+            Self::String(loc) => Self::String(loc.clone()),
+            Self::Int(loc) => Self::Int(loc.clone()),
+            Self::Nat(loc) => Self::Nat(loc.clone()),
             Self::Continue(loc) => Self::Continue(loc.clone()),
 
             Self::Recursive(loc, asc, label1, t) => {
@@ -774,6 +811,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
                     .collect::<Result<_, _>>()?,
             ),
             Self::Break(loc) => Self::Break(loc),
+            Self::String(loc) => Self::String(loc),
+            Self::Int(loc) => Self::Int(loc),
+            Self::Nat(loc) => Self::Nat(loc),
             Self::Continue(loc) => Self::Continue(loc),
 
             Self::Recursive(loc, asc, label, t) => {
@@ -894,6 +934,9 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
                     .collect::<Result<_, _>>()?,
             ),
             Self::Break(loc) => Self::Break(loc),
+            Self::String(loc) => Self::String(loc),
+            Self::Int(loc) => Self::Int(loc),
+            Self::Nat(loc) => Self::Nat(loc),
             Self::Continue(loc) => Self::Continue(loc),
 
             Self::Recursive(loc, asc, label, t) => Self::Recursive(
@@ -964,6 +1007,8 @@ impl<Loc: Clone, Name: Clone + Eq + Hash> Type<Loc, Name> {
                 }
             }
             Self::Break(_) => {}
+            Self::String(_) => {}
+            Self::Int(_) | Self::Nat(_) => {}
             Self::Continue(_) => {}
 
             Self::Recursive(_, asc, _, t) => {
@@ -996,11 +1041,25 @@ pub struct Context<Loc, Name> {
     declarations: Arc<IndexMap<Name, (Loc, Type<Loc, Name>)>>,
     unchecked_definitions: Arc<IndexMap<Name, (Loc, Arc<Expression<Loc, Name, ()>>)>>,
     checked_definitions: Arc<RwLock<IndexMap<Name, CheckedDef<Loc, Name>>>>,
+    holes: Arc<RwLock<IndexMap<Loc, Hole<Loc, Name>>>>,
+    /// Where arithmetic takes a `Nat`, by the location of the operand.
+    naturals: Arc<RwLock<HashSet<Loc>>>,
     current_deps: IndexSet<Name>,
     variables: IndexMap<Name, Type<Loc, Name>>,
     loop_points: IndexMap<Option<Name>, (Name, Arc<IndexMap<Name, Type<Loc, Name>>>)>,
 }
 
+/// A hole met while checking, and what there is to fill it with.
+#[derive(Clone, Debug)]
+pub struct Hole<Loc, Name> {
+    pub loc: Loc,
+    pub name: Option<Name>,
+    /// The type the hole needs to be.
+    pub typ: Type<Loc, Name>,
+    /// The variables left in its process, which it takes.
+    pub variables: IndexMap<Name, Type<Loc, Name>>,
+}
+
 #[derive(Clone, Debug)]
 struct CheckedDef<Loc, Name> {
     loc: Loc,
@@ -1050,6 +1109,8 @@ where
             declarations: Arc::new(declarations),
             unchecked_definitions: Arc::new(unchecked_definitions),
             checked_definitions: Arc::new(RwLock::new(IndexMap::new())),
+            holes: Arc::new(RwLock::new(IndexMap::new())),
+            naturals: Arc::new(RwLock::new(HashSet::new())),
             current_deps: IndexSet::new(),
             variables: IndexMap::new(),
             loop_points: IndexMap::new(),
@@ -1122,12 +1183,35 @@ where
             .collect()
     }
 
+    /// The type of each checked definition, declared or inferred.
+    pub fn get_checked_types(&self) -> IndexMap<Name, Type<Loc, Name>> {
+        self.checked_definitions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, checked)| (name.clone(), checked.typ.clone()))
+            .collect()
+    }
+
+    /// The holes in the checked definitions, in the order they were met.
+    pub fn get_holes(&self) -> Vec<Hole<Loc, Name>> {
+        self.holes.read().unwrap().values().cloned().collect()
+    }
+
+    /// Where arithmetic in the checked definitions takes a `Nat`, by the location of the
+    /// operand, so that a run can ask for one there.
+    pub fn get_naturals(&self) -> HashSet<Loc> {
+        self.naturals.read().unwrap().clone()
+    }
+
     pub fn split(&self) -> Self {
         Self {
             type_defs: self.type_defs.clone(),
             declarations: self.declarations.clone(),
             unchecked_definitions: self.unchecked_definitions.clone(),
             checked_definitions: self.checked_definitions.clone(),
+            holes: self.holes.clone(),
+            naturals: self.naturals.clone(),
             current_deps: self.current_deps.clone(),
             variables: IndexMap::new(),
             loop_points: self.loop_points.clone(),
@@ -1328,7 +1412,8 @@ where
                 };
                 let argument = self.check_expression(None, argument, &argument_type)?;
                 self.put(loc, object.clone(), *then_type.clone())?;
-                let (process, inferred_types) = analyze_process(self, process)?;
+                let (process, inferred_types) = analyze_process(self, process)
+                    .map_err(|error| error.after_value(loc, object, process))?;
                 (Command::Send(argument, process), inferred_types)
             }
 
@@ -1345,7 +1430,8 @@ where
                 }
                 self.put(loc, parameter.clone(), *parameter_type.clone())?;
                 self.put(loc, object.clone(), *then_type.clone())?;
-                let (process, inferred_types) = analyze_process(self, process)?;
+                let (process, inferred_types) = analyze_process(self, process)
+                    .map_err(|error| error.after_value(loc, object, process))?;
                 (
                     Command::Receive(parameter.clone(), annotation.clone(), process),
                     inferred_types,
@@ -1368,7 +1454,8 @@ where
                     ));
                 };
                 self.put(loc, object.clone(), branch_type.clone())?;
-                let (process, inferred_types) = analyze_process(self, process)?;
+                let (process, inferred_types) = analyze_process(self, process)
+                    .map_err(|error| error.after_selection(object, chosen, process))?;
                 (Command::Choose(chosen.clone(), process), inferred_types)
             }
 
@@ -1380,16 +1467,26 @@ where
                         typ.clone(),
                     ));
                 };
-                if let Some(missing) = required_branches
-                    .keys()
-                    .find(|&branch| !branches.contains(branch))
-                {
-                    return Err(TypeError::MissingBranch(
+                let redundant: Vec<Name> = branches
+                    .iter()
+                    .filter(|&branch| !required_branches.contains_key(branch))
+                    .cloned()
+                    .collect();
+                if !redundant.is_empty() {
+                    return Err(TypeError::RedundantBranch(
                         loc.clone(),
-                        missing.clone(),
+                        redundant,
                         typ.clone(),
                     ));
                 }
+                let missing: Vec<Name> = required_branches
+                    .keys()
+                    .filter(|&branch| !branches.contains(branch))
+                    .cloned()
+                    .collect();
+                if !missing.is_empty() {
+                    return Err(TypeError::MissingBranch(loc.clone(), missing, typ.clone()));
+                }
 
                 let original_context = self.clone();
                 let mut typed_processes = Vec::new();
@@ -1398,14 +1495,7 @@ where
                 for (branch, process) in branches.iter().zip(processes.iter()) {
                     *self = original_context.clone();
 
-                    let Some(branch_type) = required_branches.get(branch) else {
-                        return Err(TypeError::RedundantBranch(
-                            loc.clone(),
-                            branch.clone(),
-                            typ.clone(),
-                        ));
-                    };
-                    self.put(loc, object.clone(), branch_type.clone())?;
+                    self.put(loc, object.clone(), required_branches[branch].clone())?;
                     let (process, inferred_in_branch) = analyze_process(self, process)?;
                     typed_processes.push(process);
 
@@ -1879,9 +1969,127 @@ where
                     process,
                 )))
             }
+
+            Expression::StringLiteral(loc, text) => {
+                Type::String(loc.clone()).check_assignable(loc, target_type, &self.type_defs)?;
+                Ok(Arc::new(Expression::StringLiteral(
+                    loc.clone(),
+                    Arc::clone(text),
+                )))
+            }
+
+            Expression::Format(loc, parts) => {
+                self.check_holes(inference_subject, parts)?;
+                Type::String(loc.clone()).check_assignable(loc, target_type, &self.type_defs)?;
+                Ok(Arc::new(Expression::Format(loc.clone(), Arc::clone(parts))))
+            }
+
+            Expression::Hole(loc, name) => {
+                // Loops may check a process more than once, so a hole is kept by its
+                // location, with what it saw last.
+                self.holes.write().unwrap().insert(
+                    loc.clone(),
+                    Hole {
+                        loc: loc.clone(),
+                        name: name.clone(),
+                        typ: target_type.clone(),
+                        variables: std::mem::take(&mut self.variables),
+                    },
+                );
+                Ok(Arc::new(Expression::Hole(loc.clone(), name.clone())))
+            }
+
+            Expression::Placeholder(loc) => {
+                // Its syntax error is already reported, so it fits whatever it's meant
+                // to be, taking the variables it could have used with it.
+                self.variables.clear();
+                Ok(Arc::new(Expression::Placeholder(loc.clone())))
+            }
+
+            Expression::IntLiteral(loc, number) => {
+                Self::number_type(loc, *number).check_assignable(
+                    loc,
+                    target_type,
+                    &self.type_defs,
+                )?;
+                Ok(Arc::new(Expression::IntLiteral(loc.clone(), *number)))
+            }
+
+            Expression::Arithmetic(loc, operator, left, right) => {
+                let (expression, typ) =
+                    self.check_arithmetic(inference_subject, loc, *operator, left, right)?;
+                typ.check_assignable(loc, target_type, &self.type_defs)?;
+                Ok(expression)
+            }
+        }
+    }
+
+    /// A `Nat` if `number` isn't negative, or else an `Int`.
+    fn number_type(loc: &Loc, number: i128) -> Type<Loc, Name> {
+        if number < 0 {
+            Type::Int(loc.clone())
+        } else {
+            Type::Nat(loc.clone())
         }
     }
 
+    /// Checks that both operands of arithmetic are numbers, using up what they
+    /// capture. Adding or multiplying two `Nat`s makes a `Nat`; anything else makes an
+    /// `Int`. Where an operand is a `Nat` is noted, for a run to ask for one there.
+    fn check_arithmetic(
+        &mut self,
+        inference_subject: Option<&Name>,
+        loc: &Loc,
+        operator: Operator,
+        left: &Expression<Loc, Name, ()>,
+        right: &Expression<Loc, Name, ()>,
+    ) -> Result<(Arc<Expression<Loc, Name, Type<Loc, Name>>>, Type<Loc, Name>), TypeError<Loc, Name>>
+    {
+        let (left, left_type) = self.infer_expression(inference_subject, left)?;
+        let (right, right_type) = self.infer_expression(inference_subject, right)?;
+        let mut natural = operator.keeps_natural();
+        for (operand, typ) in [(&left, &left_type), (&right, &right_type)] {
+            typ.check_assignable(loc, &Type::Int(loc.clone()), &self.type_defs)?;
+            if typ.is_assignable_to(&Type::Nat(loc.clone()), &self.type_defs, &HashSet::new())? {
+                self.naturals.write().unwrap().insert(operand.loc().clone());
+            } else {
+                natural = false;
+            }
+        }
+        let typ = if natural {
+            Type::Nat(loc.clone())
+        } else {
+            Type::Int(loc.clone())
+        };
+        Ok((
+            Arc::new(Expression::Arithmetic(loc.clone(), operator, left, right)),
+            typ,
+        ))
+    }
+
+    /// Checks that each hole in a formatted string is filled with a string, using up
+    /// the variables that fill them.
+    fn check_holes(
+        &mut self,
+        inference_subject: Option<&Name>,
+        parts: &[StringPart<Loc, Name>],
+    ) -> Result<(), TypeError<Loc, Name>> {
+        for part in parts {
+            let StringPart::Hole(loc, name) = part else {
+                continue;
+            };
+            if Some(name) == inference_subject {
+                return Err(TypeError::TypeMustBeKnownAtThisPoint(
+                    loc.clone(),
+                    name.clone(),
+                ));
+            }
+            let typ = self.get(loc, name)?;
+            typ.check_assignable(loc, &Type::String(loc.clone()), &self.type_defs)?;
+        }
+        Ok(())
+    }
+
     pub fn infer_expression(
         &mut self,
         inference_subject: Option<&Name>,
@@ -1930,6 +2138,32 @@ where
                     dual,
                 ))
             }
+
+            Expression::StringLiteral(loc, text) => Ok((
+                Arc::new(Expression::StringLiteral(loc.clone(), Arc::clone(text))),
+                Type::String(loc.clone()),
+            )),
+
+            Expression::Format(loc, parts) => {
+                self.check_holes(inference_subject, parts)?;
+                Ok((
+                    Arc::new(Expression::Format(loc.clone(), Arc::clone(parts))),
+                    Type::String(loc.clone()),
+                ))
+            }
+
+            Expression::Hole(loc, _) => Err(TypeError::HoleTypeMustBeKnown(loc.clone())),
+
+            Expression::Placeholder(loc) => Err(TypeError::PlaceholderTypeMustBeKnown(loc.clone())),
+
+            Expression::IntLiteral(loc, number) => Ok((
+                Arc::new(Expression::IntLiteral(loc.clone(), *number)),
+                Self::number_type(loc, *number),
+            )),
+
+            Expression::Arithmetic(loc, operator, left, right) => {
+                self.check_arithmetic(inference_subject, loc, *operator, left, right)
+            }
         }
     }
 
@@ -1987,6 +2221,7 @@ impl<Loc, Name: Display> Type<Loc, Name> {
                     indentation(f, indent + 1)?;
                     write!(f, ".{} ", branch)?;
                     typ.pretty(f, indent + 1)?;
+                    write!(f, ",")?;
                 }
                 indentation(f, indent)?;
                 write!(f, "}}")
@@ -1996,14 +2231,18 @@ impl<Loc, Name: Display> Type<Loc, Name> {
                 write!(f, "{{")?;
                 for (branch, typ) in branches {
                     indentation(f, indent + 1)?;
-                    write!(f, "{} => ", branch)?;
+                    write!(f, ".{} => ", branch)?;
                     typ.pretty(f, indent + 1)?;
+                    write!(f, ",")?;
                 }
                 indentation(f, indent)?;
                 write!(f, "}}")
             }
 
             Self::Break(_) => write!(f, "!"),
+            Self::String(_) => write!(f, "String"),
+            Self::Int(_) => write!(f, "Int"),
+            Self::Nat(_) => write!(f, "Nat"),
             Self::Continue(_) => write!(f, "?"),
 
             Self::Recursive(_, asc, label, body) => {
@@ -2077,51 +2316,182 @@ fn indentation(f: &mut impl Write, indent: usize) -> fmt::Result {
     Ok(())
 }
 
-fn two_labels_from_two_locs(
-    code: &str,
+impl<Loc, Name: Display> Type<Loc, Name> {
+    /// The branch of this either or choice whose name is closest to `branch`, for
+    /// suggesting what a misspelled branch was meant to be.
+    fn closest_branch(&self, branch: &Name) -> Option<String> {
+        let (Self::Either(_, branches) | Self::Choice(_, branches)) = self else {
+            return None;
+        };
+        let names: Vec<String> = branches.keys().map(ToString::to_string).collect();
+        suggest::closest(&branch.to_string(), names.iter().map(String::as_str)).map(str::to_owned)
+    }
+}
+
+impl<Loc: Eq, Name: Clone + Eq> TypeError<Loc, Name> {
+    /// When `then` selects again on `object` right after `chosen`, as in `x.a.b`, and
+    /// that selection is what failed, names the earlier selections in the error.
+    fn after_selection<Typ>(
+        self,
+        object: &Name,
+        chosen: &Name,
+        then: &Process<Loc, Name, Typ>,
+    ) -> Self {
+        let Process::Do(next_loc, subject, _, Command::Choose(next, _)) = then else {
+            return self;
+        };
+        if subject != object {
+            return self;
+        }
+        match self {
+            Self::InvalidBranch(loc, branch, typ)
+            | Self::InvalidOperation(loc, Operation::Choose(_, branch), typ)
+                if &loc == next_loc && &branch == next =>
+            {
+                Self::InvalidBranchAfterSelection(loc, vec![chosen.clone()], branch, typ)
+            }
+            Self::InvalidBranchAfterSelection(loc, mut selected, branch, typ)
+                if selected.first() == Some(next) =>
+            {
+                selected.insert(0, chosen.clone());
+                Self::InvalidBranchAfterSelection(loc, selected, branch, typ)
+            }
+            error => error,
+        }
+    }
+
+    /// When a value was sent or received on `object` and `then` goes on to send or
+    /// receive more as part of the same list, as in `f(a, b, c)`, turns a failure on
+    /// one of those into a count of how many values fit.
+    fn after_value<Typ>(self, loc: &Loc, object: &Name, then: &Process<Loc, Name, Typ>) -> Self {
+        match self {
+            Self::InvalidOperation(
+                error_loc,
+                operation @ (Operation::Send(_) | Operation::Receive(_)),
+                typ,
+            ) if &error_loc == loc => {
+                let more = values_in_list(loc, object, then);
+                if more == 0 {
+                    return Self::InvalidOperation(error_loc, operation, typ);
+                }
+                Self::WrongNumberOfValues(error_loc, 1, 1 + more, typ)
+            }
+            Self::WrongNumberOfValues(error_loc, fitting, given, typ) if &error_loc == loc => {
+                Self::WrongNumberOfValues(error_loc, fitting + 1, given + 1, typ)
+            }
+            error => error,
+        }
+    }
+}
+
+/// How many sends or receives on `object`, written in the list at `loc`, start `process`.
+fn values_in_list<Loc: Eq, Name: Eq, Typ>(
+    loc: &Loc,
+    object: &Name,
+    mut process: &Process<Loc, Name, Typ>,
+) -> usize {
+    let mut count = 0;
+    while let Process::Do(next_loc, subject, _, command) = process {
+        if next_loc != loc || subject != object {
+            break;
+        }
+        process = match command {
+            Command::Send(_, then) | Command::Receive(_, _, then) => then,
+            _ => break,
+        };
+        count += 1;
+    }
+    count
+}
+
+pub(super) fn two_labels_from_two_locs(
     loc1: &Loc,
     loc2: &Loc,
     label1: impl Into<Option<String>>,
     label2: impl Into<Option<String>>,
 ) -> Vec<LabeledSpan> {
-    use crate::playground::labels_from_loc;
-    let mut labels = labels_from_loc(code, loc1);
+    use crate::compiled::labels_from_loc;
+    let mut labels = labels_from_loc(loc1);
     let label1 = label1.into();
     let label2 = label2.into();
     labels.iter_mut().for_each(|x| x.set_label(label1.clone()));
-    let mut labels2 = labels_from_loc(code, loc2);
+    let mut labels2 = labels_from_loc(loc2);
     labels2.iter_mut().for_each(|x| x.set_label(label2.clone()));
     labels.extend(labels2);
     labels
 }
 
+/// Lists `branches` for an error message, along with the noun and which of the verbs in
+/// `(one, many)` go with them, such as ``Branches `a`, `b` were``.
+fn list_branches<Name: Display>(
+    branches: &[Name],
+    (one, many): (&'static str, &'static str),
+) -> (String, &'static str, &'static str) {
+    let listed = branches
+        .iter()
+        .map(|branch| format!("`{}`", branch))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match branches.len() {
+        1 => (listed, "Branch", one),
+        _ => (listed, "Branches", many),
+    }
+}
+
+impl<Name: Display> Hole<super::parse::Loc, Name> {
+    /// What the hole needs to be and what there is to make it from.
+    pub fn to_report(&self, source_code: Arc<str>) -> miette::Report {
+        use crate::compiled::labels_from_loc;
+        let mut typ = String::new();
+        self.typ.pretty(&mut typ, 1).unwrap();
+        let mut variables = String::new();
+        for (name, typ) in &self.variables {
+            write!(&mut variables, "\n  {}: ", name).unwrap();
+            typ.pretty(&mut variables, 1).unwrap();
+        }
+        if variables.is_empty() {
+            variables.push_str("\n  nothing");
+        }
+        miette::miette!(
+            severity = miette::Severity::Advice,
+            labels = labels_from_loc(&self.loc),
+            "Hole `?{}` needs to be:\n\n  {}\n\nIt has to work with:\n{}\n",
+            self.name
+                .as_ref()
+                .map_or(String::new(), ToString::to_string),
+            typ,
+            variables
+        )
+        .with_source_code(source_code)
+    }
+}
+
 impl<Name: Display> TypeError<super::parse::Loc, Name> {
     pub fn into_report(&self, source_code: Arc<str>) -> miette::Report {
-        use crate::playground::labels_from_loc;
-        let code = &source_code;
+        use crate::compiled::labels_from_loc;
         match self {
             Self::TypeNameAlreadyDefined(loc1, loc2, name) => {
                 miette::miette!(
-                    labels = two_labels_from_two_locs(code, loc1, loc2, "this".to_owned(), "is already defined here".to_owned()),
+                    labels = two_labels_from_two_locs(loc1, loc2, "this".to_owned(), "is already defined here".to_owned()),
                     "Type `{}` is already defined.", name
                 )
             }
             Self::NameAlreadyDeclared(loc1, loc2, name) => {
                 miette::miette!(
-                    labels = two_labels_from_two_locs(code, loc1, loc2, "this".to_owned(), "is already declared here".to_owned()),
+                    labels = two_labels_from_two_locs(loc1, loc2, "this".to_owned(), "is already declared here".to_owned()),
                     "`{}` is already declared.",
                     name,
                 )
             }
             Self::NameAlreadyDefined(loc1, loc2, name) => {
                 miette::miette!(
-                    labels = two_labels_from_two_locs(code, loc1, loc2, "this".to_owned(), "is already defined here".to_owned()),
+                    labels = two_labels_from_two_locs(loc1, loc2, "this".to_owned(), "is already defined here".to_owned()),
                     "`{}` is already defined",
                     name,
                 )
             }
             Self::DeclaredButNotDefined(loc,  name) => {
-                let mut labels = labels_from_loc(code, loc);
+                let mut labels = labels_from_loc(loc);
                 labels.iter_mut().for_each(|x| {
                     x.set_label(Some("declared here".to_owned()));
                 });
@@ -2132,25 +2502,25 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::NoMatchingRecursiveOrIterative(loc, _) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "This `self` has no matching `recursive` or `iterative`.",
                 )
             }
             Self::SelfUsedInNegativePosition(loc, _) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "This `self` is used in a negative position.\n\nNegative self-references are not allowed."
                 )
             }
             Self::TypeNameNotDefined(loc, name) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(labels = labels, "Type `{}` is not defined.", name)
             }
             Self::DependencyCycle(loc, deps) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 let mut deps_str = String::new();
                 for (i, dep) in deps.iter().enumerate() {
                     if i > 0 {
@@ -2165,7 +2535,7 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::WrongNumberOfTypeArgs(loc, name, required_number, provided_number) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "Type `{}` has {} type arguments, but {} were provided.",
@@ -2175,11 +2545,26 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::NameNotDefined(loc, name) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(labels = labels, "`{}` is not defined.", name)
             }
+            Self::NameNotInScope(loc, name, binders) => {
+                let mut labels = labels_from_loc(loc);
+                for binder in binders {
+                    let mut bound = labels_from_loc(binder);
+                    (bound.iter_mut())
+                        .for_each(|label| label.set_label(Some("bound here, out of reach".to_owned())));
+                    labels.extend(bound);
+                }
+                miette::miette!(
+                    labels = labels,
+                    help = "A name bound in a branch, or inside an expression, can't be used outside of it.",
+                    "`{}` is not in scope here.",
+                    name
+                )
+            }
             Self::ShadowedObligation(loc, name) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "Cannot re-assign `{}` before handling it.",
@@ -2187,11 +2572,27 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::TypeMustBeKnownAtThisPoint(loc, _) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(labels = labels, "Type must be known at this point.")
             }
+            Self::HoleTypeMustBeKnown(loc) => {
+                let labels = labels_from_loc(loc);
+                miette::miette!(
+                    labels = labels,
+                    help = "Give it one with an annotation, as in `let x: T = ?`, or declare the definition it's in.",
+                    "The type this hole needs must be known at this point."
+                )
+            }
+            Self::PlaceholderTypeMustBeKnown(loc) => {
+                let labels = labels_from_loc(loc);
+                miette::miette!(
+                    labels = labels,
+                    help = "Declare the definition it's in, so the rest of the program can still be checked.",
+                    "The type of what failed to parse here must be known at this point."
+                )
+            }
             Self::ParameterTypeMustBeKnown(loc, _, param) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "Type of parameter `{}` must be known.",
@@ -2199,7 +2600,7 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::CannotAssignFromTo(loc, from_type, to_type) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 let (mut from_type_str, mut to_type_str) = (String::new(), String::new());
                 from_type.pretty(&mut from_type_str, 1).unwrap();
                 to_type.pretty(&mut to_type_str, 1).unwrap();
@@ -2211,7 +2612,7 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::UnfulfilledObligations(loc, names) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "Cannot end this process before handling {}.",
@@ -2227,52 +2628,131 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::InvalidOperation(loc, _, typ) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
+                let mut typ_str = String::new();
+                typ.pretty(&mut typ_str, 1).unwrap();
+                match mistakes::in_types(self) {
+                    Some(hint) => miette::miette!(
+                        labels = labels,
+                        help = hint.to_help(),
+                        "This operation cannot be performed on:\n\n  {}\n",
+                        typ_str
+                    ),
+                    None => miette::miette!(
+                        labels = labels,
+                        "This operation cannot be performed on:\n\n  {}\n",
+                        typ_str
+                    ),
+                }
+            }
+            Self::WrongNumberOfValues(loc, fitting, given, typ) => {
+                let labels = labels_from_loc(loc);
                 let mut typ_str = String::new();
                 typ.pretty(&mut typ_str, 1).unwrap();
                 miette::miette!(
                     labels = labels,
-                    "This operation cannot be performed on:\n\n  {}\n",
+                    "There are {} values here, but the type only has room for {}.\n\nAfter those, it continues as:\n\n  {}\n",
+                    given,
+                    fitting,
                     typ_str
                 )
             }
             Self::InvalidBranch(loc, branch, typ) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 let mut typ_str = String::new();
                 typ.pretty(&mut typ_str, 1).unwrap();
-                miette::miette!(
-                    labels = labels,
-                    "Branch `{}` is not available on:\n\n  {}\n",
-                    branch,
-                    typ_str
-                )
+                match typ.closest_branch(branch) {
+                    Some(closest) => miette::miette!(
+                        labels = labels,
+                        help = format!("Did you mean `.{}`?", closest),
+                        "Branch `{}` is not available on:\n\n  {}\n",
+                        branch,
+                        typ_str
+                    ),
+                    None => miette::miette!(
+                        labels = labels,
+                        "Branch `{}` is not available on:\n\n  {}\n",
+                        branch,
+                        typ_str
+                    ),
+                }
             }
-            Self::MissingBranch(loc, branch, typ) => {
-                let labels = labels_from_loc(code, loc);
+            Self::InvalidBranchAfterSelection(loc, selected, branch, typ) => {
+                let labels = labels_from_loc(loc);
                 let mut typ_str = String::new();
                 typ.pretty(&mut typ_str, 1).unwrap();
-                miette::miette!(
-                    labels = labels,
-                    "Branch `{}` was not handled for:\n\n  {}\n",
-                    branch,
-                    typ_str
-                )
+                let selected = selected
+                    .iter()
+                    .map(|name| format!(".{}", name))
+                    .collect::<String>();
+                match typ.closest_branch(branch) {
+                    Some(closest) => miette::miette!(
+                        labels = labels,
+                        help = format!("Did you mean `.{}`?", closest),
+                        "Branch `{}` is not available after selecting `{}`, on:\n\n  {}\n",
+                        branch,
+                        selected,
+                        typ_str
+                    ),
+                    None => miette::miette!(
+                        labels = labels,
+                        "Branch `{}` is not available after selecting `{}`, on:\n\n  {}\n",
+                        branch,
+                        selected,
+                        typ_str
+                    ),
+                }
             }
-            Self::RedundantBranch(loc, branch, typ) => {
-                let labels = labels_from_loc(code, loc);
+            Self::MissingBranch(loc, branches, typ) => {
+                let labels = labels_from_loc(loc);
                 let mut typ_str = String::new();
                 typ.pretty(&mut typ_str, 1).unwrap();
+                let (listed, noun, verb) = list_branches(branches, ("was", "were"));
                 miette::miette!(
                     labels = labels,
-                    "Branch `{}` is not possible for:\n\n  {}\n",
-                    branch,
+                    "{} {} {} not handled for:\n\n  {}\n",
+                    noun,
+                    listed,
+                    verb,
                     typ_str
                 )
             }
+            Self::RedundantBranch(loc, branches, typ) => {
+                let labels = labels_from_loc(loc);
+                let mut typ_str = String::new();
+                typ.pretty(&mut typ_str, 1).unwrap();
+                let (listed, noun, verb) = list_branches(branches, ("is", "are"));
+                let suggestions = branches
+                    .iter()
+                    .filter_map(|branch| {
+                        let closest = typ.closest_branch(branch)?;
+                        Some(format!("Did you mean `.{}` instead of `.{}`?", closest, branch))
+                    })
+                    .collect::<Vec<_>>();
+                if suggestions.is_empty() {
+                    miette::miette!(
+                        labels = labels,
+                        "{} {} {} not possible for:\n\n  {}\n",
+                        noun,
+                        listed,
+                        verb,
+                        typ_str
+                    )
+                } else {
+                    miette::miette!(
+                        labels = labels,
+                        help = suggestions.join("\n"),
+                        "{} {} {} not possible for:\n\n  {}\n",
+                        noun,
+                        listed,
+                        verb,
+                        typ_str
+                    )
+                }
+            }
             Self::TypesCannotBeUnified(typ1, typ2) => {
                 miette::miette!(
                     labels = two_labels_from_two_locs(
-                        code,
                         typ1.get_loc(),
                         typ2.get_loc(),
                         "this".to_owned(),
@@ -2282,18 +2762,18 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::NoSuchLoopPoint(loc, _) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(labels = labels, "There is no matching loop point in scope.")
             }
             Self::DoesNotDescendSubjectOfBegin(loc, _) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "This `loop` may diverge. Value does not descend from the corresponding `begin`.\n\nIf this is intended, use `unfounded begin`.",
                 )
             }
             Self::LoopVariableNotPreserved(loc, name) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 miette::miette!(
                     labels = labels,
                     "`{}` is used by next iteration, but is no longer defined.",
@@ -2301,7 +2781,7 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::LoopVariableChangedType(loc, name, loop_type, begin_type) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 let (mut loop_type_str, mut begin_type_str) = (String::new(), String::new());
                 loop_type.pretty(&mut loop_type_str, 1).unwrap();
                 begin_type.pretty(&mut begin_type_str, 1).unwrap();
@@ -2314,7 +2794,7 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
                 )
             }
             Self::Telltypes(loc, variables) => {
-                let labels = labels_from_loc(code, loc);
+                let labels = labels_from_loc(loc);
                 let mut buf = String::new();
                 for (name, typ) in variables {
                     write!(&mut buf, "{}: ", name).unwrap();
@@ -2330,3 +2810,362 @@ impl<Name: Display> TypeError<super::parse::Loc, Name> {
         }.with_source_code(source_code)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{compiled::Compiled, par::frontend::Par};
+    use std::sync::Arc;
+
+    fn checks(code: &str) -> bool {
+        let Ok(compiled) = Compiled::from_source(&Par, code) else {
+            panic!("should compile");
+        };
+        compiled.checked.is_ok()
+    }
+
+    fn error(code: &str) -> (String, Option<String>) {
+        let Ok(compiled) = Compiled::from_source(&Par, code) else {
+            panic!("should compile");
+        };
+        let Err(error) = compiled.checked else {
+            panic!("should not type check");
+        };
+        let report = error.into_report(Arc::from(code));
+        let help = report.help().map(|help| help.to_string());
+        (report.to_string(), help)
+    }
+
+    #[test]
+    fn test_dual_type_definition() {
+        let protocol = "
+            type Counter<T> = recursive either { .tick (T) self, .done ! }
+            type Listener<T> = dual Counter<T>
+            type Unit = !
+        ";
+        assert!(checks(&format!(
+            "{protocol}
+             dec forward : [Counter<Unit>] chan Listener<Unit>
+             def forward = [c] c"
+        )));
+        assert!(!checks(&format!(
+            "{protocol}
+             dec forward : [Counter<Unit>] Listener<Unit>
+             def forward = [c] c"
+        )));
+
+        // A type that happens to be called `dual` can still be named.
+        assert!(checks(
+            "type dual = !
+             type Unit = dual
+             dec unit : Unit
+             def unit = !"
+        ));
+    }
+
+    #[test]
+    fn test_misspelled_branches() {
+        let types = "
+            type Result = either { .success!, .failure! }
+            type Handler = { .success => !, .failure => ! }
+        ";
+        let (message, help) = error(&format!("{types} dec ok : Result def ok = .sucess!"));
+        assert!(
+            message.contains("Branch `sucess` is not available"),
+            "{}",
+            message
+        );
+        assert_eq!(help.as_deref(), Some("Did you mean `.success`?"));
+
+        let (message, help) = error(&format!(
+            "{types} dec handle : Handler
+             def handle = {{ .sucess => !, .failure => !, .zzz => ! }}"
+        ));
+        assert!(
+            message.contains("Branches `sucess`, `zzz` are not possible"),
+            "{}",
+            message
+        );
+        assert_eq!(
+            help.as_deref(),
+            Some("Did you mean `.success` instead of `.sucess`?")
+        );
+    }
+
+    #[test]
+    fn test_branch_after_selection() {
+        let menu = "type Menu = { .a => { .c => { .e => ! }, .d => ! } }";
+        let (message, _) = error(&format!("{menu} dec pick : [Menu] ! def pick = [x] x.a.b"));
+        assert!(
+            message.contains("Branch `b` is not available after selecting `.a`"),
+            "{}",
+            message
+        );
+        let (message, _) = error(&format!(
+            "{menu} dec pick : [Menu] ! def pick = [x] x.a.c.b"
+        ));
+        assert!(
+            message.contains("Branch `b` is not available after selecting `.a.c`"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_missing_branches() {
+        let color = "type Color = either { .red!, .green!, .blue! }";
+        let (message, _) = error(&format!(
+            "{color} dec name : [Color] Color
+             def name = [c] c {{ .green! => .green! }}"
+        ));
+        assert!(
+            message.contains("Branches `red`, `blue` were not handled"),
+            "{}",
+            message
+        );
+        let (message, _) = error(&format!(
+            "{color} dec name : [Color] Color
+             def name = [c] c {{ .red! => .red!, .green! => .green! }}"
+        ));
+        assert!(
+            message.contains("Branch `blue` was not handled"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_too_many_values() {
+        let types = "
+            type Bool = either { .true!, .false! }
+            dec both : [Bool, Bool] Bool
+            def both = [a, b] a { .true! => b, .false! => b { .true! => .false!, .false! => .false! } }
+        ";
+        let (message, _) = error(&format!(
+            "{types} def three = both(.true!, .true!, .false!)"
+        ));
+        assert!(
+            message.starts_with("There are 3 values here, but the type only has room for 2."),
+            "{}",
+            message
+        );
+        let (message, _) = error(&format!(
+            "{types} dec pair : (Bool, Bool) ! def pair = (.true!, .false!, .true!, .false!) !"
+        ));
+        assert!(
+            message.starts_with("There are 4 values here, but the type only has room for 2."),
+            "{}",
+            message
+        );
+
+        let (message, _) = error(&format!(
+            "{types} def one = both(.true!) (.false!) (.true!)"
+        ));
+        assert!(
+            message.starts_with("This operation cannot be performed on"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_string_literals() {
+        assert!(checks(
+            r#"type Greeting = either { .hello String, .bye! }
+               dec hi : Greeting
+               def hi = .hello "Hi!"
+               dec pass : [String] String
+               def pass = [s] s"#
+        ));
+        assert!(!checks(r#"dec unit : ! def unit = "!""#));
+        assert!(!checks(r#"dec s : String def s = chan c { c! }"#));
+    }
+
+    #[test]
+    fn test_string_formatting() {
+        assert!(checks(
+            r#"dec greet : [String] String
+               def greet = [name] "Hello, {name}!"
+               dec you : String
+               def you = greet("you")"#
+        ));
+        assert!(!checks(
+            r#"dec greet : [either { .a! }] String
+               def greet = [name] "Hello, {name}!""#
+        ));
+        // Filling a hole uses the string up.
+        let (message, _) = error(
+            r#"dec twice : [String] String
+               def twice = [name] "{name}{name}""#,
+        );
+        assert!(message.contains("`name` is not defined"), "{}", message);
+    }
+
+    #[test]
+    fn test_pass_after_branches() {
+        let bool = "type Bool = either { .true!, .false! }";
+        // Branches that don't end go on with the process after them.
+        assert!(checks(&format!(
+            "{bool}
+             dec not : [Bool] Bool
+             def not = [b] chan r {{
+               b {{
+                 .true! => {{ r.false }}
+                 .false! => {{ r.true }}
+               }}
+               r!
+             }}"
+        )));
+        // One that ends doesn't, and what the others bound is in scope after them.
+        let answer = |no: &str| {
+            format!(
+                "{bool}
+                 dec answer : [Bool] String
+                 def answer = [b] chan r {{
+                   b {{
+                     .true! => {{ let s = \"yes\" }}
+                     .false! => {{ {no} }}
+                   }}
+                   r <> s
+                 }}"
+            )
+        };
+        assert!(checks(&answer("let s = \"no\"")));
+        assert!(checks(&answer("r <> \"no\"")));
+        assert!(!checks(&answer("let s: Bool = .true!")));
+        assert!(!checks(&answer("let t = \"no\"")));
+
+        let unreachable = format!(
+            "{bool}
+             dec same : [Bool] Bool
+             def same = [b] chan r {{
+               b {{
+                 .true! => {{ r.true! }}
+                 .false! => {{ r.false! }}
+               }}
+               r.true!
+             }}"
+        );
+        assert!(matches!(
+            Compiled::from_source(&Par, &unreachable),
+            Err(crate::compiled::Error::Compile(
+                crate::par::language::CompileError::UnreachablePass(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_list_literals() {
+        let color = "type Color = either { .red!, .green!, .blue! }";
+        assert!(checks(&format!(
+            "{color}
+             dec colors : List<Color>
+             def colors = [.red!, .green!, .blue!]
+             dec nested : List<List<Color>>
+             def nested = [[.red!], [], [.blue!, .green!,]]
+             dec both : [Color, Color] List<Color>
+             def both = [a, b] [a, b]
+             dec pick : {{ .one => List<Color>, .none => List<Color> }}
+             def pick = {{ .one => [.red!] .none => [] }}
+             dec pair : ([Color] List<Color>) List<Color>
+             def pair = ([c] [c]) [.red!]"
+        )));
+        assert!(!checks(&format!(
+            "{color}
+             dec colors : List<Color>
+             def colors = [.red!, \"red\"]"
+        )));
+        // A program's own `List` is used instead of the builtin one.
+        assert!(!checks(&format!(
+            "{color}
+             type List<T> = recursive either {{ .nil!, .cons(T) self }}
+             dec colors : List<Color>
+             def colors = [.red!]"
+        )));
+    }
+
+    #[test]
+    fn test_name_not_in_scope() {
+        let options = "
+            type Option = either { .some!, .none! }
+            dec f : [Option] !
+        ";
+        let (message, _) = error(&format!(
+            "{options} def f = [o] o {{ .some x => x, .none! => x }}"
+        ));
+        assert_eq!(message, "`x` is not in scope here.");
+        let (message, _) = error(&format!(
+            "{options} def f = [o] o {{ .some x => x, .none! => y }}"
+        ));
+        assert_eq!(message, "`y` is not defined.");
+    }
+
+    #[test]
+    fn test_holes() {
+        let code = "type Bool = either { .true!, .false! }
+                    def swap(b: Bool) : Bool = ?todo
+                    def pair(a: Bool, b: Bool) : (Bool) Bool = (?) ?rest";
+        let Ok(compiled) = Compiled::from_source(&Par, code) else {
+            panic!("should compile");
+        };
+        let holes = compiled.checked.expect("should type check").holes;
+        let summary = (holes.iter())
+            .map(|hole| {
+                let name = hole
+                    .name
+                    .as_ref()
+                    .map_or(String::new(), ToString::to_string);
+                let variables = hole.variables.keys().map(ToString::to_string);
+                (name, variables.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("todo".to_owned(), vec!["b".to_owned()]),
+                (String::new(), vec!["a".to_owned(), "b".to_owned()]),
+                ("rest".to_owned(), vec![]),
+            ]
+        );
+
+        let (message, _) = error("def x = ?");
+        assert_eq!(
+            message,
+            "The type this hole needs must be known at this point."
+        );
+    }
+
+    #[test]
+    fn test_definition_parameters() {
+        let bool = "type Bool = either { .true!, .false! }";
+        assert!(checks(&format!(
+            "{bool}
+             def swap(b: Bool) : Bool = b {{ .true! => .false!, .false! => .true! }}
+             def both(a: Bool, (b: Bool) !) : (Bool, Bool)! = (a, b)!
+             def swapped : Bool = swap(.true!)"
+        )));
+        assert!(!checks(&format!(
+            "{bool}
+             def first(a: Bool, b: Bool) : Bool = a"
+        )));
+        assert!(!checks(&format!(
+            "{bool}
+             def swap(b: Bool) : ! = b"
+        )));
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert!(checks(
+            "dec six : Nat
+             def six = 2 * 3
+             dec below : Int
+             def below = 1 - 2
+             dec add : [Nat, Nat] Nat
+             def add = [a, b] a + b
+             def eleven : Int = add(six, 5)"
+        ));
+        let (message, _) = error("dec bad : Nat def bad = 1 - 2");
+        assert!(message.contains("Nat"), "{}", message);
+        assert!(!checks("def bad : Int = 1 + { \"one\" }"));
+    }
+}
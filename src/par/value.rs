@@ -0,0 +1,323 @@
+use super::{
+    language::{Apply, Construct, Expression},
+    lexer::{lex, quote, unescape, Input, Token, TokenKind},
+    parse::{
+        commit_after, label, list, name, number, parse_all, t, Loc, Name, Result, SyntaxError,
+    },
+};
+use std::{fmt::Display, sync::Arc};
+use winnow::{
+    combinator::{alt, eof, opt},
+    error::{StrContext, StrContextValue},
+    Parser,
+};
+
+/// A value given to a program from the outside, such as a command-line argument.
+///
+/// Values are written in the part of Par's construction syntax that only builds data:
+/// `!` is the unit, `.name value` selects a branch, `(value, ...) value` sends values
+/// one after another, `"text"` is a string, `12` or `-3` is a number, and a bare
+/// `name` refers to a global definition. A selection with nothing after it ends the
+/// value, so `.foo` is short for `.foo!`. Lists are written `[a, b, c]`, short for
+/// `.item(a) .item(b) .item(c) .empty!`, and non-empty ones are shown that way too.
+/// Likewise, `true` and `false` are short for `.true!` and `.false!`, and shown that
+/// way.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Reference(Name),
+    Send(Box<Self>, Box<Self>),
+    Choose(Name, Box<Self>),
+    Break,
+    String(String),
+    Int(i128),
+}
+
+pub fn parse_value(input: &str) -> std::result::Result<Value, SyntaxError> {
+    let toks = lex(input);
+    parse_all(
+        &toks,
+        (
+            value,
+            eof.context(StrContext::Expected(StrContextValue::Description(
+                "end of value",
+            ))),
+        )
+            .map(|(value, _eof)| value),
+    )
+}
+
+fn value(input: &mut Input) -> Result<Value> {
+    alt((
+        value_break,
+        value_choose,
+        value_send,
+        value_string,
+        value_int,
+        value_list,
+        value_bool,
+        value_reference,
+    ))
+    .context(StrContext::Label("value"))
+    .parse_next(input)
+}
+
+fn value_break(input: &mut Input) -> Result<Value> {
+    t("!").map(|_| Value::Break).parse_next(input)
+}
+
+fn value_choose(input: &mut Input) -> Result<Value> {
+    commit_after(t("."), (label, opt(value)))
+        .map(|(chosen, value)| Value::Choose(chosen, Box::new(value.unwrap_or(Value::Break))))
+        .parse_next(input)
+}
+
+fn value_send(input: &mut Input) -> Result<Value> {
+    commit_after(t("("), (list(value), t(")"), value))
+        .map(|(arguments, _, mut value)| {
+            for argument in arguments.into_iter().rev() {
+                value = Value::Send(Box::new(argument), Box::new(value));
+            }
+            value
+        })
+        .parse_next(input)
+}
+
+fn value_string(input: &mut Input) -> Result<Value> {
+    TokenKind::String
+        .verify_map(|token: &Token| unescape(token.raw))
+        .map(Value::String)
+        .parse_next(input)
+}
+
+fn value_int(input: &mut Input) -> Result<Value> {
+    number.map(Value::Int).parse_next(input)
+}
+
+fn value_list(input: &mut Input) -> Result<Value> {
+    commit_after(t("["), (opt(list(value)), t("]")))
+        .map(|(items, _)| {
+            let mut value = Value::Choose(Name::from("empty".to_owned()), Box::new(Value::Break));
+            for item in items.unwrap_or_default().into_iter().rev() {
+                value = Value::Choose(
+                    Name::from("item".to_owned()),
+                    Box::new(Value::Send(Box::new(item), Box::new(value))),
+                );
+            }
+            value
+        })
+        .parse_next(input)
+}
+
+fn value_bool(input: &mut Input) -> Result<Value> {
+    TokenKind::Ident
+        .verify_map(|token: &Token| match token.raw {
+            "true" | "false" => Some(Value::Choose(
+                Name::from(token.raw.to_owned()),
+                Box::new(Value::Break),
+            )),
+            _ => None,
+        })
+        .parse_next(input)
+}
+
+fn value_reference(input: &mut Input) -> Result<Value> {
+    name.map(Value::Reference).parse_next(input)
+}
+
+impl Value {
+    pub fn to_expression(&self) -> Expression<Loc, Name> {
+        match self {
+            Self::Reference(name) => Expression::Reference(Loc::External, name.clone()),
+            Self::String(text) => {
+                Expression::StringLiteral(Loc::External, Arc::from(text.as_str()))
+            }
+            Self::Int(number) => Expression::IntLiteral(Loc::External, *number),
+            _ => Expression::Construction(Loc::External, self.to_construct()),
+        }
+    }
+
+    fn to_construct(&self) -> Construct<Loc, Name> {
+        match self {
+            Self::Reference(_) | Self::String(_) | Self::Int(_) => {
+                Construct::Then(Loc::External, Box::new(self.to_expression()))
+            }
+            Self::Send(argument, then) => Construct::Send(
+                Loc::External,
+                Box::new(argument.to_expression()),
+                Box::new(then.to_construct()),
+            ),
+            Self::Choose(chosen, then) => {
+                Construct::Choose(Loc::External, chosen.clone(), Box::new(then.to_construct()))
+            }
+            Self::Break => Construct::Break(Loc::External),
+        }
+    }
+
+    /// The items of a value shaped like a non-empty list, `.item(a) .item(b) .empty!`.
+    fn list_items(&self) -> Option<Vec<&Self>> {
+        let mut items = Vec::new();
+        let mut value = self;
+        loop {
+            let Self::Choose(chosen, then) = value else {
+                return None;
+            };
+            match (chosen.string.as_str(), then.as_ref()) {
+                ("empty", Self::Break) if !items.is_empty() => return Some(items),
+                ("item", Self::Send(item, rest)) => {
+                    items.push(item.as_ref());
+                    value = rest;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Builds the expression `function(arguments...)`.
+    pub fn apply_to(function: Name, arguments: &[Value]) -> Expression<Loc, Name> {
+        let mut apply = Apply::Noop(Loc::External);
+        for argument in arguments.iter().rev() {
+            apply = Apply::Send(
+                Loc::External,
+                Box::new(argument.to_expression()),
+                Box::new(apply),
+            );
+        }
+        Expression::Application(
+            Loc::External,
+            Box::new(Expression::Reference(Loc::External, function)),
+            apply,
+        )
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Long values like lists continue for as long as they go, so walk along
+        // the continuation instead of recursing into it.
+        let mut value = self;
+        loop {
+            if let Some(items) = value.list_items() {
+                write!(f, "[")?;
+                for (index, item) in items.into_iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                return write!(f, "]");
+            }
+            let then = match value {
+                Self::Reference(name) => return write!(f, "{}", name),
+                Self::Send(argument, then) => {
+                    write!(f, "({}", argument)?;
+                    let mut then = then;
+                    while let Self::Send(argument, rest) = then.as_ref() {
+                        write!(f, ", {}", argument)?;
+                        then = rest;
+                    }
+                    write!(f, ")")?;
+                    then
+                }
+                Self::Choose(chosen, then)
+                    if matches!(chosen.string.as_str(), "true" | "false")
+                        && matches!(then.as_ref(), Self::Break) =>
+                {
+                    return write!(f, "{}", chosen);
+                }
+                Self::Choose(chosen, then) => {
+                    write!(f, ".{}", chosen)?;
+                    then
+                }
+                Self::Break => return write!(f, "!"),
+                Self::String(text) => return write!(f, "{}", quote(text)),
+                Self::Int(number) => return write!(f, "{}", number),
+            };
+            if !matches!(then.as_ref(), Self::Break | Self::Send(_, _)) {
+                write!(f, " ")?;
+            }
+            value = then;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn name(string: &str) -> Name {
+        Name::from(string.to_owned())
+    }
+
+    #[test]
+    fn test_parse_value() {
+        assert_eq!(parse_value("!").unwrap(), Value::Break);
+        assert_eq!(
+            parse_value(".foo").unwrap(),
+            Value::Choose(name("foo"), Box::new(Value::Break))
+        );
+        assert_eq!(
+            parse_value("(x)!").unwrap(),
+            Value::Send(
+                Box::new(Value::Reference(name("x"))),
+                Box::new(Value::Break)
+            )
+        );
+        assert_eq!(
+            parse_value(".item(.red!, .green) .empty!").unwrap(),
+            Value::Choose(
+                name("item"),
+                Box::new(Value::Send(
+                    Box::new(Value::Choose(name("red"), Box::new(Value::Break))),
+                    Box::new(Value::Send(
+                        Box::new(Value::Choose(name("green"), Box::new(Value::Break))),
+                        Box::new(Value::Choose(name("empty"), Box::new(Value::Break))),
+                    )),
+                ))
+            )
+        );
+        assert!(parse_value("").is_err());
+        assert!(parse_value("(x)").is_err());
+        assert!(parse_value("chan x { x! }").is_err());
+        assert!(parse_value("! !").is_err());
+        assert_eq!(
+            parse_value("[.red, x]").unwrap(),
+            parse_value(".item(.red!) .item(x) .empty!").unwrap()
+        );
+        assert!(parse_value("[x,,]").is_err());
+    }
+
+    #[test]
+    fn test_print_value() {
+        for (input, printed) in [
+            ("!", "!"),
+            (".foo", ".foo!"),
+            ("(x)!", "(x)!"),
+            ("(x) y", "(x) y"),
+            (".some x", ".some x"),
+            ("(.a, .b) (.c)!", "(.a!, .b!, .c!)!"),
+            (".item(.red!) .item(.green) .empty!", "[.red!, .green!]"),
+            ("[.red, [], [x]]", "[.red!, .empty!, [x]]"),
+            ("[]", ".empty!"),
+            (".some [x]", ".some [x]"),
+            ("(x) [y]", "(x) [y]"),
+            (".item(x, y) .empty!", ".item(x, y) .empty!"),
+            (r#".some "a \"b\"""#, r#".some "a \"b\"""#),
+            (r#"("x", .y)!"#, r#"("x", .y!)!"#),
+            ("(12, -3) .n 007", "(12, -3) .n 7"),
+            ("[1, 2]", "[1, 2]"),
+            (".true", "true"),
+            ("[false, .true x]", "[false, .true x]"),
+            (".some true", ".some true"),
+        ] {
+            let value = parse_value(input).unwrap();
+            assert_eq!(value.to_string(), printed);
+            assert_eq!(parse_value(printed).unwrap(), value);
+        }
+        assert_eq!(
+            parse_value(&i128::MIN.to_string()).unwrap(),
+            Value::Int(i128::MIN)
+        );
+        // The sign goes right before the digits.
+        assert!(parse_value("- 3").is_err());
+    }
+}
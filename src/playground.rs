@@ -1,168 +1,177 @@
 use std::{
-    collections::BTreeSet,
+    cell::RefCell,
+    collections::{BTreeSet, HashMap},
     fmt::Write,
     fs::File,
-    path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    mem,
+    path::Path,
+    rc::Rc,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
 };
 
 use eframe::egui;
 use egui_code_editor::{CodeEditor, ColorTheme, Syntax};
-use indexmap::IndexMap;
+use futures::{channel::mpsc, StreamExt};
+use tokio::sync::watch;
 
 use crate::{
+    build_info, cli,
+    compiled::{definition_under_cursor, Compiled, Error},
+    config::{Config, Preset, Theme},
+    events::{EventBus, Progress, RuntimeEvent},
+    gallery::{FrontMatter, EXAMPLES},
     interact::{Event, Handle, Request},
     par::{
-        language::{CompileError, Internal},
-        parse::{parse_program, Loc, Name, Program, SyntaxError},
+        frontend::{Frontend, FRONTENDS},
+        language::Internal,
+        lexer::quote,
+        lint::{self, Warning},
+        outline,
+        parse::{Loc, Name},
         process::Expression,
-        runtime::{self, Context, Operation},
-        types::{self, Type, TypeError},
+        runtime::Context,
     },
-    spawn::TokioSpawn,
+    session::{Session, Tab},
+    spawn::{Supervisor, TokioSpawn},
+    trace::TraceLog,
+    transcript::{Step, Transcript},
+    tutorial::{Tutorial, LESSONS},
 };
-use miette::{LabeledSpan, SourceOffset, SourceSpan};
 
 pub struct Playground {
-    file_path: Option<PathBuf>,
-    code: String,
+    /// The window this is shown in. The root window saves the sessions of every window.
+    viewport: egui::ViewportId,
+    /// The other windows open, each with tabs of its own. Only the root window has any.
+    windows: Vec<Playground>,
+    /// How many windows have been opened, to give each one an id of its own.
+    windows_opened: u64,
+    /// Whether "New window" was clicked in this window this frame.
+    new_window: bool,
+    shared: Rc<RefCell<Shared>>,
+    session: Session,
+    /// The session as it was last written out.
+    autosaved: Session,
+    autosaved_at: Instant,
     compiled: Option<Result<Compiled, Error>>,
     compiled_code: Arc<str>,
     interact: Option<Interact>,
-    editor_font_size: f32,
     show_compiled: bool,
+    show_protocol: bool,
+    show_type: bool,
+    show_console: bool,
+    show_trace: bool,
+    show_about: bool,
+    cursor_line: Option<usize>,
+    events: EventBus,
+    status: mpsc::UnboundedReceiver<RuntimeEvent>,
+    compile_time: Option<Duration>,
+    progress: watch::Receiver<Progress>,
+    /// The latest progress of the run being shown, if it has reported any.
+    processes: Progress,
+    tutorial: Option<Tutorial>,
+    /// The tab the tutorial opened, which each lesson's starter code is loaded into,
+    /// unless it's been closed since.
+    tutorial_tab: Option<usize>,
+    transcript: Option<Transcript>,
+    chosen: Option<Chosen>,
+    /// Why the last preset could not be run.
+    preset_error: Option<String>,
+    /// What a process of the last run said when it panicked, stopping the run.
+    panicked: Option<String>,
 }
 
-#[derive(Clone)]
-pub(crate) struct Compiled {
-    pub(crate) program: Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, ()>>>,
-    pub(crate) pretty: String,
-    pub(crate) checked: Result<Checked, TypeError<Loc, Internal<Name>>>,
+/// What all the windows share. Changing a setting in one window changes it in every one.
+struct Shared {
+    editor_font_size: f32,
+    frontend: &'static dyn Frontend,
+    presets: Vec<Preset>,
+    /// How many steps a run may take before it's stopped, if it's limited.
+    max_steps: Option<usize>,
+    /// Programs compiled lately by any window, oldest first, with the syntax they were
+    /// written in. A window compiling the same code as another reuses its result.
+    compiled: Vec<(&'static str, Arc<str>, Compiled)>,
 }
 
-impl Compiled {
-    pub(crate) fn from_string(source: &str) -> Result<Compiled, Error> {
-        parse_program(source)
-            .map_err(Error::Parse)
-            .and_then(|program| {
-                let type_defs = program
-                    .type_defs
-                    .into_iter()
-                    .map(|(loc, name, params, typ)| {
-                        (
-                            loc,
-                            Internal::Original(name),
-                            params.into_iter().map(Internal::Original).collect(),
-                            typ.map_names(&mut Internal::Original),
-                        )
-                    })
-                    .collect();
-                let declarations = program
-                    .declarations
-                    .into_iter()
-                    .map(|(loc, name, typ)| {
-                        (
-                            loc,
-                            Internal::Original(name),
-                            typ.map_names(&mut Internal::Original),
-                        )
-                    })
-                    .collect();
-                let compile_result = program
-                    .definitions
-                    .into_iter()
-                    .map(|(loc, name, def)| {
-                        def.compile().map(|compiled| {
-                            (
-                                loc,
-                                Internal::Original(name.clone()),
-                                compiled.optimize().fix_captures(&IndexMap::new()).0,
-                            )
-                        })
-                    })
-                    .collect::<Result<_, CompileError<Loc>>>();
-                match compile_result {
-                    Ok(compiled) => Ok(Compiled::from_program(Program {
-                        type_defs,
-                        declarations,
-                        definitions: compiled,
-                    })),
-                    Err(error) => Err(Error::Compile(error)),
-                }
-            })
-    }
-
-    pub(crate) fn from_program(
-        program: Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, ()>>>,
-    ) -> Self {
-        let pretty = program
-            .definitions
-            .iter()
-            .map(|(_, name, def)| {
-                let mut buf = String::new();
-                write!(&mut buf, "define {} = ", name).expect("write failed");
-                def.pretty(&mut buf, 0).expect("write failed");
-                write!(&mut buf, "\n\n").expect("write failed");
-                buf
-            })
-            .collect();
+/// How many compiled programs are kept for the windows to share.
+const COMPILED_KEPT: usize = 8;
 
-        // attempt to type check
-        let definitions = match types::Context::new_with_type_checking(&program) {
-            Ok(context) => context.get_checked_definitions(),
-            Err(error) => {
-                return Compiled {
-                    program,
-                    pretty,
-                    checked: Err(error),
-                }
-            }
-        };
-        let new_program = Program {
-            type_defs: program.type_defs.clone(),
-            declarations: program.declarations.clone(),
-            definitions,
-        };
-        return Compiled {
-            program,
-            pretty,
-            checked: Ok(Checked::from_program(new_program)),
-        };
+impl Shared {
+    fn compiled(&self, frontend: &dyn Frontend, code: &str) -> Option<Compiled> {
+        (self.compiled.iter())
+            .find(|(name, compiled_code, _)| *name == frontend.name() && **compiled_code == *code)
+            .map(|(_, _, compiled)| compiled.clone())
     }
-}
 
-#[derive(Clone)]
-pub(crate) struct Checked {}
-
-impl Checked {
-    pub(crate) fn from_program(
-        // not used for anything, so there's no reason to store it ATM.
-        _: Program<
-            Loc,
-            Internal<Name>,
-            Arc<Expression<Loc, Internal<Name>, Type<Loc, Internal<Name>>>>,
-        >,
-    ) -> Self {
-        Checked {}
+    fn keep_compiled(&mut self, frontend: &dyn Frontend, code: Arc<str>, compiled: Compiled) {
+        if self.compiled.len() == COMPILED_KEPT {
+            self.compiled.remove(0);
+        }
+        self.compiled.push((frontend.name(), code, compiled));
     }
 }
 
-#[derive(Debug)]
-pub(crate) enum Error {
-    Parse(SyntaxError),
-    Compile(CompileError<Loc>),
-    Type(TypeError<Loc, Internal<Name>>),
-    Runtime(runtime::Error<Loc, Internal<Name>>),
-}
+const RUN_UNDER_CURSOR: egui::KeyboardShortcut =
+    egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Enter);
+
+/// The step limit offered when one is turned on in the playground.
+const DEFAULT_MAX_STEPS: usize = 10_000_000;
+
+/// How long edits wait before the session is written out.
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 struct Interact {
     code: Arc<str>,
     handle: Arc<Mutex<Handle<Loc, Internal<Name>, ()>>>,
+    supervisor: Arc<Supervisor>,
+    /// The number of the run, which its progress is reported with.
+    run: u64,
+    /// How many layers are shown of the values at each path, where more than
+    /// [`FOLDED_LAYERS`] have been unfolded.
+    unfolded: Arc<Mutex<HashMap<Vec<usize>, usize>>>,
+    /// What's been typed so far for each value at a path waiting for a string.
+    typed: Arc<Mutex<HashMap<Vec<usize>, String>>>,
+}
+
+/// What was picked from the _Run_ menu. A preset needs compiling with its arguments
+/// before it runs.
+enum Picked {
+    Definition(String),
+    Preset(Preset),
+}
+
+/// How many layers of a value are shown before the rest is folded away. A layer starts
+/// at each branch the value selects, so a long list shows only its first few items
+/// until more are asked for, rather than laying out every one of them.
+const FOLDED_LAYERS: usize = 8;
+
+/// A choice clicked, or a string entered, in the interaction pane. It's made once the
+/// pane is done drawing, so that nothing in the value is locked while it is recorded.
+struct Chosen {
+    handle: Arc<Mutex<Handle<Loc, Internal<Name>, ()>>>,
+    loc: Loc,
+    choice: Choice,
+    path: Vec<usize>,
+}
+
+enum Choice {
+    Branch(Internal<Name>),
+    String(Arc<str>),
+    Int(i128),
 }
 
 impl Playground {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Box<Self> {
+    pub fn new(
+        cc: &eframe::CreationContext<'_>,
+        config: &Config,
+        frontend: &'static dyn Frontend,
+    ) -> Box<Self> {
+        cc.egui_ctx.set_theme(match config.theme() {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        });
         cc.egui_ctx.all_styles_mut(|style| {
             style.text_styles.extend([
                 (egui::TextStyle::Monospace, egui::FontId::monospace(16.0)),
@@ -172,21 +181,163 @@ impl Playground {
             style.visuals.code_bg_color = egui::Color32::TRANSPARENT;
             style.wrap_mode = Some(egui::TextWrapMode::Extend);
         });
-        let default_code = DEFAULT_CODE.to_string();
-        Box::new(Self {
-            file_path: None,
-            code: default_code.clone(),
+        let shared = Rc::new(RefCell::new(Shared {
+            editor_font_size: config.font_size(),
+            frontend,
+            presets: config.presets.clone(),
+            max_steps: config.max_steps,
+            compiled: Vec::new(),
+        }));
+        let mut session = Session::load().unwrap_or_else(|| Session::new(DEFAULT_CODE.to_string()));
+        let windows = mem::take(&mut session.windows);
+        let mut root = Self::window(
+            &cc.egui_ctx,
+            egui::ViewportId::ROOT,
+            Rc::clone(&shared),
+            session,
+        );
+        for session in windows {
+            root.open_window(&cc.egui_ctx, session);
+        }
+        root.autosaved = root.saved();
+        Box::new(root)
+    }
+
+    /// A window showing `session`.
+    fn window(
+        ctx: &egui::Context,
+        viewport: egui::ViewportId,
+        shared: Rc<RefCell<Shared>>,
+        session: Session,
+    ) -> Self {
+        let events = EventBus::default();
+        let mut repaints = events.subscribe();
+        let mut progress = events.progress();
+        let ctx = ctx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = repaints.next() => if event.is_none() { break },
+                    changed = progress.changed() => if changed.is_err() { break },
+                }
+                ctx.request_repaint();
+            }
+        });
+
+        Self {
+            viewport,
+            windows: Vec::new(),
+            windows_opened: 0,
+            new_window: false,
+            shared,
             compiled: None,
-            compiled_code: Arc::from(default_code),
+            compiled_code: Arc::from(session.tab().code.as_str()),
+            autosaved: session.clone(),
+            autosaved_at: Instant::now(),
+            session,
             interact: None,
-            editor_font_size: 16.0,
             show_compiled: false,
-        })
+            show_protocol: false,
+            show_type: false,
+            show_console: false,
+            show_trace: false,
+            show_about: false,
+            cursor_line: None,
+            status: events.subscribe(),
+            progress: events.progress(),
+            events,
+            compile_time: None,
+            processes: Progress::default(),
+            tutorial: None,
+            tutorial_tab: None,
+            transcript: None,
+            chosen: None,
+            preset_error: None,
+            panicked: None,
+        }
     }
 }
 
 impl eframe::App for Playground {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.show(ctx);
+
+        let mut new_window = mem::take(&mut self.new_window);
+        self.windows.retain_mut(|window| {
+            let title = format!("⅋layground — {}", window.session.tab().title());
+            ctx.show_viewport_immediate(
+                window.viewport,
+                egui::ViewportBuilder::default()
+                    .with_title(title)
+                    .with_inner_size([1000.0, 700.0]),
+                |ctx, _| {
+                    window.show(ctx);
+                    new_window |= mem::take(&mut window.new_window);
+                    !ctx.input(|input| input.viewport().close_requested())
+                },
+            )
+        });
+        if new_window {
+            self.open_window(ctx, Session::new(DEFAULT_CODE.to_string()));
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        let _ = self.saved().save();
+    }
+}
+
+impl Playground {
+    /// Opens another window showing `session`.
+    fn open_window(&mut self, ctx: &egui::Context, session: Session) {
+        self.windows_opened += 1;
+        let viewport = egui::ViewportId::from_hash_of(("window", self.windows_opened));
+        (self.windows).push(Self::window(
+            ctx,
+            viewport,
+            Rc::clone(&self.shared),
+            session,
+        ));
+    }
+
+    /// This window's session, along with those of the other windows open.
+    fn saved(&self) -> Session {
+        Session {
+            windows: (self.windows.iter())
+                .map(|window| window.session.clone())
+                .collect(),
+            ..self.session.clone()
+        }
+    }
+
+    /// Draws this window, whichever viewport `ctx` is for.
+    fn show(&mut self, ctx: &egui::Context) {
+        while let Ok(event) = self.status.try_recv() {
+            match event {
+                RuntimeEvent::CompileStarted => self.compile_time = None,
+                RuntimeEvent::CompileFinished(time) => self.compile_time = Some(time),
+                RuntimeEvent::OutputAvailable
+                | RuntimeEvent::Failed
+                | RuntimeEvent::Panicked { .. } => {}
+            }
+        }
+        // Runs replaced by another may still be winding down, reporting their own counts.
+        let progress = *self.progress.borrow_and_update();
+        if (self.interact.as_ref()).is_some_and(|int| int.run == progress.run) {
+            self.processes = progress;
+        }
+        // A run with a panicked process is dropped whole. Its handles may have been
+        // left half updated, so they aren't shown or cancelled.
+        if let Some(panic) = (self.interact.as_ref()).and_then(|int| int.supervisor.panic()) {
+            self.interact = None;
+            self.panicked = Some(panic);
+        }
+
+        if ctx.input_mut(|input| input.consume_shortcut(&RUN_UNDER_CURSOR)) {
+            self.run_under_cursor();
+        }
+        self.autosave(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             egui::SidePanel::left("interaction")
                 .resizable(true)
@@ -195,14 +346,15 @@ impl eframe::App for Playground {
                 .show_inside(ui, |ui| {
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         ui.horizontal(|ui| {
-                            if ui.button(egui::RichText::new("-").monospace()).clicked() {
-                                self.editor_font_size = (self.editor_font_size - 1.0).max(8.0);
-                            }
-                            ui.label(
-                                egui::RichText::new(self.editor_font_size.to_string()).strong(),
-                            );
-                            if ui.button(egui::RichText::new("+").monospace()).clicked() {
-                                self.editor_font_size = (self.editor_font_size + 1.0).min(320.0);
+                            {
+                                let font_size = &mut self.shared.borrow_mut().editor_font_size;
+                                if ui.button(egui::RichText::new("-").monospace()).clicked() {
+                                    *font_size = (*font_size - 1.0).max(8.0);
+                                }
+                                ui.label(egui::RichText::new(font_size.to_string()).strong());
+                                if ui.button(egui::RichText::new("+").monospace()).clicked() {
+                                    *font_size = (*font_size + 1.0).min(320.0);
+                                }
                             }
 
                             ui.add_space(5.0);
@@ -217,7 +369,7 @@ impl eframe::App for Playground {
                                         ui.close_menu();
                                     }
 
-                                    if let Some(path) = self.file_path.clone() {
+                                    if let Some(path) = self.session.tab().file_path.clone() {
                                         if ui.button(egui::RichText::new("Save").strong()).clicked()
                                         {
                                             self.save_file(&path);
@@ -232,43 +384,137 @@ impl eframe::App for Playground {
                                         self.save_file_as();
                                         ui.close_menu();
                                     }
+
+                                    if ui
+                                        .add_enabled(
+                                            self.transcript.is_some(),
+                                            egui::Button::new(
+                                                egui::RichText::new("Save transcript...").strong(),
+                                            ),
+                                        )
+                                        .on_hover_text(
+                                            "Save the choices made in the running definition, \
+                                             to replay with `par interact-replay`",
+                                        )
+                                        .clicked()
+                                    {
+                                        self.save_transcript();
+                                        ui.close_menu();
+                                    }
+
+                                    ui.menu_button(
+                                        egui::RichText::new("Examples").strong(),
+                                        |ui| {
+                                            self.show_examples(ui);
+                                        },
+                                    );
+
+                                    ui.menu_button(egui::RichText::new("Syntax").strong(), |ui| {
+                                        for &frontend in FRONTENDS {
+                                            let selected =
+                                                frontend.name() == self.frontend().name();
+                                            if ui.radio(selected, frontend.name()).clicked() {
+                                                self.shared.borrow_mut().frontend = frontend;
+                                                self.compiled = None;
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    });
+
+                                    ui.menu_button(egui::RichText::new("Limits").strong(), |ui| {
+                                        let max_steps = &mut self.shared.borrow_mut().max_steps;
+                                        let mut limited = max_steps.is_some();
+                                        ui.horizontal(|ui| {
+                                            ui.checkbox(&mut limited, "Stop runs after");
+                                            let mut steps = max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+                                            ui.add_enabled(
+                                                limited,
+                                                egui::DragValue::new(&mut steps)
+                                                    .range(1..=usize::MAX)
+                                                    .suffix(" steps"),
+                                            );
+                                            *max_steps = limited.then_some(steps);
+                                        });
+                                    });
+
+                                    if ui
+                                        .button(egui::RichText::new("New window").strong())
+                                        .on_hover_text("Another window with tabs of its own")
+                                        .clicked()
+                                    {
+                                        self.new_window = true;
+                                        ui.close_menu();
+                                    }
+
+                                    if ui
+                                        .button(egui::RichText::new("Tutorial").strong())
+                                        .clicked()
+                                    {
+                                        self.tutorial = Some(Tutorial::default());
+                                        self.session
+                                            .open(Tab::new(None, LESSONS[0].starter().to_owned()));
+                                        self.tutorial_tab = Some(self.session.current);
+                                        self.compiled = None;
+                                        ui.close_menu();
+                                    }
                                 },
                             );
 
-                            ui.add_space(5.0);
-
-                            if let Some(file_name) =
-                                self.file_path.as_ref().and_then(|p| p.file_name())
-                            {
-                                ui.label(
-                                    egui::RichText::new(format!(
-                                        "{}",
-                                        file_name.to_str().unwrap_or("")
-                                    ))
-                                    .strong(),
-                                );
-                            }
+                            egui::menu::menu_custom_button(
+                                ui,
+                                egui::Button::new(egui::RichText::new("Help").strong()),
+                                |ui| {
+                                    if ui.button(egui::RichText::new("About").strong()).clicked() {
+                                        self.show_about = !self.show_about;
+                                        ui.close_menu();
+                                    }
+                                },
+                            );
                         });
 
                         ui.separator();
+                        self.show_tabs(ui);
+                        ui.separator();
+
+                        if self.tutorial.is_some() {
+                            self.show_tutorial(ui);
+                        } else {
+                            self.show_front_matter(ui);
+                        }
 
-                        CodeEditor::default()
+                        let output = CodeEditor::default()
                             .id_source("code")
                             .with_syntax(par_syntax())
                             .with_rows(32)
-                            .with_fontsize(self.editor_font_size)
+                            .with_fontsize(self.shared.borrow().editor_font_size)
                             .with_theme(self.get_theme(ui))
                             .with_numlines(true)
-                            .show(ui, &mut self.code);
+                            .show(ui, &mut self.session.tab_mut().code);
+                        if let Some(range) = output.cursor_range {
+                            let code = &self.session.tab().code;
+                            let offset = (code.char_indices())
+                                .nth(range.primary.ccursor.index)
+                                .map_or(code.len(), |(offset, _)| offset);
+                            if let Loc::Code { line, .. } = Loc::from_offset(code, offset) {
+                                self.cursor_line = Some(line);
+                            }
+                        }
                     });
                 });
 
             self.show_interaction(ui);
         });
+
+        egui::Window::new("About")
+            .open(&mut self.show_about)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("⅋layground").heading());
+                ui.label(egui::RichText::new(format!("par-lang {}", build_info::report())).code());
+            });
     }
-}
 
-impl Playground {
     fn open_file(&mut self) {
         if let Some(path) = rfd::FileDialog::new().pick_file() {
             if let Ok(file_content) = File::open(&path).and_then(|mut file| {
@@ -277,9 +523,166 @@ impl Playground {
                 file.read_to_string(&mut buf)?;
                 Ok(buf)
             }) {
-                self.file_path = Some(path);
-                self.code = file_content;
+                self.session.open(Tab::new(Some(path), file_content));
+                self.compiled = None;
+            }
+        }
+    }
+
+    fn show_examples(&mut self, ui: &mut egui::Ui) {
+        for example in EXAMPLES {
+            let title = FrontMatter::parse(example.code)
+                .title
+                .unwrap_or_else(|| example.file.to_owned());
+            if ui.button(title).clicked() {
+                self.session.open(Tab::new(None, example.code.to_owned()));
+                self.compiled = None;
+                ui.close_menu();
+            }
+        }
+    }
+
+    /// Shows what the program's front matter says about it above the code.
+    fn show_front_matter(&mut self, ui: &mut egui::Ui) {
+        let front_matter = FrontMatter::parse(&self.session.tab().code);
+        if front_matter.is_empty() {
+            return;
+        }
+        if let Some(title) = &front_matter.title {
+            ui.label(egui::RichText::new(title).heading());
+        }
+        if let Some(description) = &front_matter.description {
+            ui.label(description);
+        }
+        if let Some(run) = &front_matter.run {
+            let text = match &front_matter.expect {
+                Some(expect) => format!("Run `{}`, expecting {}", run, expect),
+                None => format!("Run `{}`", run),
+            };
+            ui.label(egui::RichText::new(text).color(blue()));
+        }
+        for suggestion in &front_matter.tries {
+            ui.label(format!("• {}", suggestion));
+        }
+        ui.separator();
+    }
+
+    /// Shows the current lesson above the code, with a way to check the code against it
+    /// and to move between lessons.
+    fn show_tutorial(&mut self, ui: &mut egui::Ui) {
+        let Some(tutorial) = &mut self.tutorial else {
+            return;
+        };
+        tutorial.poll();
+        let lesson = tutorial.current();
+        let mut go_to = None;
+        let mut exit = false;
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(tutorial.lesson > 0, egui::Button::new("◀"))
+                .clicked()
+            {
+                go_to = Some(tutorial.lesson - 1);
+            }
+            egui::ComboBox::from_id_salt("lesson")
+                .selected_text(format!(
+                    "{}/{}: {}",
+                    tutorial.lesson + 1,
+                    LESSONS.len(),
+                    lesson.title()
+                ))
+                .show_ui(ui, |ui| {
+                    for (index, other) in LESSONS.iter().enumerate() {
+                        let mark = if tutorial.completed.contains(&index) {
+                            "✔"
+                        } else {
+                            "  "
+                        };
+                        if ui
+                            .selectable_label(
+                                index == tutorial.lesson,
+                                format!("{} {}", mark, other.title()),
+                            )
+                            .clicked()
+                        {
+                            go_to = Some(index);
+                        }
+                    }
+                });
+            if ui
+                .add_enabled(tutorial.lesson + 1 < LESSONS.len(), egui::Button::new("▶"))
+                .clicked()
+            {
+                go_to = Some(tutorial.lesson + 1);
+            }
+            ui.add_space(5.0);
+            if ui.button("Exit tutorial").clicked() {
+                exit = true;
+            }
+        });
+
+        ui.label(egui::RichText::new(lesson.title()).heading());
+        show_markdown(ui, &lesson.prose());
+
+        ui.horizontal(|ui| {
+            let check = egui::Button::new(
+                egui::RichText::new("Check")
+                    .strong()
+                    .color(egui::Color32::BLACK),
+            )
+            .fill(green().lerp_to_gamma(egui::Color32::WHITE, 0.3));
+            if ui.add_enabled(!tutorial.is_checking(), check).clicked() {
+                let ctx = ui.ctx().clone();
+                let tab = self
+                    .tutorial_tab
+                    .map_or(self.session.tab(), |tab| &self.session.tabs[tab]);
+                tutorial.check(Arc::from(tab.code.as_str()), move || ctx.request_repaint());
+            }
+            if lesson.solution().is_some() {
+                ui.checkbox(&mut tutorial.show_solution, "Show solution");
+            }
+            if tutorial.is_checking() {
+                ui.spinner();
+            }
+        });
+        match &tutorial.outcome {
+            Some(Ok(())) => {
+                let text = if tutorial.lesson + 1 < LESSONS.len() {
+                    "Well done! On to the next lesson."
+                } else {
+                    "Well done! That was the last lesson."
+                };
+                ui.label(egui::RichText::new(text).color(green()));
             }
+            Some(Err(error)) => {
+                ui.label(egui::RichText::new(error).color(red()).code());
+            }
+            None => {}
+        }
+        if let (true, Some(solution)) = (tutorial.show_solution, lesson.solution()) {
+            ui.label(egui::RichText::new(solution.trim_end()).code());
+        }
+        ui.separator();
+
+        if let Some(index) = go_to {
+            tutorial.go_to(index);
+            let starter = tutorial.current().starter().to_owned();
+            match self.tutorial_tab {
+                Some(tab) => {
+                    self.session.current = tab;
+                    self.session.tab_mut().code = starter;
+                }
+                None => {
+                    self.session.open(Tab::new(None, starter));
+                    self.tutorial_tab = Some(self.session.current);
+                }
+            }
+            self.compiled = None;
+        }
+        if exit {
+            self.tutorial = None;
+            self.tutorial_tab = None;
         }
     }
 
@@ -292,11 +695,106 @@ impl Playground {
         }
     }
 
+    fn save_transcript(&mut self) {
+        let (Some(int), Some(transcript)) = (&self.interact, &mut self.transcript) else {
+            return;
+        };
+        transcript.result = cli::observe(&int.code, &int.handle);
+        let Ok(json) = serde_json::to_string_pretty(transcript) else {
+            return;
+        };
+        if let Some(path) = rfd::FileDialog::new()
+            .set_can_create_directories(true)
+            .add_filter("Transcript", &["json"])
+            .save_file()
+        {
+            let _ = std::fs::write(path, json);
+        }
+    }
+
+    /// What has been done so far on the channels of the running definition.
+    fn trace_log(&self) -> Option<TraceLog> {
+        let (Some(int), Some(transcript)) = (&self.interact, &self.transcript) else {
+            return None;
+        };
+        let trace = int.handle.lock().expect("lock failed").trace();
+        Some(TraceLog::new(transcript.definition.clone(), &trace))
+    }
+
     fn save_file(&mut self, path: &Path) {
-        let _ = File::create(&path).and_then(|mut file| {
+        let tab = self.session.tab_mut();
+        let saved = File::create(&path).and_then(|mut file| {
             use std::io::Write;
-            file.write_all(self.code.as_bytes())
+            file.write_all(tab.code.as_bytes())
         });
+        if saved.is_ok() {
+            tab.file_path = Some(path.to_owned());
+            tab.saved = tab.code.clone();
+        }
+    }
+
+    /// One button per open tab, marked with `•` when it has unsaved changes.
+    fn show_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
+        let mut closed = None;
+        ui.horizontal_wrapped(|ui| {
+            for (index, tab) in self.session.tabs.iter().enumerate() {
+                let mut title = tab.title();
+                if tab.is_modified() {
+                    title.push_str(" •");
+                }
+                let current = index == self.session.current;
+                if ui.selectable_label(current, title).clicked() && !current {
+                    selected = Some(index);
+                }
+                if ui.small_button("×").on_hover_text("Close").clicked() {
+                    closed = Some(index);
+                }
+                ui.add_space(5.0);
+            }
+            if ui.small_button("+").on_hover_text("New tab").clicked() {
+                self.session.open(Tab::new(None, String::new()));
+                self.compiled = None;
+            }
+        });
+        if let Some(index) = selected {
+            self.session.current = index;
+            self.compiled = None;
+        }
+        if let Some(index) = closed {
+            self.session.close(index);
+            self.tutorial_tab = match self.tutorial_tab {
+                Some(tab) if tab == index => None,
+                Some(tab) if tab > index => Some(tab - 1),
+                tab => tab,
+            };
+            self.compiled = None;
+        }
+    }
+
+    /// Writes the session of every window out when one has changed, at most every
+    /// [`AUTOSAVE_INTERVAL`]. The root window does it for all of them.
+    fn autosave(&mut self, ctx: &egui::Context) {
+        if self.viewport != egui::ViewportId::ROOT {
+            return;
+        }
+        let saved = self.saved();
+        if saved == self.autosaved {
+            return;
+        }
+        let since = self.autosaved_at.elapsed();
+        if since < AUTOSAVE_INTERVAL {
+            ctx.request_repaint_after(AUTOSAVE_INTERVAL - since);
+            return;
+        }
+        self.autosaved_at = Instant::now();
+        if saved.save().is_ok() {
+            self.autosaved = saved;
+        }
+    }
+
+    fn frontend(&self) -> &'static dyn Frontend {
+        self.shared.borrow().frontend
     }
 
     fn get_theme(&self, ui: &egui::Ui) -> ColorTheme {
@@ -307,51 +805,204 @@ impl Playground {
         }
     }
 
-    fn run(
-        interact: &mut Option<Interact>,
-        ui: &mut egui::Ui,
-        program: &Program<Loc, Internal<Name>, Arc<Expression<Loc, Internal<Name>, ()>>>,
-        compiled_code: Arc<str>,
-    ) {
+    /// Lists the definitions to run, each followed by its presets, and returns the one
+    /// picked.
+    fn run(ui: &mut egui::Ui, compiled: &Compiled, presets: &[Preset]) -> Option<Picked> {
+        let mut chosen = None;
         egui::ScrollArea::vertical().show(ui, |ui| {
-            for (_, internal_name, expression) in &program.definitions {
+            for (_, internal_name, _) in &compiled.program.definitions {
                 if let Internal::Original(name) = internal_name {
                     if ui.button(&name.string).clicked() {
-                        if let Some(int) = interact.take() {
-                            int.handle.lock().expect("lock failed").cancel();
-                        }
-                        *interact = Some(Interact {
-                            code: Arc::clone(&compiled_code),
-                            handle: Handle::start_expression(
-                                Arc::new({
-                                    let ctx = ui.ctx().clone();
-                                    move || ctx.request_repaint()
-                                }),
-                                Context::new(
-                                    Arc::new(TokioSpawn),
-                                    Arc::new(
-                                        program
-                                            .definitions
-                                            .iter()
-                                            .map(|(_, name, expr)| (name.clone(), expr.clone()))
-                                            .collect(),
-                                    ),
-                                ),
-                                expression,
-                            ),
-                        });
+                        chosen = Some(Picked::Definition(name.string.clone()));
                         ui.close_menu();
                     }
+                    for preset in presets.iter().filter(|p| p.definition == name.string) {
+                        let button = ui
+                            .button(egui::RichText::new(format!("▸ {}", preset.name)).italics())
+                            .on_hover_text(format!(
+                                "{} {}",
+                                preset.definition,
+                                preset.arguments.join(" ")
+                            ));
+                        if button.clicked() {
+                            chosen = Some(Picked::Preset(preset.clone()));
+                            ui.close_menu();
+                        }
+                    }
                 }
             }
         });
+        chosen
+    }
+
+    /// Where a run of something from `compiled` starts, stopping after `max_steps`, what
+    /// watches over its processes, and the number of the run.
+    fn context(
+        events: &EventBus,
+        max_steps: Option<usize>,
+        compiled: &Compiled,
+    ) -> (Context<Loc, Internal<Name>, ()>, Arc<Supervisor>, u64) {
+        let spawner = TokioSpawn::new(events.clone());
+        let supervisor = spawner.supervisor();
+        let run = spawner.run();
+        let context = Context::new(Arc::new(spawner), compiled.definitions())
+            .with_max_steps(max_steps)
+            .with_naturals(compiled.naturals());
+        (context, supervisor, run)
+    }
+
+    fn start(
+        interact: &mut Option<Interact>,
+        transcript: &mut Option<Transcript>,
+        events: &EventBus,
+        (context, supervisor, run): (Context<Loc, Internal<Name>, ()>, Arc<Supervisor>, u64),
+        compiled_code: Arc<str>,
+        (definition, arguments): (&str, &[String]),
+        expression: &Arc<Expression<Loc, Internal<Name>, ()>>,
+    ) {
+        if let Some(int) = interact.take() {
+            int.handle.lock().expect("lock failed").cancel();
+        }
+        *transcript = Some(Transcript::new(definition.to_owned(), arguments.to_vec()));
+        *interact = Some(Interact {
+            code: compiled_code,
+            handle: Handle::start_expression(events.clone(), context, expression),
+            supervisor,
+            run,
+            unfolded: Arc::default(),
+            typed: Arc::default(),
+        });
+    }
+
+    /// Recompiles and runs the definition the editor's cursor is in.
+    fn run_under_cursor(&mut self) {
+        let Some(cursor_line) = self.cursor_line else {
+            return;
+        };
+        self.recompile();
+        let Some(Ok(compiled)) = &self.compiled else {
+            return;
+        };
+        if let Some((name, expression)) = definition_under_cursor(&compiled.program, cursor_line) {
+            Self::start(
+                &mut self.interact,
+                &mut self.transcript,
+                &self.events,
+                Self::context(&self.events, self.shared.borrow().max_steps, compiled),
+                self.compiled_code.clone(),
+                (&name.string, &[]),
+                expression,
+            );
+        }
+    }
+
+    /// Recompiles and runs again whatever definition was run last, if it's still there,
+    /// applied to the same arguments if it was run from a preset.
+    fn rerun(&mut self) {
+        let Some(transcript) = &self.transcript else {
+            return;
+        };
+        let definition = transcript.definition.clone();
+        let arguments = transcript.arguments.clone();
+        self.recompile();
+        if arguments.is_empty() {
+            self.run_definition(&definition);
+        } else {
+            self.run_applied(&definition, &arguments);
+        }
+    }
+
+    /// Runs the definition called `definition` from what was compiled last, if it's there.
+    fn run_definition(&mut self, definition: &str) {
+        let Some(Ok(compiled)) = &self.compiled else {
+            return;
+        };
+        let found =
+            compiled
+                .program
+                .definitions
+                .iter()
+                .find_map(|(_, name, expression)| match name {
+                    Internal::Original(name) if name.string == definition => {
+                        Some((name, expression))
+                    }
+                    _ => None,
+                });
+        if let Some((name, expression)) = found {
+            Self::start(
+                &mut self.interact,
+                &mut self.transcript,
+                &self.events,
+                Self::context(&self.events, self.shared.borrow().max_steps, compiled),
+                self.compiled_code.clone(),
+                (&name.string, &[]),
+                expression,
+            );
+        }
+    }
+
+    /// Compiles the code with `arguments` applied to `definition`, and runs that.
+    fn run_applied(&mut self, definition: &str, arguments: &[String]) {
+        self.preset_error = None;
+        self.panicked = None;
+        let code = Arc::from(self.session.tab().code.as_str());
+        let (mut program, errors) = self.frontend().parse(&code);
+        let compiled = if errors.is_empty() {
+            cli::entry(&mut program, definition, arguments).and_then(|entry| {
+                stacker::grow(32 * 1024 * 1024, || Compiled::from_parsed(program))
+                    .map(|compiled| (compiled, entry))
+                    .map_err(|error| error.display(Arc::clone(&code)))
+            })
+        } else {
+            let error = stacker::grow(32 * 1024 * 1024, || Error::recovered(errors, program));
+            Err(error.display(Arc::clone(&code)))
+        };
+        let (compiled, entry) = match compiled {
+            Ok(compiled) => compiled,
+            Err(error) => {
+                self.preset_error = Some(error);
+                return;
+            }
+        };
+        if let Err(error) = &compiled.checked {
+            self.preset_error = Some(Error::Type(error.clone()).display(code));
+            return;
+        }
+        let expression = Arc::clone(&compiled.definitions()[&Internal::Original(entry.clone())]);
+        Self::start(
+            &mut self.interact,
+            &mut self.transcript,
+            &self.events,
+            Self::context(&self.events, self.shared.borrow().max_steps, &compiled),
+            code,
+            (definition, arguments),
+            &expression,
+        );
     }
 
     fn recompile(&mut self) {
-        self.compiled = stacker::grow(32 * 1024 * 1024, || {
-            Some(Compiled::from_string(self.code.as_str()))
+        self.preset_error = None;
+        self.panicked = None;
+        self.events.publish(RuntimeEvent::CompileStarted);
+        let started = Instant::now();
+        let frontend = self.frontend();
+        let code: Arc<str> = Arc::from(self.session.tab().code.as_str());
+        let cached = self.shared.borrow().compiled(frontend, &code);
+        self.compiled = Some(match cached {
+            Some(compiled) => Ok(compiled),
+            None => {
+                let compiled =
+                    stacker::grow(32 * 1024 * 1024, || Compiled::from_source(frontend, &code));
+                if let Ok(compiled) = &compiled {
+                    let mut shared = self.shared.borrow_mut();
+                    shared.keep_compiled(frontend, Arc::clone(&code), compiled.clone());
+                }
+                compiled
+            }
         });
-        self.compiled_code = Arc::from(self.code.as_str());
+        self.compiled_code = code;
+        self.events
+            .publish(RuntimeEvent::CompileFinished(started.elapsed()));
     }
 
     fn show_interaction(&mut self, ui: &mut egui::Ui) {
@@ -363,11 +1014,25 @@ impl Playground {
                     self.recompile();
                 }
 
-                if let Some(Ok(Compiled { program, .. })) = &mut self.compiled {
+                let mut picked = None;
+                if let Some(Ok(compiled)) = &mut self.compiled {
                     ui.checkbox(
                         &mut self.show_compiled,
                         egui::RichText::new("Show compiled"),
                     );
+                    if !self.show_compiled && compiled.checked.is_ok() {
+                        ui.checkbox(
+                            &mut self.show_protocol,
+                            egui::RichText::new("Show protocol"),
+                        )
+                        .on_hover_text(
+                            "What the definition under the cursor does with each channel",
+                        );
+                        ui.checkbox(&mut self.show_type, egui::RichText::new("Show type"))
+                            .on_hover_text(
+                                "The type of the definition under the cursor, step by step",
+                            );
+                    }
 
                     if !self.show_compiled {
                         egui::menu::menu_custom_button(
@@ -379,18 +1044,95 @@ impl Playground {
                             )
                             .fill(green().lerp_to_gamma(egui::Color32::WHITE, 0.3)),
                             |ui| {
-                                Self::run(
-                                    &mut self.interact,
-                                    ui,
-                                    program,
-                                    self.compiled_code.clone(),
-                                );
+                                picked = Self::run(ui, compiled, &self.shared.borrow().presets);
                             },
-                        );
+                        )
+                        .response
+                        .on_hover_text(format!(
+                            "{} runs the definition under the cursor",
+                            ui.ctx().format_shortcut(&RUN_UNDER_CURSOR)
+                        ));
                     }
                 }
+                match picked {
+                    Some(Picked::Definition(definition)) => self.run_definition(&definition),
+                    Some(Picked::Preset(preset)) => {
+                        self.run_applied(&preset.definition, &preset.arguments)
+                    }
+                    None => {}
+                }
+
+                if self.compiled.is_some() && !self.show_compiled {
+                    ui.checkbox(&mut self.show_console, egui::RichText::new("Console"))
+                        .on_hover_text("The same output the command line would give");
+                }
+                if self.interact.is_some() && !self.show_compiled {
+                    ui.checkbox(&mut self.show_trace, egui::RichText::new("Trace"))
+                        .on_hover_text("Everything done on the channels read back, in order");
+                }
+
+                if let Some(time) = self.compile_time {
+                    ui.label(
+                        egui::RichText::new(format!("compiled in {} ms", time.as_millis())).weak(),
+                    );
+                }
+                if let Some(int) = &self.interact {
+                    let processes = if self.processes.run == int.run {
+                        self.processes.processes
+                    } else {
+                        0
+                    };
+                    ui.label(egui::RichText::new(format!("{} processes", processes)).weak());
+                }
             });
 
+            if self.show_trace && !self.show_compiled {
+                if let Some(log) = self.trace_log() {
+                    egui::TopBottomPanel::bottom("trace")
+                        .resizable(true)
+                        .show_inside(ui, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new("Trace").strong());
+                                if ui.button("Save...").clicked() {
+                                    save_trace(&log);
+                                }
+                            });
+                            egui::ScrollArea::both()
+                                .id_salt("trace")
+                                .stick_to_bottom(true)
+                                .show(ui, |ui| {
+                                    for entry in &log.entries {
+                                        ui.label(egui::RichText::new(entry.to_string()).code());
+                                    }
+                                });
+                        });
+                }
+            }
+
+            if self.show_console && !self.show_compiled {
+                egui::TopBottomPanel::bottom("console")
+                    .resizable(true)
+                    .show_inside(ui, |ui| {
+                        let console = self.console();
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Console").strong());
+                            if ui.button("Copy").clicked() {
+                                ui.ctx().copy_text(console.clone());
+                            }
+                        });
+                        egui::ScrollArea::both()
+                            .id_salt("console")
+                            .stick_to_bottom(true)
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut console.as_str())
+                                        .code_editor()
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+                    });
+            }
+
             egui::CentralPanel::default().show_inside(ui, |ui| {
                 egui::ScrollArea::both().show(ui, |ui| {
                     if let Some(Err(error)) = &self.compiled {
@@ -400,26 +1142,80 @@ impl Playground {
                                 .code(),
                         );
                     }
+                    if let Some(error) = &self.preset_error {
+                        ui.label(egui::RichText::new(error).color(red()).code());
+                    }
+                    if let Some(panic) = &self.panicked {
+                        let error =
+                            format!("The run was stopped, as a process panicked: {}", panic);
+                        ui.label(egui::RichText::new(error).color(red()).code());
+                    }
 
                     let theme = self.get_theme(ui);
+                    let warnings = self.warnings();
                     if let Some(Ok(Compiled {
                         pretty, checked, ..
                     })) = &mut self.compiled
                     {
+                        if !self.show_compiled {
+                            for report in lint::reports(&warnings, self.compiled_code.clone()) {
+                                let warning = format!("{:?}", report);
+                                ui.label(egui::RichText::new(warning).color(yellow()).code());
+                            }
+                        }
+
                         if self.show_compiled {
                             CodeEditor::default()
                                 .id_source("compiled")
                                 .with_syntax(par_syntax())
                                 .with_rows(32)
-                                .with_fontsize(self.editor_font_size)
+                                .with_fontsize(self.shared.borrow().editor_font_size)
                                 .with_theme(theme)
                                 .with_numlines(true)
                                 .show(ui, pretty);
-                        } else if let Ok(_) = checked {
+                        } else if let Ok(checked) = checked {
                             // :)
                             ui.label(
                                 egui::RichText::new("Type checking successful").color(green()),
                             );
+                            if !checked.holes.is_empty() {
+                                egui::CollapsingHeader::new(format!(
+                                    "Holes ({})",
+                                    checked.holes.len()
+                                ))
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    for hole in &checked.holes {
+                                        let report = hole.to_report(self.compiled_code.clone());
+                                        ui.label(
+                                            egui::RichText::new(format!("{:?}", report))
+                                                .color(blue())
+                                                .code(),
+                                        );
+                                    }
+                                });
+                            }
+                            if self.show_protocol {
+                                let protocol = (self.cursor_line)
+                                    .and_then(|line| checked.protocol(line))
+                                    .unwrap_or_else(|| {
+                                        "Put the cursor in a definition to see what it does \
+                                         with its channels."
+                                            .to_owned()
+                                    });
+                                ui.label(egui::RichText::new(protocol).code());
+                            }
+                            if self.show_type {
+                                match self.cursor_line.and_then(|line| checked.outline(line)) {
+                                    Some((name, nodes)) => {
+                                        ui.label(egui::RichText::new(format!("{} :", name)).code());
+                                        show_outline(ui, &nodes, &mut vec![name.to_string()]);
+                                    }
+                                    None => {
+                                        ui.label("Put the cursor in a definition to see its type.");
+                                    }
+                                }
+                            }
                         } else if let Err(err) = checked {
                             let error =
                                 Error::Type(err.clone()).display(self.compiled_code.clone());
@@ -428,8 +1224,30 @@ impl Playground {
                         }
                     }
                     if !self.show_compiled {
-                        if let Some(int) = &self.interact {
-                            self.show_interact(ui, int.clone());
+                        if let Some(int) = self.interact.clone() {
+                            let stale = *int.code != self.session.tab().code;
+                            if stale {
+                                ui.horizontal(|ui| {
+                                    ui.label(
+                                        egui::RichText::new(
+                                            "The code has changed since this was run.",
+                                        )
+                                        .weak(),
+                                    );
+                                    if ui.button("Re-run").clicked() {
+                                        self.rerun();
+                                    }
+                                });
+                            }
+                            ui.scope(|ui| {
+                                if stale {
+                                    ui.multiply_opacity(0.5);
+                                }
+                                self.show_interact(ui, int, Vec::new());
+                            });
+                        }
+                        if let Some(chosen) = self.chosen.take() {
+                            self.choose(chosen);
                         }
                     }
                 });
@@ -437,8 +1255,85 @@ impl Playground {
         });
     }
 
-    fn show_interact(&mut self, ui: &mut egui::Ui, int: Interact) {
-        let handle = int.handle.lock().expect("lock failed");
+    /// The warnings about what was compiled last, including the definitions never used
+    /// by the one run last, if any was.
+    fn warnings(&self) -> Vec<Warning<Loc, Name>> {
+        let Some(Ok(compiled)) = &self.compiled else {
+            return Vec::new();
+        };
+        let run = (self.transcript.as_ref()).map(|transcript| transcript.definition.as_str());
+        compiled.warnings_running(run)
+    }
+
+    /// What `par check` and `par run` would have printed for the last compile and run.
+    fn console(&self) -> String {
+        let code = self.compiled_code.clone();
+        let mut out = String::new();
+        match &self.compiled {
+            None => {}
+            Some(Err(error)) => {
+                writeln!(out, "{}", error.display(code.clone())).expect("write failed")
+            }
+            Some(Ok(compiled)) => {
+                for report in lint::reports(&self.warnings(), code.clone()) {
+                    writeln!(out, "{:?}", report).expect("write failed");
+                }
+                if let Ok(checked) = &compiled.checked {
+                    for hole in &checked.holes {
+                        writeln!(out, "{:?}", hole.to_report(code.clone())).expect("write failed");
+                    }
+                }
+                match &compiled.checked {
+                    Ok(_) => writeln!(
+                        out,
+                        "{}",
+                        cli::checked_message(compiled.program.definitions.len())
+                    ),
+                    Err(error) => writeln!(out, "{}", Error::Type(error.clone()).display(code)),
+                }
+                .expect("write failed");
+            }
+        }
+        if let Some(time) = self.compile_time {
+            writeln!(out, "Compiled in {} ms.", time.as_millis()).expect("write failed");
+        }
+
+        if let Some(int) = &self.interact {
+            if let Some(transcript) = &self.transcript {
+                writeln!(out, "\nRunning `{}`:", transcript.definition).expect("write failed");
+            }
+            match cli::output(&int.code, &int.handle) {
+                Ok(output) | Err(output) => out.push_str(&output),
+            }
+        }
+        out
+    }
+
+    /// Makes a choice clicked in the interaction pane, noting it in the transcript along
+    /// with what the whole value looked like before it.
+    fn choose(&mut self, chosen: Chosen) {
+        if let (Some(int), Some(transcript)) = (&self.interact, &mut self.transcript) {
+            transcript.steps.push(Step {
+                seen: cli::observe(&int.code, &int.handle),
+                path: chosen.path,
+                choice: match &chosen.choice {
+                    Choice::Branch(branch) => branch.to_string(),
+                    Choice::String(text) => quote(text),
+                    Choice::Int(number) => number.to_string(),
+                },
+            });
+        }
+        match chosen.choice {
+            Choice::Branch(branch) => Handle::choose(chosen.handle, chosen.loc, branch),
+            Choice::String(text) => Handle::send_string(chosen.handle, chosen.loc, text),
+            Choice::Int(number) => Handle::send_int(chosen.handle, chosen.loc, number),
+        }
+    }
+
+    fn show_interact(&mut self, ui: &mut egui::Ui, int: Interact, path: Vec<usize>) {
+        // A panic while the handle was locked poisons it. The run is dropped as soon as
+        // its supervisor learns of the panic, so until then it's shown as it was left.
+        let handle = int.handle.lock().unwrap_or_else(PoisonError::into_inner);
 
         egui::Frame::default()
             .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
@@ -449,7 +1344,16 @@ impl Playground {
                     let mut to_the_side = Vec::new();
 
                     ui.vertical(|ui| {
-                        for event in handle.events() {
+                        let layers = (int.unfolded.lock().expect("lock failed"))
+                            .get(&path)
+                            .copied()
+                            .unwrap_or(FOLDED_LAYERS);
+                        let shown = (handle.events().iter().enumerate())
+                            .filter(|(_, event)| matches!(event, Event::Choose(_, _)))
+                            .nth(layers)
+                            .map_or(handle.events().len(), |(index, _)| index);
+
+                        for (index, event) in handle.events()[..shown].iter().enumerate() {
                             match event {
                                 Event::Send(_, argument) => {
                                     self.show_interact(
@@ -457,12 +1361,17 @@ impl Playground {
                                         Interact {
                                             code: Arc::clone(&int.code),
                                             handle: Arc::clone(&argument),
+                                            supervisor: Arc::clone(&int.supervisor),
+                                            run: int.run,
+                                            unfolded: Arc::clone(&int.unfolded),
+                                            typed: Arc::clone(&int.typed),
                                         },
+                                        [path.as_slice(), &[index]].concat(),
                                     );
                                 }
 
                                 Event::Receive(_, parameter) => {
-                                    to_the_side.push(Arc::clone(&parameter))
+                                    to_the_side.push((index, Arc::clone(&parameter)))
                                 }
 
                                 Event::Choose(_, chosen) => {
@@ -502,6 +1411,28 @@ impl Playground {
                                         ui.label(egui::RichText::new("continue").italics().code());
                                     });
                                 }
+
+                                Event::String(_, text) => {
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(quote(text)).code());
+                                    });
+                                }
+
+                                Event::Int(_, number) => {
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(number.to_string()).code());
+                                    });
+                                }
+                            }
+                        }
+
+                        if shown < handle.events().len() {
+                            let unfold = ui
+                                .button(egui::RichText::new("⋯").strong().code())
+                                .on_hover_text("Unfold one more layer of this value");
+                            if unfold.clicked() {
+                                (int.unfolded.lock().expect("lock failed"))
+                                    .insert(path.clone(), layers + 1);
                             }
                         }
 
@@ -529,15 +1460,68 @@ impl Playground {
                                                 )
                                                 .clicked()
                                             {
-                                                Handle::choose(
-                                                    Arc::clone(&int.handle),
-                                                    loc.clone(),
-                                                    choice.clone(),
-                                                );
+                                                self.chosen = Some(Chosen {
+                                                    handle: Arc::clone(&int.handle),
+                                                    loc: loc.clone(),
+                                                    choice: Choice::Branch(choice.clone()),
+                                                    path: path.clone(),
+                                                });
                                             }
                                         }
                                     });
                                 }
+                                Ok(Request::String(loc)) => {
+                                    drop(handle);
+                                    let mut typed = int.typed.lock().expect("lock failed");
+                                    let text = typed.entry(path.clone()).or_default();
+                                    let input = ui.add(
+                                        egui::TextEdit::singleline(text)
+                                            .code_editor()
+                                            .hint_text("a string")
+                                            .desired_width(160.0),
+                                    );
+                                    let entered = input.lost_focus()
+                                        && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    if ui.button(egui::RichText::new("Send").strong()).clicked()
+                                        || entered
+                                    {
+                                        self.chosen = Some(Chosen {
+                                            handle: Arc::clone(&int.handle),
+                                            loc,
+                                            choice: Choice::String(Arc::from(text.as_str())),
+                                            path: path.clone(),
+                                        });
+                                    }
+                                }
+                                Ok(Request::Int(loc, asked)) => {
+                                    drop(handle);
+                                    let mut typed = int.typed.lock().expect("lock failed");
+                                    let text = typed.entry(path.clone()).or_default();
+                                    let input = ui.add(
+                                        egui::TextEdit::singleline(text)
+                                            .code_editor()
+                                            .hint_text(format!("a {}", asked))
+                                            .desired_width(160.0),
+                                    );
+                                    let number = (text.trim().parse::<i128>().ok())
+                                        .filter(|number| asked.contains(*number));
+                                    let entered = input.lost_focus()
+                                        && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                                    let send = ui.add_enabled(
+                                        number.is_some(),
+                                        egui::Button::new(egui::RichText::new("Send").strong()),
+                                    );
+                                    if let Some(number) = number {
+                                        if send.clicked() || entered {
+                                            self.chosen = Some(Chosen {
+                                                handle: Arc::clone(&int.handle),
+                                                loc,
+                                                choice: Choice::Int(number),
+                                                path: path.clone(),
+                                            });
+                                        }
+                                    }
+                                }
                                 Err(error) => {
                                     ui.label(
                                         egui::RichText::new(
@@ -551,13 +1535,18 @@ impl Playground {
                         }
                     });
 
-                    for side in to_the_side {
+                    for (index, side) in to_the_side {
                         self.show_interact(
                             ui,
                             Interact {
                                 code: Arc::clone(&int.code),
                                 handle: side,
+                                supervisor: Arc::clone(&int.supervisor),
+                                run: int.run,
+                                unfolded: Arc::clone(&int.unfolded),
+                                typed: Arc::clone(&int.typed),
                             },
+                            [path.as_slice(), &[index]].concat(),
                         );
                     }
                 });
@@ -565,208 +1554,6 @@ impl Playground {
     }
 }
 
-/// Create a `LabeledSpan` without a label at `loc`
-pub fn labels_from_loc<'s>(code: &'s str, loc: &Loc) -> Vec<LabeledSpan> {
-    match loc {
-        Loc::Code { line, column } => vec![LabeledSpan::new_with_span(
-            None,
-            SourceOffset::from_location(&code, *line, *column),
-        )],
-        Loc::External => vec![],
-    }
-}
-pub fn span_from_loc<'s>(code: &'s str, loc: &Loc) -> Option<SourceSpan> {
-    match loc {
-        Loc::Code { line, column } => {
-            Some(SourceOffset::from_location(&code, *line, *column).into())
-        }
-        Loc::External => None,
-    }
-}
-
-#[derive(Debug, miette::Diagnostic)]
-struct RuntimeError {
-    #[label]
-    span: Option<SourceSpan>,
-    #[label(collection)]
-    others: Vec<LabeledSpan>,
-    #[related]
-    related: Vec<miette::ErrReport>,
-    message: String,
-}
-impl core::fmt::Display for RuntimeError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        format!("Runtime Error: {}.", self.message).fmt(f)
-    }
-}
-impl core::error::Error for RuntimeError {}
-
-impl Error {
-    pub fn display(&self, code: Arc<str>) -> String {
-        match self {
-            Self::Parse(error) => {
-                // Show syntax error with miette's formatting
-                format!(
-                    "{:?}",
-                    miette::Report::from(error.to_owned()).with_source_code(code)
-                )
-            }
-
-            Self::Compile(CompileError::MustEndProcess(loc)) => {
-                let labels = labels_from_loc(&code, loc);
-                let code = if labels.is_empty() {
-                    "<UI>".into()
-                } else {
-                    code
-                };
-                let error = miette::miette! {
-                    labels = labels,
-                    "This process must end."
-                }
-                .with_source_code(code);
-                format!("{error:?}")
-            }
-
-            Self::Type(error) => format!("{:?}", error.into_report(code)),
-
-            Self::Runtime(error) => format!(
-                "{:?}",
-                miette::Report::from(Self::display_runtime_error(&code, error))
-            ),
-        }
-    }
-
-    fn display_runtime_error(
-        code: &str,
-        error: &runtime::Error<Loc, Internal<Name>>,
-    ) -> RuntimeError {
-        use runtime::Error::*;
-        match error {
-            NameNotDefined(loc, name) => RuntimeError {
-                span: span_from_loc(code, loc),
-                related: Vec::new(),
-                others: Vec::new(),
-                message: format!("`{}` is not defined.", name),
-            },
-            ShadowedObligation(loc, name) => RuntimeError {
-                span: span_from_loc(code, loc),
-                related: Vec::new(),
-                others: Vec::new(),
-                message: format!("Cannot re-assign `{}` before handling it.", name),
-            },
-            UnfulfilledObligations(loc, names) => RuntimeError {
-                span: span_from_loc(code, loc),
-                related: Vec::new(),
-                others: Vec::new(),
-                message: format!(
-                    "Cannot end this process before handling {}.",
-                    names
-                        .iter()
-                        .enumerate()
-                        .map(|(i, name)| if i == 0 {
-                            format!("`{}`", name)
-                        } else {
-                            format!(", `{}`", name)
-                        })
-                        .collect::<String>()
-                ),
-            },
-            IncompatibleOperations(op1, op2) => RuntimeError {
-                span: None,
-                related: Vec::new(),
-                others: [
-                    Self::display_operation(code, op1),
-                    Self::display_operation(code, op2),
-                ]
-                .into_iter()
-                .flatten()
-                .collect(),
-                message: "These operations are incompatible.".to_owned(),
-            },
-            NoSuchLoopPoint(loc, _) => RuntimeError {
-                span: span_from_loc(code, loc),
-                others: Vec::new(),
-                related: Vec::new(),
-                message: "There is no matching loop point in scope.".to_owned(),
-            },
-            Multiple(error1, error2) => RuntimeError {
-                span: None,
-                others: Vec::new(),
-                related: vec![
-                    miette::Report::from(Self::display_runtime_error(code, error1)),
-                    miette::Report::from(Self::display_runtime_error(code, error2)),
-                ],
-                message: "multiple errors".to_owned(),
-            },
-        }
-    }
-
-    fn display_operation(code: &str, op: &Operation<Loc, Internal<Name>>) -> Vec<LabeledSpan> {
-        match op {
-            Operation::Unknown(loc) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some("Unknown operation.".to_owned()));
-                    x
-                })
-                .collect(),
-            Operation::Send(loc) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some("This side is sending a value.".to_owned()));
-                    x
-                })
-                .collect(),
-            Operation::Receive(loc) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some("This side is receiving a value.".to_owned()));
-                    x
-                })
-                .collect(),
-            Operation::Choose(loc, chosen) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some(format!("This side is choosing `{}`.", chosen)));
-                    x
-                })
-                .collect(),
-            Operation::Match(loc, choices) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some(format!(
-                        "This side is offering either of {}.",
-                        choices
-                            .iter()
-                            .enumerate()
-                            .map(|(i, name)| if i == 0 {
-                                format!("`{}`", name)
-                            } else {
-                                format!(", `{}`", name)
-                            })
-                            .collect::<String>(),
-                    )));
-                    x
-                })
-                .collect(),
-            Operation::Break(loc) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some("This side is breaking.".to_owned()));
-                    x
-                })
-                .collect(),
-            Operation::Continue(loc) => labels_from_loc(code, loc)
-                .into_iter()
-                .map(|mut x| {
-                    x.set_label(Some("This side is continuing.".to_owned()));
-                    x
-                })
-                .collect(),
-        }
-    }
-}
-
 fn par_syntax() -> Syntax {
     Syntax {
         language: "Par",
@@ -793,12 +1580,134 @@ fn par_syntax() -> Syntax {
             "recursive",
             "iterative",
             "self",
+            "if",
+            "then",
+            "else",
         ]),
-        types: BTreeSet::from([]),
+        types: BTreeSet::from(["String", "Int", "Nat"]),
         special: BTreeSet::from(["<>"]),
     }
 }
 
+/// Shows each step of a type, with what it takes apart folded under it. The first two
+/// levels start unfolded. `path` keeps each step's folding apart from the others'.
+fn show_outline(ui: &mut egui::Ui, nodes: &[outline::Node], path: &mut Vec<String>) {
+    for (index, node) in nodes.iter().enumerate() {
+        let label = egui::RichText::new(&node.label).code();
+        if node.children.is_empty() {
+            ui.label(label);
+            continue;
+        }
+        path.push(format!("{}{}", index, node.label));
+        egui::CollapsingHeader::new(label)
+            .id_salt(&*path)
+            .default_open(path.len() <= 3)
+            .show(ui, |ui| show_outline(ui, &node.children, path));
+        path.pop();
+    }
+}
+
+fn save_trace(log: &TraceLog) {
+    let Ok(json) = serde_json::to_string_pretty(log) else {
+        return;
+    };
+    if let Some(path) = rfd::FileDialog::new()
+        .set_can_create_directories(true)
+        .add_filter("Trace", &["json"])
+        .save_file()
+    {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// Shows the little Markdown that lessons use: `#` headings, paragraphs, fenced code,
+/// and `**strong**`, `_emphasized_`, and `` `code` `` spans.
+fn show_markdown(ui: &mut egui::Ui, markdown: &str) {
+    let mut paragraph = String::new();
+    let mut code: Option<String> = None;
+
+    for line in markdown.lines().chain([""]) {
+        if let Some(block) = &mut code {
+            if line.trim() == "```" {
+                ui.label(egui::RichText::new(block.trim_end()).code());
+                code = None;
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+            continue;
+        }
+
+        let line = line.trim();
+        let heading = line.strip_prefix("# ");
+        if (line.is_empty() || line.starts_with("```") || heading.is_some())
+            && !paragraph.is_empty()
+        {
+            show_inline_markdown(ui, &paragraph);
+            paragraph.clear();
+        }
+        if line.starts_with("```") {
+            code = Some(String::new());
+        } else if let Some(heading) = heading {
+            ui.label(egui::RichText::new(heading).heading());
+        } else if !line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(line);
+        }
+    }
+}
+
+fn show_inline_markdown(ui: &mut egui::Ui, text: &str) {
+    let mut job = egui::text::LayoutJob::default();
+    let (mut strong, mut emphasized, mut code) = (false, false, false);
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let marker = if code {
+            &['`'][..]
+        } else {
+            &['*', '_', '`'][..]
+        };
+        let end = rest.find(marker).unwrap_or(rest.len());
+        if end > 0 {
+            let mut segment = egui::RichText::new(&rest[..end]);
+            if strong {
+                segment = segment.strong();
+            }
+            if emphasized {
+                segment = segment.italics();
+            }
+            if code {
+                segment = segment.code();
+            }
+            segment.append_to(
+                &mut job,
+                ui.style(),
+                egui::FontSelection::Default,
+                egui::Align::Center,
+            );
+        }
+        rest = &rest[end..];
+        if let Some(after) = rest.strip_prefix("**") {
+            strong = !strong;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('_') {
+            emphasized = !emphasized;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('`') {
+            code = !code;
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('*') {
+            emphasized = !emphasized;
+            rest = after;
+        }
+    }
+
+    ui.add(egui::Label::new(job).wrap());
+}
+
 fn fix_dark_theme(mut theme: ColorTheme) -> ColorTheme {
     theme.bg = "#1F1F1F";
     theme.functions = theme.literals;
@@ -823,4 +1732,8 @@ fn blue() -> egui::Color32 {
     egui::Color32::from_hex("#118ab2").unwrap()
 }
 
+fn yellow() -> egui::Color32 {
+    egui::Color32::from_hex("#e0a526").unwrap()
+}
+
 static DEFAULT_CODE: &str = include_str!("../examples/sample.par");
@@ -0,0 +1,155 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// The tabs open in the playground. It's saved as it changes, and the playground
+/// starts where it left off.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub tabs: Vec<Tab>,
+    /// Index of the tab being edited.
+    pub current: usize,
+    /// The sessions of the other windows open, in the order they were opened. Only the
+    /// root window's session has any.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<Session>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tab {
+    /// The file the tab is saved to, if it has been opened from or saved to one.
+    pub file_path: Option<PathBuf>,
+    pub code: String,
+    /// The code as it was last opened or saved, to tell whether there are unsaved changes.
+    pub saved: String,
+}
+
+impl Tab {
+    pub fn new(file_path: Option<PathBuf>, code: String) -> Self {
+        Self {
+            file_path,
+            saved: code.clone(),
+            code,
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.code != self.saved
+    }
+
+    /// The file name, or `untitled` for a tab that has never been saved.
+    pub fn title(&self) -> String {
+        (self.file_path.as_ref())
+            .and_then(|path| path.file_name())
+            .map_or_else(
+                || "untitled".to_owned(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+    }
+}
+
+impl Session {
+    pub fn new(code: String) -> Self {
+        Self {
+            tabs: vec![Tab::new(None, code)],
+            current: 0,
+            windows: Vec::new(),
+        }
+    }
+
+    /// `session.json` next to the user's configuration.
+    fn path() -> Option<PathBuf> {
+        config::directory().map(|dir| dir.join("session.json"))
+    }
+
+    /// The session saved last time, if there is one and it can be read.
+    pub fn load() -> Option<Self> {
+        Self::parse(&fs::read_to_string(Self::path()?).ok()?)
+    }
+
+    fn parse(json: &str) -> Option<Self> {
+        let session: Self = serde_json::from_str(json).ok()?;
+        session.is_valid().then_some(session)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.current < self.tabs.len() && self.windows.iter().all(Self::is_valid)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Self::path().ok_or(io::ErrorKind::NotFound)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.current]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current]
+    }
+
+    /// Adds a tab after the others and switches to it.
+    pub fn open(&mut self, tab: Tab) {
+        self.tabs.push(tab);
+        self.current = self.tabs.len() - 1;
+    }
+
+    /// Closes a tab, switching to its neighbour if it was the current one. The last
+    /// tab is never closed, only emptied.
+    pub fn close(&mut self, index: usize) {
+        if self.tabs.len() == 1 {
+            self.tabs[0] = Tab::new(None, String::new());
+            return;
+        }
+        self.tabs.remove(index);
+        if self.current > index || self.current == self.tabs.len() {
+            self.current -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_session_tabs() {
+        let mut session = Session::new("def a = !".to_owned());
+        assert!(!session.tab().is_modified());
+        session.tab_mut().code.push_str("\ndef b = !");
+        assert!(session.tab().is_modified());
+
+        session.open(Tab::new(Some(PathBuf::from("dir/b.par")), String::new()));
+        session.open(Tab::new(None, String::new()));
+        assert_eq!(session.current, 2);
+        assert_eq!(session.tabs[1].title(), "b.par");
+        assert_eq!(session.tab().title(), "untitled");
+
+        session.close(0);
+        assert_eq!(session.current, 1);
+        session.close(1);
+        assert_eq!(session.current, 0);
+        session.close(0);
+        assert_eq!(session.tabs.len(), 1);
+        assert_eq!(session.tab().code, "");
+
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(Session::parse(&json), Some(session));
+        assert_eq!(Session::parse(r#"{"tabs": [], "current": 0}"#), None);
+
+        let mut root = Session::new("def a = !".to_owned());
+        root.windows.push(Session::new("def b = !".to_owned()));
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(Session::parse(&json), Some(root.clone()));
+        root.windows[0].current = 1;
+        let json = serde_json::to_string(&root).unwrap();
+        assert_eq!(Session::parse(&json), None);
+        assert_eq!(Session::parse("{"), None);
+    }
+}
@@ -1,15 +1,346 @@
-use std::future::IntoFuture;
+use std::{
+    any::Any,
+    future::{Future, IntoFuture},
+    panic::AssertUnwindSafe,
+    pin::pin,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
 
-use futures::task::Spawn;
+use futures::{
+    channel::oneshot,
+    future::{self, Shared},
+    task::Spawn,
+    FutureExt,
+};
+use tokio::runtime::Runtime;
 
-pub struct TokioSpawn;
+use crate::events::{EventBus, Progress, RuntimeEvent};
+
+static POOL: OnceLock<Runtime> = OnceLock::new();
+
+/// How many runs have been started, to number each one.
+static RUNS: AtomicU64 = AtomicU64::new(0);
+
+/// Starts the runtime processes are run on, with `threads` worker threads, or one per
+/// core if not given. It is kept apart from the one the command line and the playground
+/// run on, so a long computation never keeps them from responding.
+///
+/// Only the first call, explicit or through [`pool`], decides the number of threads.
+pub fn start_pool(threads: Option<usize>) -> &'static Runtime {
+    POOL.get_or_init(|| {
+        let mut runtime = tokio::runtime::Builder::new_multi_thread();
+        if let Some(threads) = threads {
+            runtime.worker_threads(threads);
+        }
+        runtime
+            .thread_name("par-compute")
+            .enable_all()
+            .build()
+            .expect("failed to start the compute pool")
+    })
+}
+
+pub fn pool() -> &'static Runtime {
+    start_pool(None)
+}
+
+/// Runs `f` on the compute pool's threads for blocking work, and waits for it without
+/// holding up the caller's runtime.
+pub async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    pool()
+        .spawn_blocking(f)
+        .await
+        .expect("blocking computation panicked")
+}
+
+/// Spawns the processes of one run onto the compute pool, reporting each one as
+/// [`Progress`]. They're all watched over by one [`Supervisor`], and what they do is
+/// counted by one [`Activity`].
+pub struct TokioSpawn {
+    events: EventBus,
+    run: u64,
+    spawned: AtomicUsize,
+    supervisor: Arc<Supervisor>,
+    activity: Arc<Activity>,
+}
+
+impl TokioSpawn {
+    pub fn new(events: EventBus) -> Self {
+        Self {
+            events,
+            run: RUNS.fetch_add(1, Ordering::Relaxed) + 1,
+            spawned: AtomicUsize::new(0),
+            supervisor: Arc::new(Supervisor::new()),
+            activity: Arc::new(Activity::default()),
+        }
+    }
+
+    /// The number of this run, which its [`Progress`] and panic are reported with.
+    pub fn run(&self) -> u64 {
+        self.run
+    }
+
+    pub fn supervisor(&self) -> Arc<Supervisor> {
+        Arc::clone(&self.supervisor)
+    }
+
+    pub fn activity(&self) -> Arc<Activity> {
+        Arc::clone(&self.activity)
+    }
+}
+
+/// Counts what the processes of one run do, to tell a run that is only slow from one
+/// where every process is waiting on another and nothing can happen anymore.
+#[derive(Default)]
+pub struct Activity {
+    /// Processes spawned and polls finished, so far.
+    steps: AtomicUsize,
+    /// Processes being polled right now.
+    running: AtomicUsize,
+}
+
+impl Activity {
+    /// Where the run is at now, to be passed to [`Activity::idle_since`] later.
+    pub fn mark(&self) -> usize {
+        self.steps.load(Ordering::SeqCst)
+    }
+
+    /// Whether no process has been spawned or run since `mark`, and none is running.
+    pub fn idle_since(&self, mark: usize) -> bool {
+        self.running.load(Ordering::SeqCst) == 0 && self.steps.load(Ordering::SeqCst) == mark
+    }
+
+    async fn track(&self, process: impl Future<Output = ()>) {
+        /// Counts a poll as finished even when it panics.
+        struct Polling<'a>(&'a Activity);
+
+        impl Drop for Polling<'_> {
+            fn drop(&mut self) {
+                self.0.steps.fetch_add(1, Ordering::SeqCst);
+                self.0.running.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut process = pin!(process);
+        future::poll_fn(|cx| {
+            self.running.fetch_add(1, Ordering::SeqCst);
+            let _polling = Polling(self);
+            process.as_mut().poll(cx)
+        })
+        .await
+    }
+}
+
+/// Watches over the processes of one run. When one of them panics, the others are
+/// dropped along with the channels between them, instead of being left waiting on it
+/// forever, and anything spawned after is dropped right away. What the panic said is
+/// kept for whoever started the run to show.
+pub struct Supervisor {
+    panic: Mutex<Option<String>>,
+    stop: Mutex<Option<oneshot::Sender<()>>>,
+    stopped: Shared<oneshot::Receiver<()>>,
+}
+
+impl Supervisor {
+    fn new() -> Self {
+        let (stop, stopped) = oneshot::channel();
+        Self {
+            panic: Mutex::new(None),
+            stop: Mutex::new(Some(stop)),
+            stopped: stopped.shared(),
+        }
+    }
+
+    /// What the first process of the run to panic said, if one has.
+    pub fn panic(&self) -> Option<String> {
+        self.panic.lock().expect("lock failed").clone()
+    }
+
+    fn stop(&self, panic: Box<dyn Any + Send>) {
+        let message = (panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string()))
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "a process panicked".to_owned());
+        self.panic
+            .lock()
+            .expect("lock failed")
+            .get_or_insert(message);
+        if let Some(stop) = self.stop.lock().expect("lock failed").take() {
+            let _ = stop.send(());
+        }
+    }
+
+    /// Runs `process` until it's done, it panics, or another process of the run does.
+    /// Returns whether it panicked.
+    async fn supervise(&self, process: impl Future<Output = ()>) -> bool {
+        let process = AssertUnwindSafe(process).catch_unwind();
+        match future::select(pin!(process), self.stopped.clone()).await {
+            future::Either::Left((Err(panic), _)) => {
+                self.stop(panic);
+                true
+            }
+            _ => false,
+        }
+    }
+}
 
 impl Spawn for TokioSpawn {
     fn spawn_obj(
         &self,
         future: futures::task::FutureObj<'static, ()>,
     ) -> Result<(), futures::task::SpawnError> {
-        drop(tokio::task::spawn(future.into_future()));
+        if self.supervisor.panic().is_some() {
+            return Ok(());
+        }
+        let processes = self.spawned.fetch_add(1, Ordering::Relaxed) + 1;
+        self.activity.steps.fetch_add(1, Ordering::SeqCst);
+        let run = self.run;
+        self.events.report(Progress { run, processes });
+        let supervisor = Arc::clone(&self.supervisor);
+        let activity = Arc::clone(&self.activity);
+        let events = self.events.clone();
+        drop(pool().spawn(async move {
+            let process = activity.track(future.into_future());
+            if supervisor.supervise(process).await {
+                events.publish(RuntimeEvent::Panicked { run });
+            }
+        }));
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::{task::SpawnExt, StreamExt};
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn panic_stops_the_run() {
+        let bus = EventBus::default();
+        let mut events = bus.subscribe();
+        let spawner = TokioSpawn::new(bus);
+        let supervisor = spawner.supervisor();
+
+        // A process waiting on another one that never comes.
+        let (never, waiting) = oneshot::channel::<()>();
+        let (dropped, dropped_rx) = oneshot::channel::<()>();
+        spawner
+            .spawn(async move {
+                let _dropped = dropped;
+                let _ = waiting.await;
+            })
+            .expect("spawn failed");
+        spawner
+            .spawn(async { panic!("injected") })
+            .expect("spawn failed");
+
+        // Dropping the waiting process drops the sender it was holding.
+        tokio::time::timeout(Duration::from_secs(5), dropped_rx)
+            .await
+            .expect("the waiting process should be dropped")
+            .expect_err("nothing is ever sent");
+        assert_eq!(supervisor.panic(), Some("injected".to_owned()));
+        drop(never);
+
+        let panicked = async {
+            while let Some(event) = events.next().await {
+                if let RuntimeEvent::Panicked { run } = event {
+                    assert_eq!(run, spawner.run());
+                    return;
+                }
+            }
+            panic!("no panic was reported");
+        };
+        tokio::time::timeout(Duration::from_secs(5), panicked)
+            .await
+            .expect("the panic should be reported");
+
+        // Anything spawned afterwards is dropped without running.
+        let (ran, ran_rx) = oneshot::channel();
+        spawner
+            .spawn(async move {
+                let _ = ran.send(());
+            })
+            .expect("spawn failed");
+        assert!(ran_rx.await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn formatted_panics_are_kept() {
+        let spawner = TokioSpawn::new(EventBus::default());
+        let supervisor = spawner.supervisor();
+        spawner
+            .spawn(async { panic!("injected {}", 2) })
+            .expect("spawn failed");
+        while supervisor.panic().is_none() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(supervisor.panic(), Some("injected 2".to_owned()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_without_a_panic_are_left_alone() {
+        let spawner = TokioSpawn::new(EventBus::default());
+        let (done, done_rx) = oneshot::channel();
+        spawner
+            .spawn(async move {
+                let _ = done.send(());
+            })
+            .expect("spawn failed");
+        assert!(done_rx.await.is_ok());
+        assert_eq!(spawner.supervisor().panic(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn progress_is_kept_apart_per_run() {
+        let bus = EventBus::default();
+        let progress = bus.progress();
+        let first = TokioSpawn::new(bus.clone());
+        let second = TokioSpawn::new(bus);
+        assert_ne!(first.run(), second.run());
+        for _ in 0..3 {
+            first.spawn(async {}).expect("spawn failed");
+        }
+        second.spawn(async {}).expect("spawn failed");
+        assert_eq!(
+            *progress.borrow(),
+            Progress {
+                run: second.run(),
+                processes: 1
+            }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn waiting_processes_are_idle() {
+        let spawner = TokioSpawn::new(EventBus::default());
+        let activity = spawner.activity();
+        let (started, started_rx) = oneshot::channel();
+        let (wake, waiting) = oneshot::channel::<()>();
+        spawner
+            .spawn(async move {
+                let _ = started.send(());
+                let _ = waiting.await;
+            })
+            .expect("spawn failed");
+        started_rx.await.expect("the process should start");
+
+        let mut mark = activity.mark();
+        while !activity.idle_since(mark) {
+            mark = activity.mark();
+            tokio::task::yield_now().await;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(activity.idle_since(mark));
+
+        wake.send(()).expect("the process should be waiting");
+        while activity.idle_since(mark) {
+            tokio::task::yield_now().await;
+        }
+    }
+}
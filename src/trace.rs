@@ -0,0 +1,95 @@
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    interact::{Action, Traced},
+    par::{
+        language::Internal,
+        lexer::quote,
+        parse::{Loc, Name},
+    },
+};
+
+/// What was done on the channels of a run, as the playground saves it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceLog {
+    /// The definition that was run.
+    pub definition: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Microseconds after the run started.
+    pub at_us: u64,
+    /// Which channel: starting from the value that was run, the position among the
+    /// events of each sent or received value to step into, as in a transcript.
+    pub path: Vec<usize>,
+    /// `send`, `receive`, `choose`, `either`, `break`, `continue`, `string`, or `int`.
+    pub action: String,
+    /// The branch chosen, the string sent, quoted, or the number sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Where in the code it was done, if anywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl TraceLog {
+    pub fn new(definition: String, trace: &[Traced<Loc, Internal<Name>>]) -> Self {
+        Self {
+            definition,
+            entries: trace.iter().map(Entry::from).collect(),
+        }
+    }
+}
+
+impl From<&Traced<Loc, Internal<Name>>> for Entry {
+    fn from(traced: &Traced<Loc, Internal<Name>>) -> Self {
+        let (action, label) = match &traced.action {
+            Action::Send => ("send", None),
+            Action::Receive => ("receive", None),
+            Action::Choose(chosen) => ("choose", Some(chosen.to_string())),
+            Action::Either(chosen) => ("either", Some(chosen.to_string())),
+            Action::Break => ("break", None),
+            Action::Continue => ("continue", None),
+            Action::String(text) => ("string", Some(quote(text))),
+            Action::Int(number) => ("int", Some(number.to_string())),
+        };
+        let (line, column) = match traced.loc {
+            Loc::Code { line, column, .. } => (Some(line), Some(column)),
+            Loc::External => (None, None),
+        };
+        Self {
+            at_us: traced.at.as_micros() as u64,
+            path: traced.path.clone(),
+            action: action.to_owned(),
+            label,
+            line,
+            column,
+        }
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path: Vec<String> = self.path.iter().map(ToString::to_string).collect();
+        write!(
+            f,
+            "{:>10.3} ms  [{}]  {}",
+            self.at_us as f64 / 1000.0,
+            path.join("."),
+            self.action
+        )?;
+        if let Some(label) = &self.label {
+            write!(f, " {}", label)?;
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "  at {}:{}", line, column)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// A recorded session with the playground, which `par interact-replay` can run again
+/// without it, checking that everything looks the same at every step.
+///
+/// What was seen is written the way `par run` shows a value that isn't plain data.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// The definition that was run.
+    pub definition: String,
+    /// What the definition was applied to, in the value syntax, if it was run from a
+    /// preset.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<String>,
+    pub steps: Vec<Step>,
+    /// What was seen after the last step.
+    pub result: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    /// What was seen before making the choice.
+    pub seen: String,
+    /// Where the choice was made: starting from the value that was run, the position
+    /// among the events of each sent or received value to step into.
+    pub path: Vec<usize>,
+    /// The branch chosen, or the string typed in, quoted, where a string was asked for.
+    pub choice: String,
+}
+
+impl Transcript {
+    #[cfg(feature = "gui")]
+    pub fn new(definition: String, arguments: Vec<String>) -> Self {
+        Self {
+            definition,
+            arguments,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use futures::channel::oneshot;
+
+use crate::{
+    cli,
+    compiled::{Compiled, Error},
+    config::Verbosity,
+    par::frontend::Par,
+};
+
+/// One step of the playground's tutorial.
+///
+/// The text is Markdown, starting with a `# Title` line. Of its code blocks, the one
+/// fenced as `par starter` is loaded into the editor, and the one fenced as
+/// `par solution` is shown on request; the rest are part of the text.
+pub struct Lesson {
+    pub text: &'static str,
+    pub check: Check,
+}
+
+/// What a lesson's code has to do to pass.
+pub enum Check {
+    TypeChecks,
+    /// Applied to each list of arguments, `definition` must give the value after it,
+    /// written as `par run` would print it.
+    Evaluates {
+        definition: &'static str,
+        cases: &'static [(&'static [&'static str], &'static str)],
+    },
+}
+
+pub static LESSONS: &[Lesson] = &[
+    Lesson {
+        text: include_str!("../tutorial/01_choosing.md"),
+        check: Check::Evaluates {
+            definition: "favorite",
+            cases: &[(&[], ".green!")],
+        },
+    },
+    Lesson {
+        text: include_str!("../tutorial/02_functions.md"),
+        check: Check::Evaluates {
+            definition: "swap",
+            cases: &[(&["true"], "false"), (&["false"], "true")],
+        },
+    },
+    Lesson {
+        text: include_str!("../tutorial/03_linearity.md"),
+        check: Check::TypeChecks,
+    },
+    Lesson {
+        text: include_str!("../tutorial/04_lists.md"),
+        check: Check::Evaluates {
+            definition: "three",
+            cases: &[(&[".red", ".green", ".blue"], "[.red!, .green!, .blue!]")],
+        },
+    },
+];
+
+impl Lesson {
+    pub fn title(&self) -> &'static str {
+        self.text
+            .lines()
+            .next()
+            .and_then(|line| line.strip_prefix("# "))
+            .unwrap_or("Untitled")
+    }
+
+    /// The text without its title and the starter and solution code.
+    pub fn prose(&self) -> String {
+        let mut prose = String::new();
+        let mut skipping = false;
+        for line in self.text.lines().skip(1) {
+            match line.trim() {
+                "```par starter" | "```par solution" => skipping = true,
+                "```" if skipping => skipping = false,
+                _ if !skipping => {
+                    prose.push_str(line);
+                    prose.push('\n');
+                }
+                _ => {}
+            }
+        }
+        prose.trim().to_owned()
+    }
+
+    pub fn starter(&self) -> &'static str {
+        self.code("starter").unwrap_or("")
+    }
+
+    pub fn solution(&self) -> Option<&'static str> {
+        self.code("solution")
+    }
+
+    fn code(&self, tag: &str) -> Option<&'static str> {
+        let fence = format!("```par {}\n", tag);
+        let start = self.text.find(&fence)? + fence.len();
+        let length = self.text[start..].find("```")?;
+        Some(&self.text[start..start + length])
+    }
+}
+
+impl Check {
+    /// Compiles `code` and runs whatever the check asks for, explaining what's wrong
+    /// if it doesn't pass.
+    pub async fn run(&self, code: Arc<str>) -> Result<(), String> {
+        match self {
+            Self::TypeChecks => {
+                let compiled = stacker::grow(32 * 1024 * 1024, || {
+                    Compiled::from_source(&Par, &code).map_err(|error| error.display(code.clone()))
+                })?;
+                compiled
+                    .checked
+                    .map(|_| ())
+                    .map_err(|error| Error::Type(error).display(code))
+            }
+            Self::Evaluates { definition, cases } => {
+                for (arguments, expected) in cases.iter() {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| argument.to_string())
+                        .collect::<Vec<_>>();
+                    let result = cli::evaluate(
+                        &Par,
+                        code.clone(),
+                        definition,
+                        &arguments,
+                        Verbosity::Quiet,
+                        None,
+                    )
+                    .await?;
+                    if result.trim_end() != *expected {
+                        let call = match arguments.is_empty() {
+                            true => definition.to_string(),
+                            false => format!("{}({})", definition, arguments.join(", ")),
+                        };
+                        return Err(format!(
+                            "`{}` gave\n\n    {}\n\nbut it should give\n\n    {}",
+                            call,
+                            result.trim_end(),
+                            expected
+                        ));
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Where the learner is in the tutorial.
+#[derive(Default)]
+pub struct Tutorial {
+    pub lesson: usize,
+    pub completed: BTreeSet<usize>,
+    pub outcome: Option<Result<(), String>>,
+    pub show_solution: bool,
+    pending: Option<oneshot::Receiver<Result<(), String>>>,
+}
+
+impl Tutorial {
+    pub fn current(&self) -> &'static Lesson {
+        &LESSONS[self.lesson]
+    }
+
+    pub fn go_to(&mut self, lesson: usize) {
+        self.lesson = lesson.min(LESSONS.len() - 1);
+        self.outcome = None;
+        self.show_solution = false;
+        self.pending = None;
+    }
+
+    /// Starts checking `code` against the current lesson, calling `done` once the
+    /// outcome is in.
+    pub fn check(&mut self, code: Arc<str>, done: impl FnOnce() + Send + 'static) {
+        let (tx, rx) = oneshot::channel();
+        let check = &self.current().check;
+        tokio::spawn(async move {
+            let _ = tx.send(check.run(code).await);
+            done();
+        });
+        self.outcome = None;
+        self.pending = Some(rx);
+    }
+
+    pub fn is_checking(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Takes in the outcome of the last check, if it has arrived.
+    pub fn poll(&mut self) {
+        let Some(pending) = &mut self.pending else {
+            return;
+        };
+        match pending.try_recv() {
+            Ok(None) => return,
+            Ok(Some(outcome)) => {
+                if outcome.is_ok() {
+                    self.completed.insert(self.lesson);
+                }
+                self.outcome = Some(outcome);
+            }
+            Err(oneshot::Canceled) => {}
+        }
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_lessons() {
+        for lesson in LESSONS {
+            assert_ne!(lesson.title(), "Untitled");
+            assert!(!lesson.prose().contains("```par"), "{}", lesson.title());
+            let starter = Arc::from(lesson.starter());
+            let solution = Arc::from(lesson.solution().expect("every lesson has a solution"));
+            assert!(
+                lesson.check.run(starter).await.is_err(),
+                "the starter code of {:?} already passes",
+                lesson.title()
+            );
+            assert_eq!(
+                lesson.check.run(solution).await,
+                Ok(()),
+                "the solution of {:?} doesn't pass",
+                lesson.title()
+            );
+        }
+    }
+}